@@ -1,19 +1,33 @@
-use std::env::temp_dir;
-use std::path::PathBuf;
+use std::env::{self, temp_dir};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use alloy::hex;
 use alloy::primitives::utils::format_ether;
 use alloy::primitives::{Address, U256};
 use anyhow::{Context, Result};
-use clap::Parser;
+use clap::{Args, Parser, Subcommand};
 
-use crate::wallet::{WalletStatus, display_wallet_status, generate_wallet};
-use blacklight_contract_clients::BlacklightClient;
-use chain_args::{ChainArgs, ChainConfig};
+use crate::artifacts::{ArtifactManifest, DEFAULT_ARTIFACTS_URL};
+use crate::http::HttpClientConfig;
+use crate::runway::RunwayThresholds;
+use crate::verification::VerificationTimeouts;
+use crate::version::VERSION;
+use crate::wallet::{
+    DEFAULT_DERIVATION_PATH, WalletStatus, allocate_index, derive_wallet, display_wallet_status,
+    generate_mnemonic, generate_wallet,
+};
+use alloy::signers::local::PrivateKeySigner;
+use blacklight_contract_clients::{
+    BlacklightClient, ContractConfig, FeeConfig, OperatorMetadata, RetryPolicy, SignerConfig,
+};
+use chain_args::{ChainArgs, ChainConfig, FeeArgs, ReceiptArgs};
+use key_args::KeyArgs;
 use state_file::StateFile;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
-const STATE_FILE_NODE: &str = "blacklight_node.env";
+pub(crate) const STATE_FILE_NODE: &str = "blacklight_node.env";
+const KEYSTORE_FILE_NODE: &str = "blacklight_node.keystore.json";
 
 /// Minimum ETH balance required to continue operating
 /// Node will initiate shutdown if balance falls below this threshold
@@ -33,9 +47,23 @@ pub struct CliArgs {
     #[clap(flatten)]
     pub chain_args: ChainArgs,
 
-    /// Private key for contract interactions
-    #[arg(long, env = "PRIVATE_KEY")]
-    pub private_key: Option<String>,
+    #[clap(flatten)]
+    pub fees: FeeArgs,
+
+    #[clap(flatten)]
+    pub receipts: ReceiptArgs,
+
+    #[clap(flatten)]
+    pub keys: KeyArgs,
+
+    #[clap(flatten)]
+    pub mnemonic: MnemonicArgs,
+
+    #[clap(flatten)]
+    pub identities: IdentityArgs,
+
+    #[clap(flatten)]
+    pub metadata: MetadataArgs,
 
     /// The path where nilcc artifacts will be cached.
     #[clap(short, long, default_value = default_artifact_cache_path().into_os_string(), env = "ARTIFACT_CACHE")]
@@ -44,6 +72,637 @@ pub struct CliArgs {
     /// The path where AMD certificates will be cached.
     #[clap(short, long, default_value = default_cert_cache_path().into_os_string(), env = "CERT_CACHE")]
     pub cert_cache: PathBuf,
+
+    /// Base URL nilcc artifacts are downloaded from, e.g. a local mirror
+    #[arg(long, env = "ARTIFACTS_URL", default_value = DEFAULT_ARTIFACTS_URL)]
+    pub artifacts_url: String,
+
+    /// Manifest with the pinned SHA-256 hashes of nilcc artifacts, in sha256sum format. When
+    /// set, HTXs using a nilcc version the manifest doesn't pin are refused
+    #[arg(long, env = "ARTIFACT_MANIFEST")]
+    pub artifact_manifest: Option<PathBuf>,
+
+    /// Directory evidence bundles of Failure verdicts are written to
+    #[arg(long, env = "EVIDENCE_DIR")]
+    pub evidence_dir: Option<PathBuf>,
+
+    /// nilcc versions whose artifacts are downloaded at startup
+    #[arg(long, env = "PREFETCH_ARTIFACTS", value_delimiter = ',')]
+    pub prefetch_artifacts: Vec<String>,
+
+    #[clap(flatten)]
+    pub timeouts: TimeoutArgs,
+
+    #[clap(flatten)]
+    pub http: HttpArgs,
+
+    #[clap(flatten)]
+    pub caches: CacheArgs,
+
+    #[clap(flatten)]
+    pub health: HealthArgs,
+
+    #[clap(flatten)]
+    pub shadow: ShadowArgs,
+
+    #[clap(flatten)]
+    pub consensus: ConsensusArgs,
+
+    #[clap(flatten)]
+    pub shutdown: ShutdownArgs,
+
+    #[clap(flatten)]
+    pub jail: JailArgs,
+
+    #[clap(flatten)]
+    pub status: StatusArgs,
+
+    #[clap(flatten)]
+    pub metrics: MetricsArgs,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+impl CliArgs {
+    /// Load the artifact manifest, or an empty one if none is configured.
+    pub fn artifact_manifest(&self) -> Result<ArtifactManifest> {
+        match &self.artifact_manifest {
+            Some(path) => ArtifactManifest::load(path),
+            None => Ok(ArtifactManifest::default()),
+        }
+    }
+}
+
+/// Commands that run instead of the node
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Manage the nilcc artifact cache
+    #[command(subcommand)]
+    Artifacts(ArtifactsCommand),
+
+    /// Inspect evidence bundles of Failure verdicts
+    #[command(subcommand)]
+    Evidence(EvidenceCommand),
+
+    /// Record or replay the HTTP exchanges of verifying a single HTX
+    #[command(subcommand)]
+    Tape(TapeCommand),
+
+    /// Manage the encrypted keystore holding the node's private key
+    #[command(subcommand)]
+    Keystore(KeystoreCommand),
+
+    /// Generate a mnemonic or list the accounts derived from it
+    #[command(subcommand)]
+    Mnemonic(MnemonicCommand),
+
+    #[command(flatten)]
+    Staking(StakingCommand),
+
+    /// Move the operator and its stake to a new key, resuming a rotation in progress
+    RotateKey {
+        /// Store the new private key in plaintext in the state file when no keystore
+        /// passphrase is given
+        #[arg(long)]
+        allow_plaintext_key: bool,
+    },
+
+    /// Print the operator metadata document, to host it, and the URI the node registers
+    Metadata,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum StakingCommand {
+    /// Stake NIL to an operator, approving the staking contract to spend it first if needed
+    Stake {
+        /// Amount of NIL, e.g. 1000 or 0.5
+        amount: String,
+
+        /// Operator to stake to, this node by default
+        #[arg(long)]
+        operator: Option<Address>,
+    },
+
+    /// Request to unstake NIL from an operator, which starts its unbonding period
+    Unstake {
+        /// Amount of NIL, e.g. 1000 or 0.5
+        amount: String,
+
+        /// Operator to unstake from, this node by default
+        #[arg(long)]
+        operator: Option<Address>,
+    },
+
+    /// Withdraw the unbonded NIL whose release time has passed
+    Withdraw {
+        /// Operator the NIL was unstaked from, this node by default
+        #[arg(long)]
+        operator: Option<Address>,
+    },
+
+    /// Reactivate this node as an operator after a deactivation
+    Reactivate,
+
+    /// Deactivate this node as an operator
+    Deactivate,
+
+    /// Show an operator's stake, status and unbonding tranches
+    StakeStatus {
+        /// Operator to show, this node by default
+        #[arg(long)]
+        operator: Option<Address>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum KeystoreCommand {
+    /// Move the plaintext private key of the state file into an encrypted keystore
+    Migrate,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum MnemonicCommand {
+    /// Generate a new random mnemonic
+    New,
+
+    /// List the addresses derived from the configured mnemonic, e.g. to fund a fleet of nodes
+    Accounts {
+        /// Number of accounts to list, starting at MNEMONIC_BASE_INDEX
+        #[arg(long, default_value_t = 10)]
+        count: u32,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum TapeCommand {
+    /// Verify an HTX against the live services and record every response
+    Record {
+        /// File with the raw HTX JSON
+        #[arg(long)]
+        htx: PathBuf,
+
+        /// Path the tape is written to
+        #[arg(long)]
+        output: PathBuf,
+    },
+
+    /// Verify the HTX of a tape offline and compare with the recorded verdict
+    Replay {
+        /// Path of the tape
+        tape: PathBuf,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum EvidenceCommand {
+    /// Check a bundle's content hash and repeat its offline checks
+    Verify {
+        /// Path of the evidence bundle
+        path: PathBuf,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ArtifactsCommand {
+    /// List the cached nilcc versions
+    List,
+
+    /// Verify cached nilcc versions against the artifact manifest
+    Verify {
+        /// Versions to verify, all cached versions if none are given
+        versions: Vec<String>,
+    },
+
+    /// Remove cached nilcc versions that are corrupt or not listed in --keep
+    Prune {
+        /// Versions to keep; when empty only corrupt versions are removed
+        #[arg(long, value_delimiter = ',')]
+        keep: Vec<String>,
+
+        /// Only print what would be removed
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+/// Caches that avoid repeating work for HTXs seen before
+#[derive(Args, Debug)]
+pub struct CacheArgs {
+    /// Number of generated nilcc measurements to keep, 0 disables the cache
+    #[arg(long, env = "MEASUREMENT_CACHE_SIZE", default_value_t = 256)]
+    pub measurement_cache_size: usize,
+
+    /// Seconds a conclusive verdict is reused for an identical HTX, 0 disables the cache
+    #[arg(long, env = "VERDICT_CACHE_TTL_SECS", default_value_t = 300)]
+    pub verdict_cache_ttl_secs: u64,
+}
+
+/// Shadow mode: verify rounds without registering or submitting verdicts
+#[derive(Args, Debug, Clone)]
+pub struct ShadowArgs {
+    /// Verify assigned rounds and report agreement with the network instead of voting
+    #[arg(long, env = "SHADOW_MODE")]
+    pub shadow: bool,
+
+    /// In shadow mode, verify every round rather than only those assigned to this node
+    #[arg(long, env = "SHADOW_ALL_ROUNDS", requires = "shadow")]
+    pub shadow_all_rounds: bool,
+}
+
+/// Tracking of how our verdicts compare to the rest of the committee
+#[derive(Args, Debug, Clone)]
+pub struct ConsensusArgs {
+    /// Consecutive rounds finalized against our verdict before warning, 0 disables the warning
+    #[arg(long, env = "MINORITY_WARN_THRESHOLD", default_value_t = 3)]
+    pub minority_warn_threshold: u64,
+
+    /// Seconds between agreement reports, 0 disables periodic reports
+    #[arg(long, env = "AGREEMENT_REPORT_INTERVAL_SECS", default_value_t = 300)]
+    pub agreement_report_interval_secs: u64,
+
+    /// File the latest agreement report is written to
+    #[arg(long, env = "AGREEMENT_REPORT_PATH")]
+    pub agreement_report_path: Option<PathBuf>,
+}
+
+/// Graceful shutdown behavior
+#[derive(Args, Debug, Clone)]
+pub struct ShutdownArgs {
+    /// Seconds to wait for in-flight verifications on shutdown, also bounded by their round deadlines
+    #[arg(long, env = "DRAIN_TIMEOUT_SECS", default_value_t = 120)]
+    pub drain_timeout_secs: u64,
+
+    /// Keep the node active on shutdown, for quick restarts
+    #[arg(long, env = "SKIP_DEACTIVATION")]
+    pub skip_deactivation: bool,
+}
+
+/// Periodic status report and ETH runway alerts
+#[derive(Args, Debug, Clone)]
+pub struct StatusArgs {
+    /// Seconds between status reports and ETH runway alerts, 0 disables them. The minimum ETH
+    /// balance is checked after every submitted verdict regardless
+    #[arg(long, env = "STATUS_INTERVAL_SECS", default_value_t = 60)]
+    pub status_interval_secs: u64,
+
+    /// Hours of ETH runway, at the observed verdict costs, below which a low funds warning is raised
+    #[arg(long, env = "RUNWAY_WARN_HOURS", default_value_t = 7 * 24)]
+    pub runway_warn_hours: u64,
+
+    /// Hours of ETH runway below which low funds are reported as critical
+    #[arg(long, env = "RUNWAY_CRITICAL_HOURS", default_value_t = 24)]
+    pub runway_critical_hours: u64,
+}
+
+impl StatusArgs {
+    pub fn runway_thresholds(&self) -> RunwayThresholds {
+        RunwayThresholds {
+            warn: Duration::from_secs(self.runway_warn_hours * 3600),
+            critical: Duration::from_secs(self.runway_critical_hours * 3600),
+        }
+    }
+}
+
+/// Metadata describing the operator, registered with the staking contract
+#[derive(Args, Debug, Clone)]
+pub struct MetadataArgs {
+    /// Human readable name of the operator, the operator stays anonymous if unset
+    #[arg(long, env = "OPERATOR_NAME")]
+    pub operator_name: Option<String>,
+
+    /// How to reach the operator, e.g. an email address
+    #[arg(long, env = "OPERATOR_CONTACT")]
+    pub operator_contact: Option<String>,
+
+    /// Where the node runs, e.g. eu-west
+    #[arg(long, env = "OPERATOR_REGION")]
+    pub operator_region: Option<String>,
+
+    /// Public http(s) endpoint of the operator
+    #[arg(long, env = "OPERATOR_ENDPOINT")]
+    pub operator_endpoint: Option<String>,
+
+    /// URI of a hosted metadata document to register, instead of embedding the metadata in a
+    /// data: URI
+    #[arg(long, env = "OPERATOR_METADATA_URI")]
+    pub operator_metadata_uri: Option<String>,
+}
+
+impl MetadataArgs {
+    /// The operator metadata, if a name is configured
+    pub fn metadata(&self) -> Option<OperatorMetadata> {
+        Some(OperatorMetadata {
+            name: self.operator_name.clone()?,
+            contact: self.operator_contact.clone(),
+            region: self.operator_region.clone(),
+            version: Some(VERSION.to_string()),
+            endpoint: self.operator_endpoint.clone(),
+        })
+    }
+
+    /// The validated metadata URI to register, empty for an anonymous operator
+    pub async fn metadata_uri(&self) -> Result<String> {
+        if let Some(uri) = &self.operator_metadata_uri {
+            OperatorMetadata::resolve(uri)
+                .await
+                .with_context(|| format!("Invalid operator metadata at {uri}"))?
+                .context("OPERATOR_METADATA_URI must not be empty")?;
+            return Ok(uri.clone());
+        }
+        match self.metadata() {
+            Some(metadata) => {
+                metadata.validate().context("Invalid operator metadata")?;
+                metadata.to_data_uri()
+            }
+            None => Ok(String::new()),
+        }
+    }
+}
+
+/// Print the operator metadata document and the URI the node registers
+pub async fn print_metadata(args: &MetadataArgs) -> Result<()> {
+    let metadata = args
+        .metadata()
+        .context("No operator metadata configured, set OPERATOR_NAME")?;
+    metadata.validate().context("Invalid operator metadata")?;
+    println!("{}", metadata.to_json());
+    eprintln!("Registered URI: {}", args.metadata_uri().await?);
+    Ok(())
+}
+
+/// Export of node metrics
+#[derive(Args, Debug, Clone)]
+pub struct MetricsArgs {
+    /// The OTEL collector endpoint.
+    #[arg(long, env = "OTEL_ENDPOINT")]
+    pub otel_endpoint: Option<String>,
+
+    /// The OTEL export interval in seconds.
+    #[arg(long, env = "OTEL_EXPORT_INTERVAL_SECS", default_value_t = 15)]
+    pub otel_export_interval_secs: u64,
+
+    /// The OTEL export timeout in seconds.
+    #[arg(long, env = "OTEL_EXPORT_TIMEOUT_SECS", default_value_t = 30)]
+    pub otel_export_timeout_secs: u64,
+}
+
+impl MetricsArgs {
+    /// The OTEL export configuration, if exports are enabled
+    pub fn otel_config(&self) -> Option<OtelConfig> {
+        if env::var("OTEL_SDK_DISABLED").as_deref() == Ok("true") {
+            info!("OTEL export is disabled via environment variable");
+            return None;
+        }
+        let endpoint = self.otel_endpoint.clone()?;
+        Some(OtelConfig {
+            endpoint,
+            export_timeout: Duration::from_secs(self.otel_export_timeout_secs),
+            export_interval: Duration::from_secs(self.otel_export_interval_secs),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OtelConfig {
+    pub endpoint: String,
+    pub export_timeout: Duration,
+    pub export_interval: Duration,
+}
+
+/// Detection of jailing by the staking contract
+#[derive(Args, Debug, Clone)]
+pub struct JailArgs {
+    /// Seconds between checks of the operator's jail status, 0 disables the periodic check
+    #[arg(long, env = "JAIL_CHECK_INTERVAL_SECS", default_value_t = 60)]
+    pub jail_check_interval_secs: u64,
+}
+
+/// Probes of the external services verification depends on
+#[derive(Args, Debug)]
+pub struct HealthArgs {
+    /// Seconds between dependency health probes, 0 disables probing
+    #[arg(long, env = "HEALTH_PROBE_INTERVAL_SECS", default_value_t = 60)]
+    pub health_probe_interval_secs: u64,
+
+    /// AMD Key Distribution Service URL to probe
+    #[arg(long, env = "AMD_KDS_URL", default_value = "https://kdsintf.amd.com/")]
+    pub amd_kds_url: String,
+
+    /// Intel Provisioning Certification Service URL to probe
+    #[arg(
+        long,
+        env = "INTEL_PCS_URL",
+        default_value = "https://api.trustedservices.intel.com/"
+    )]
+    pub intel_pcs_url: String,
+}
+
+/// Outbound HTTP settings used when fetching verification inputs
+#[derive(Args, Debug)]
+pub struct HttpArgs {
+    /// Proxy URL for outbound HTTP requests
+    #[arg(long = "http-proxy", env = "HTTPS_PROXY")]
+    pub http_proxy: Option<String>,
+
+    /// PEM file with additional root certificates to trust (may be repeated)
+    #[arg(long = "extra-ca-certs", env = "EXTRA_CA_CERTS", value_delimiter = ',')]
+    pub extra_ca_certs: Vec<PathBuf>,
+
+    /// User agent sent with outbound HTTP requests
+    #[arg(long, env = "HTTP_USER_AGENT", default_value = concat!("blacklight-node/", env!("CARGO_PKG_VERSION")))]
+    pub http_user_agent: String,
+
+    /// Maximum number of requests in flight to a single host
+    #[arg(long, env = "HTTP_MAX_CONNECTIONS_PER_HOST", default_value_t = 8)]
+    pub http_max_connections_per_host: usize,
+
+    /// Timeout in seconds for establishing an HTTP connection
+    #[arg(long, env = "HTTP_CONNECT_TIMEOUT_SECS", default_value_t = 5)]
+    pub http_connect_timeout_secs: u64,
+
+    /// Seconds an idle HTTP connection is kept open for reuse
+    #[arg(long, env = "HTTP_IDLE_TIMEOUT_SECS", default_value_t = 90)]
+    pub http_idle_timeout_secs: u64,
+}
+
+impl HttpArgs {
+    pub fn client_config(&self) -> HttpClientConfig {
+        HttpClientConfig {
+            proxy: self.http_proxy.clone(),
+            ca_certs: self.extra_ca_certs.clone(),
+            user_agent: self.http_user_agent.clone(),
+            max_connections_per_host: self.http_max_connections_per_host,
+            connect_timeout: Duration::from_secs(self.http_connect_timeout_secs),
+            idle_timeout: Duration::from_secs(self.http_idle_timeout_secs),
+        }
+    }
+}
+
+/// Time limits for the stages of HTX verification
+#[derive(Args, Debug)]
+pub struct TimeoutArgs {
+    /// Timeout in seconds for fetching the attestation report of a workload
+    #[arg(long, env = "REPORT_FETCH_TIMEOUT_SECS", default_value_t = 15)]
+    pub report_fetch_timeout_secs: u64,
+
+    /// Timeout in seconds for downloading the nilcc artifacts of a version
+    #[arg(long, env = "ARTIFACT_DOWNLOAD_TIMEOUT_SECS", default_value_t = 120)]
+    pub artifact_download_timeout_secs: u64,
+
+    /// Timeout in seconds for generating the expected measurement
+    #[arg(long, env = "MEASUREMENT_TIMEOUT_SECS", default_value_t = 30)]
+    pub measurement_timeout_secs: u64,
+
+    /// Timeout in seconds for fetching AMD certificates and verifying the report
+    #[arg(long, env = "CERT_FETCH_TIMEOUT_SECS", default_value_t = 20)]
+    pub cert_fetch_timeout_secs: u64,
+
+    /// Timeout in seconds for fetching the builder measurement index
+    #[arg(long, env = "BUILDER_INDEX_TIMEOUT_SECS", default_value_t = 10)]
+    pub builder_index_timeout_secs: u64,
+
+    /// Timeout in seconds for fetching Intel collateral and verifying a Phala quote
+    #[arg(long, env = "QUOTE_COLLATERAL_TIMEOUT_SECS", default_value_t = 30)]
+    pub quote_collateral_timeout_secs: u64,
+
+    /// Seconds reserved before the round deadline to submit the verdict
+    #[arg(long, env = "SUBMISSION_MARGIN_SECS", default_value_t = 10)]
+    pub submission_margin_secs: u64,
+}
+
+impl TimeoutArgs {
+    pub fn verification_timeouts(&self) -> VerificationTimeouts {
+        VerificationTimeouts {
+            report_fetch: Duration::from_secs(self.report_fetch_timeout_secs),
+            artifact_download: Duration::from_secs(self.artifact_download_timeout_secs),
+            measurement_generation: Duration::from_secs(self.measurement_timeout_secs),
+            cert_fetch: Duration::from_secs(self.cert_fetch_timeout_secs),
+            builder_index: Duration::from_secs(self.builder_index_timeout_secs),
+            quote_collateral: Duration::from_secs(self.quote_collateral_timeout_secs),
+            submission_margin: Duration::from_secs(self.submission_margin_secs),
+        }
+    }
+}
+
+/// Derivation of the node's key from a BIP-39 mnemonic shared by a fleet of nodes
+#[derive(Args, Debug, Clone)]
+pub struct MnemonicArgs {
+    /// Mnemonic the node's key is derived from, unless a private key or keystore is given
+    #[arg(long, env = "MNEMONIC")]
+    pub mnemonic: Option<String>,
+
+    /// BIP-32 derivation path, the account index is appended to it
+    #[arg(long, env = "MNEMONIC_DERIVATION_PATH", default_value = DEFAULT_DERIVATION_PATH)]
+    pub mnemonic_derivation_path: String,
+
+    /// Account index to derive, instead of allocating one
+    #[arg(long, env = "MNEMONIC_INDEX")]
+    pub mnemonic_index: Option<u32>,
+
+    /// First account index handed out to nodes
+    #[arg(long, env = "MNEMONIC_BASE_INDEX", default_value_t = 0)]
+    pub mnemonic_base_index: u32,
+
+    /// Directory shared by replicas to allocate each one a unique account index, stable across
+    /// restarts
+    #[arg(long, env = "MNEMONIC_ALLOC_ROOT")]
+    pub mnemonic_alloc_root: Option<PathBuf>,
+
+    /// Identifier of this replica in the allocation directory, the hostname by default
+    #[arg(long, env = "MNEMONIC_REPLICA_ID")]
+    pub mnemonic_replica_id: Option<String>,
+}
+
+impl MnemonicArgs {
+    /// Derive the node's wallet, if a mnemonic is configured
+    pub fn wallet(&self) -> Result<Option<PrivateKeySigner>> {
+        let Some(mnemonic) = &self.mnemonic else {
+            return Ok(None);
+        };
+        let index = self.index()?;
+        let wallet = derive_wallet(mnemonic, &self.mnemonic_derivation_path, index)?;
+        info!(
+            "Derived wallet {} from mnemonic at {}/{index}",
+            wallet.address(),
+            self.mnemonic_derivation_path
+        );
+        Ok(Some(wallet))
+    }
+
+    /// The account index of this node
+    fn index(&self) -> Result<u32> {
+        if let Some(index) = self.mnemonic_index {
+            return Ok(index);
+        }
+        let Some(root) = &self.mnemonic_alloc_root else {
+            return Ok(self.mnemonic_base_index);
+        };
+        let id = match &self.mnemonic_replica_id {
+            Some(id) => id.clone(),
+            None => hostname()?,
+        };
+        let index = allocate_index(root, &id, self.mnemonic_base_index)?;
+        info!("Replica {id} was allocated mnemonic index {index}");
+        Ok(index)
+    }
+}
+
+/// Additional operator identities the node runs alongside its own
+#[derive(Args, Debug, Clone, Default)]
+pub struct IdentityArgs {
+    /// Remote signers of additional operator identities, comma separated `address=url` pairs
+    #[arg(long, env = "IDENTITY_REMOTE_SIGNERS", value_delimiter = ',', value_parser = parse_remote_signer)]
+    pub identity_remote_signers: Vec<SignerConfig>,
+
+    /// Keystores of additional operator identities, comma separated, decrypted with the
+    /// node's keystore passphrase
+    #[arg(long, env = "IDENTITY_KEYSTORES", value_delimiter = ',')]
+    pub identity_keystores: Vec<PathBuf>,
+}
+
+impl IdentityArgs {
+    /// The signers of the additional identities, keystores are decrypted like the node's own
+    pub fn signers(&self, keys: &KeyArgs) -> Result<Vec<SignerConfig>> {
+        let mut signers = self.identity_remote_signers.clone();
+        for path in &self.identity_keystores {
+            let keys = KeyArgs {
+                private_key: None,
+                keystore_path: Some(path.clone()),
+                ..keys.clone()
+            };
+            let private_key = keys
+                .private_key()?
+                .with_context(|| format!("Failed to load identity keystore {}", path.display()))?;
+            signers.push(SignerConfig::Local(private_key));
+        }
+        Ok(signers)
+    }
+}
+
+/// Parse an `address=url` remote signer
+fn parse_remote_signer(value: &str) -> Result<SignerConfig, String> {
+    let (address, url) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected address=url, got '{value}'"))?;
+    let address = address
+        .trim()
+        .parse()
+        .map_err(|e| format!("invalid remote signer address {address}: {e}"))?;
+    Ok(SignerConfig::Remote {
+        url: url.trim().to_string(),
+        address,
+    })
+}
+
+/// The hostname, which docker sets to the container id
+fn hostname() -> Result<String> {
+    if let Ok(hostname) = env::var("HOSTNAME") {
+        return Ok(hostname);
+    }
+    let hostname = std::fs::read_to_string("/etc/hostname")
+        .context("Failed to read hostname, set MNEMONIC_REPLICA_ID")?;
+    Ok(hostname.trim().to_string())
 }
 
 /// Node configuration with all required values resolved
@@ -53,7 +712,12 @@ pub struct NodeConfig {
     pub manager_contract_address: Address,
     pub staking_contract_address: Address,
     pub token_contract_address: Address,
-    pub private_key: String,
+    pub signer: SignerConfig,
+    /// Signers of the additional operator identities run by the node
+    pub identities: Vec<SignerConfig>,
+    pub fees: FeeConfig,
+    pub receipt_timeout: Duration,
+    pub max_replacements: u32,
     pub was_wallet_created: bool,
 }
 
@@ -70,51 +734,82 @@ impl NodeConfig {
             token_contract_address,
         } = ChainConfig::new(cli_args.chain_args, &state_file)?;
 
-        // Load or generate private key
+        let identities = cli_args.identities.signers(&cli_args.keys)?;
+        let fees = cli_args.fees.fee_config()?;
+        let receipt_timeout = cli_args.receipts.receipt_timeout()?;
+        let max_replacements = cli_args.receipts.max_tx_replacements;
+
+        // Sign remotely if configured, otherwise derive, load or generate a private key
         let mut was_wallet_created = false;
-        let private_key = match cli_args
-            .private_key
-            .or_else(|| state_file.load_value("PRIVATE_KEY"))
-        {
-            Some(pk) => pk,
-            None => {
-                // Generate a new wallet
-                info!("No private key found. Generating new wallet...");
-                let wallet = generate_wallet()?;
-                let private_key = format!("0x{}", hex::encode(wallet.to_bytes()));
-                let public_key = format!("{:?}", wallet.address());
-
-                // Save all values to state file using save_all
-                let mut state = std::collections::HashMap::new();
-                state.insert("PRIVATE_KEY".to_string(), private_key.clone());
-                state.insert("PUBLIC_KEY".to_string(), public_key.clone());
-                state.insert("RPC_URL".to_string(), rpc_url.clone());
-                state.insert(
-                    "MANAGER_CONTRACT_ADDRESS".to_string(),
-                    manager_contract_address.to_string(),
-                );
-                state.insert(
-                    "STAKING_CONTRACT_ADDRESS".to_string(),
-                    staking_contract_address.to_string(),
-                );
-                state.insert(
-                    "TOKEN_CONTRACT_ADDRESS".to_string(),
-                    token_contract_address.to_string(),
-                );
-                state_file.save_all(&state).map_err(|e| {
-                    anyhow::anyhow!(
-                        "Failed to save state file on path {}: {}",
-                        STATE_FILE_NODE,
-                        e
-                    )
-                })?;
-
-                info!("New wallet generated and saved to {}", STATE_FILE_NODE);
-                info!("Address: {}", public_key);
-                was_wallet_created = true;
-
-                private_key
-            }
+        let has_private_key =
+            cli_args.keys.private_key.is_some() || cli_args.keys.keystore_path.is_some();
+        let signer = if let Some(signer) = cli_args.keys.remote_signer() {
+            signer
+        } else if !has_private_key && let Some(wallet) = cli_args.mnemonic.wallet()? {
+            SignerConfig::Local(format!("0x{}", hex::encode(wallet.to_bytes())))
+        } else {
+            let private_key = match cli_args.keys.load(&state_file)? {
+                Some(pk) => pk,
+                None => {
+                    // Generate a new wallet, encrypted if a keystore passphrase is available
+                    info!("No private key found. Generating new wallet...");
+                    let mut state = std::collections::HashMap::new();
+                    let wallet = match cli_args
+                        .keys
+                        .generate_keystore(Path::new(KEYSTORE_FILE_NODE))?
+                    {
+                        Some((wallet, path)) => {
+                            info!("Private key encrypted into keystore {}", path.display());
+                            state.insert("KEYSTORE_PATH".to_string(), path.display().to_string());
+                            wallet
+                        }
+                        None => {
+                            warn!(
+                                "No keystore passphrase given, storing the private key in plaintext in {}",
+                                STATE_FILE_NODE
+                            );
+                            let wallet = generate_wallet()?;
+                            state.insert(
+                                "PRIVATE_KEY".to_string(),
+                                format!("0x{}", hex::encode(wallet.to_bytes())),
+                            );
+                            wallet
+                        }
+                    };
+                    let private_key = format!("0x{}", hex::encode(wallet.to_bytes()));
+                    let public_key = format!("{:?}", wallet.address());
+
+                    // Save all values to state file using save_all
+                    state.insert("PUBLIC_KEY".to_string(), public_key.clone());
+                    state.insert("RPC_URL".to_string(), rpc_url.clone());
+                    state.insert(
+                        "MANAGER_CONTRACT_ADDRESS".to_string(),
+                        manager_contract_address.to_string(),
+                    );
+                    state.insert(
+                        "STAKING_CONTRACT_ADDRESS".to_string(),
+                        staking_contract_address.to_string(),
+                    );
+                    state.insert(
+                        "TOKEN_CONTRACT_ADDRESS".to_string(),
+                        token_contract_address.to_string(),
+                    );
+                    state_file.save_all(&state).map_err(|e| {
+                        anyhow::anyhow!(
+                            "Failed to save state file on path {}: {}",
+                            STATE_FILE_NODE,
+                            e
+                        )
+                    })?;
+
+                    info!("New wallet generated and saved to {}", STATE_FILE_NODE);
+                    info!("Address: {}", public_key);
+                    was_wallet_created = true;
+
+                    private_key
+                }
+            };
+            SignerConfig::Local(private_key)
         };
 
         info!(
@@ -125,10 +820,71 @@ impl NodeConfig {
             manager_contract_address,
             staking_contract_address,
             token_contract_address,
-            private_key,
+            signer,
+            identities,
+            fees,
+            receipt_timeout,
+            max_replacements,
             was_wallet_created,
         })
     }
+
+    /// The contract configuration of the node's clients.
+    pub fn contract_config(&self) -> ContractConfig {
+        ContractConfig::new(
+            self.rpc_url.clone(),
+            self.manager_contract_address,
+            self.staking_contract_address,
+            self.token_contract_address,
+        )
+        .with_fees(self.fees.clone())
+        .with_receipt_timeout(self.receipt_timeout, self.max_replacements)
+    }
+
+    /// The contract configuration of the clients submitting verdicts. Verdicts failing
+    /// transiently are retried, so a verdict is not lost to a dropped connection.
+    pub fn verdict_contract_config(&self) -> ContractConfig {
+        self.contract_config()
+            .with_retry_policy(RetryPolicy::default())
+    }
+
+    /// Connect a new client with this configuration.
+    pub async fn connect(&self) -> Result<BlacklightClient> {
+        BlacklightClient::new(self.contract_config(), self.signer.clone()).await
+    }
+}
+
+/// Move the plaintext private key of the node's state file into an encrypted keystore
+pub fn migrate_keystore(keys: &KeyArgs) -> Result<()> {
+    let state_file = StateFile::new(STATE_FILE_NODE);
+    keys.migrate(&state_file, Path::new(KEYSTORE_FILE_NODE))?;
+    Ok(())
+}
+
+/// Run a mnemonic subcommand
+pub fn run_mnemonic_command(args: &MnemonicArgs, command: MnemonicCommand) -> Result<()> {
+    match command {
+        MnemonicCommand::New => {
+            let mnemonic = generate_mnemonic()?;
+            let wallet = derive_wallet(&mnemonic, &args.mnemonic_derivation_path, 0)?;
+            println!("{mnemonic}");
+            eprintln!(
+                "First account: {} (keep the mnemonic secret, it controls every derived account)",
+                wallet.address()
+            );
+        }
+        MnemonicCommand::Accounts { count } => {
+            let mnemonic = args
+                .mnemonic
+                .as_deref()
+                .context("No mnemonic given, set MNEMONIC")?;
+            for index in args.mnemonic_base_index..args.mnemonic_base_index + count {
+                let wallet = derive_wallet(mnemonic, &args.mnemonic_derivation_path, index)?;
+                println!("{index}\t{}", wallet.address());
+            }
+        }
+    }
+    Ok(())
 }
 
 /// Validates that the node has sufficient ETH balance and staked NIL tokens
@@ -0,0 +1,420 @@
+use crate::args::ArtifactsCommand;
+use anyhow::{Context, Result, bail};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The bucket nilCC artifacts are downloaded from by default.
+pub const DEFAULT_ARTIFACTS_URL: &str = "https://nilcc.s3.eu-west-1.amazonaws.com";
+
+/// Directory in the cache that removed versions are moved to before being deleted.
+const QUARANTINE_DIR: &str = ".quarantine";
+
+/// Pinned SHA-256 hashes of the artifacts of each nilCC version.
+///
+/// The manifest uses the `sha256sum` output format, with paths relative to the artifact cache
+/// so each path starts with the nilCC version:
+///
+/// ```text
+/// 3f1c...9a2e  0.2.1/vm_images/cvm-cpu.qcow2
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ArtifactManifest {
+    versions: BTreeMap<String, BTreeMap<PathBuf, [u8; 32]>>,
+}
+
+impl ArtifactManifest {
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read artifact manifest {}", path.display()))?;
+        Self::parse(&contents)
+    }
+
+    pub fn parse(contents: &str) -> Result<Self> {
+        let mut versions: BTreeMap<String, BTreeMap<PathBuf, [u8; 32]>> = BTreeMap::new();
+        for (index, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((hash, path)) = line.split_once(char::is_whitespace) else {
+                bail!("line {}: expected '<sha256>  <version>/<path>'", index + 1);
+            };
+            let mut hash_bytes = [0u8; 32];
+            alloy::hex::decode_to_slice(hash, &mut hash_bytes)
+                .with_context(|| format!("line {}: invalid sha256 '{hash}'", index + 1))?;
+            // sha256sum marks binary mode entries with a leading '*'
+            let path = path.trim_start().trim_start_matches('*');
+            let Some((version, file)) = path.split_once('/') else {
+                bail!("line {}: path '{path}' has no version prefix", index + 1);
+            };
+            versions
+                .entry(version.to_string())
+                .or_default()
+                .insert(PathBuf::from(file), hash_bytes);
+        }
+        Ok(Self { versions })
+    }
+
+    /// The pinned files of a version, if the version is in the manifest.
+    pub fn files(&self, version: &str) -> Option<&BTreeMap<PathBuf, [u8; 32]>> {
+        self.versions.get(version)
+    }
+
+    /// Whether the manifest pins no version at all, e.g. because none is configured.
+    pub fn is_empty(&self) -> bool {
+        self.versions.is_empty()
+    }
+}
+
+/// The integrity of a cached nilCC version against the manifest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Integrity {
+    /// Every pinned file is present and matches its hash.
+    Verified,
+    /// The version has no entry in the manifest.
+    Unpinned,
+    /// Some pinned files are missing or don't match their hash.
+    Corrupt {
+        missing: Vec<PathBuf>,
+        mismatched: Vec<PathBuf>,
+    },
+}
+
+impl std::fmt::Display for Integrity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Integrity::Verified => write!(f, "verified"),
+            Integrity::Unpinned => write!(f, "unpinned"),
+            Integrity::Corrupt {
+                missing,
+                mismatched,
+            } => write!(
+                f,
+                "corrupt ({} missing, {} mismatched)",
+                missing.len(),
+                mismatched.len()
+            ),
+        }
+    }
+}
+
+/// A nilCC version present in the artifact cache.
+#[derive(Debug, Clone)]
+pub struct CachedVersion {
+    pub version: String,
+    pub size_bytes: u64,
+}
+
+/// Manages the on-disk cache of nilCC artifacts, one directory per nilCC version.
+#[derive(Debug)]
+pub struct ArtifactCache {
+    root: PathBuf,
+    manifest: ArtifactManifest,
+    /// Versions checked against the manifest since they were last downloaded.
+    verified: Mutex<HashSet<String>>,
+}
+
+impl ArtifactCache {
+    pub fn new(root: PathBuf, manifest: ArtifactManifest) -> Self {
+        Self {
+            root,
+            manifest,
+            verified: Default::default(),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn version_path(&self, version: &str) -> PathBuf {
+        self.root.join(version)
+    }
+
+    /// Whether a manifest is configured, in which case versions it doesn't pin are refused.
+    pub fn requires_pins(&self) -> bool {
+        !self.manifest.is_empty()
+    }
+
+    /// List the versions in the cache, sorted by version name.
+    pub fn list(&self) -> Result<Vec<CachedVersion>> {
+        let entries = match fs::read_dir(&self.root) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e).context("Failed to read artifact cache"),
+        };
+        let mut versions = Vec::new();
+        for entry in entries {
+            let entry = entry?;
+            if !entry.file_type()?.is_dir() || entry.file_name() == QUARANTINE_DIR {
+                continue;
+            }
+            versions.push(CachedVersion {
+                version: entry.file_name().to_string_lossy().into_owned(),
+                size_bytes: dir_size(&entry.path())?,
+            });
+        }
+        versions.sort_by(|a, b| a.version.cmp(&b.version));
+        Ok(versions)
+    }
+
+    /// Hash the files of a cached version and compare them against the manifest.
+    pub fn verify(&self, version: &str) -> Result<Integrity> {
+        let Some(files) = self.manifest.files(version) else {
+            return Ok(Integrity::Unpinned);
+        };
+        let base = self.version_path(version);
+        let mut missing = Vec::new();
+        let mut mismatched = Vec::new();
+        for (file, expected) in files {
+            match sha256_file(&base.join(file)) {
+                Ok(actual) if &actual == expected => {}
+                Ok(_) => mismatched.push(file.clone()),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => missing.push(file.clone()),
+                Err(e) => {
+                    return Err(e).with_context(|| format!("Failed to hash {}", file.display()));
+                }
+            }
+        }
+        if missing.is_empty() && mismatched.is_empty() {
+            Ok(Integrity::Verified)
+        } else {
+            Ok(Integrity::Corrupt {
+                missing,
+                mismatched,
+            })
+        }
+    }
+
+    /// Verify a version unless it was already verified since it was last downloaded.
+    ///
+    /// A corrupt version is removed from the cache so that it gets downloaded again.
+    pub fn ensure_verified(&self, version: &str) -> Result<Integrity> {
+        if self.verified.lock().unwrap().contains(version) {
+            return Ok(Integrity::Verified);
+        }
+        let integrity = self.verify(version)?;
+        match &integrity {
+            Integrity::Verified => {
+                self.verified.lock().unwrap().insert(version.to_string());
+            }
+            Integrity::Corrupt { .. } => self.remove(version)?,
+            Integrity::Unpinned => {}
+        }
+        Ok(integrity)
+    }
+
+    /// Forget that a version was verified, e.g. because it is being downloaded again.
+    pub fn invalidate(&self, version: &str) {
+        self.verified.lock().unwrap().remove(version);
+    }
+
+    /// Remove a version from the cache.
+    ///
+    /// The version directory is first moved into quarantine in one rename, so a measurement
+    /// generator reading the version concurrently never sees it half deleted, and a download
+    /// of the version starts from an empty directory.
+    pub fn remove(&self, version: &str) -> Result<()> {
+        self.invalidate(version);
+        let quarantine = self.root.join(QUARANTINE_DIR);
+        fs::create_dir_all(&quarantine).context("Failed to create artifact quarantine")?;
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let quarantined = quarantine.join(format!("{version}.{nanos}"));
+        match fs::rename(self.version_path(version), &quarantined) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("Failed to quarantine cached version {version}"));
+            }
+        }
+        fs::remove_dir_all(&quarantined)
+            .with_context(|| format!("Failed to remove cached version {version}"))
+    }
+}
+
+/// Run an `artifacts` subcommand against the cache.
+pub fn run_command(cache: &ArtifactCache, command: ArtifactsCommand) -> Result<()> {
+    match command {
+        ArtifactsCommand::List => {
+            let versions = cache.list()?;
+            if versions.is_empty() {
+                println!("No nilcc versions cached in {}", cache.root().display());
+            }
+            for CachedVersion {
+                version,
+                size_bytes,
+            } in versions
+            {
+                println!(
+                    "{version}\t{:.1} MiB",
+                    size_bytes as f64 / (1024.0 * 1024.0)
+                );
+            }
+        }
+        ArtifactsCommand::Verify { versions } => {
+            let versions = if versions.is_empty() {
+                cache.list()?.into_iter().map(|v| v.version).collect()
+            } else {
+                versions
+            };
+            let mut corrupt = 0;
+            for version in versions {
+                let integrity = cache.verify(&version)?;
+                println!("{version}\t{integrity}");
+                if let Integrity::Corrupt {
+                    missing,
+                    mismatched,
+                } = integrity
+                {
+                    for file in missing {
+                        println!("  missing:    {}", file.display());
+                    }
+                    for file in mismatched {
+                        println!("  mismatched: {}", file.display());
+                    }
+                    corrupt += 1;
+                }
+            }
+            if corrupt > 0 {
+                bail!("{corrupt} cached nilcc versions failed verification");
+            }
+        }
+        ArtifactsCommand::Prune { keep, dry_run } => {
+            for CachedVersion { version, .. } in cache.list()? {
+                let reason = if !keep.is_empty() && !keep.contains(&version) {
+                    "not kept".to_string()
+                } else {
+                    match cache.verify(&version)? {
+                        integrity @ Integrity::Corrupt { .. } => integrity.to_string(),
+                        Integrity::Verified | Integrity::Unpinned => continue,
+                    }
+                };
+                if dry_run {
+                    println!("would remove {version} ({reason})");
+                } else {
+                    cache.remove(&version)?;
+                    println!("removed {version} ({reason})");
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn sha256_file(path: &Path) -> io::Result<[u8; 32]> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+    Ok(hasher.finalize().into())
+}
+
+fn dir_size(path: &Path) -> io::Result<u64> {
+    let mut size = 0;
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            size += dir_size(&entry.path())?;
+        } else if file_type.is_file() {
+            size += entry.metadata()?.len();
+        }
+    }
+    Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_cache(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("blacklight-artifacts-{name}"));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        path
+    }
+
+    fn sha256_hex(data: &[u8]) -> String {
+        alloy::hex::encode(Sha256::digest(data))
+    }
+
+    #[test]
+    fn test_parse_manifest() {
+        let manifest = ArtifactManifest::parse(&format!(
+            "# pinned artifacts\n{}  0.2.1/kernel\n{} *0.2.1/images/disk.qcow2\n",
+            sha256_hex(b"kernel"),
+            sha256_hex(b"disk")
+        ))
+        .unwrap();
+        let files = manifest.files("0.2.1").unwrap();
+        assert_eq!(files.len(), 2);
+        assert!(files.contains_key(Path::new("images/disk.qcow2")));
+        assert!(manifest.files("0.3.0").is_none());
+    }
+
+    #[test]
+    fn test_parse_manifest_errors() {
+        assert!(ArtifactManifest::parse("nothex  0.2.1/kernel").is_err());
+        assert!(ArtifactManifest::parse(&format!("{}  kernel", sha256_hex(b"k"))).is_err());
+        assert!(ArtifactManifest::parse("just-one-field").is_err());
+    }
+
+    #[test]
+    fn test_verify_and_prune_corrupt() {
+        let root = temp_cache("verify");
+        fs::create_dir_all(root.join("0.2.1")).unwrap();
+        fs::write(root.join("0.2.1/kernel"), b"kernel").unwrap();
+        fs::create_dir_all(root.join("0.3.0")).unwrap();
+        fs::write(root.join("0.3.0/kernel"), b"tampered").unwrap();
+        let manifest = ArtifactManifest::parse(&format!(
+            "{hash}  0.2.1/kernel\n{hash}  0.3.0/kernel\n{hash}  0.3.0/initrd\n",
+            hash = sha256_hex(b"kernel")
+        ))
+        .unwrap();
+        let cache = ArtifactCache::new(root.clone(), manifest);
+
+        let versions: Vec<_> = cache
+            .list()
+            .unwrap()
+            .into_iter()
+            .map(|v| v.version)
+            .collect();
+        assert_eq!(versions, vec!["0.2.1", "0.3.0"]);
+
+        assert_eq!(cache.ensure_verified("0.2.1").unwrap(), Integrity::Verified);
+        assert_eq!(
+            cache.verify("0.3.0").unwrap(),
+            Integrity::Corrupt {
+                missing: vec![PathBuf::from("initrd")],
+                mismatched: vec![PathBuf::from("kernel")],
+            }
+        );
+        assert!(matches!(
+            cache.ensure_verified("0.3.0").unwrap(),
+            Integrity::Corrupt { .. }
+        ));
+        assert!(!root.join("0.3.0").exists());
+        assert_eq!(cache.verify("0.4.0").unwrap(), Integrity::Unpinned);
+        assert!(cache.requires_pins());
+
+        // Removed versions leave nothing behind in quarantine
+        let versions: Vec<_> = cache
+            .list()
+            .unwrap()
+            .into_iter()
+            .map(|v| v.version)
+            .collect();
+        assert_eq!(versions, vec!["0.2.1"]);
+        assert_eq!(fs::read_dir(root.join(QUARANTINE_DIR)).unwrap().count(), 0);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,330 @@
+use alloy::primitives::B256;
+use blacklight_contract_clients::heartbeat_manager::Verdict;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Instant;
+use tracing::{info, warn};
+
+/// Maximum number of rounds tracked at once; the oldest are dropped beyond this.
+const MAX_TRACKED_ROUNDS: usize = 10_000;
+
+/// Number of disagreements kept for the report.
+const MAX_DISAGREEMENTS: usize = 20;
+
+type RoundKey = (B256, u8);
+
+#[derive(Debug)]
+struct TrackedRound {
+    started: Instant,
+    verdict: Option<Verdict>,
+    /// `Some(None)` once finalized without a conclusive outcome.
+    outcome: Option<Option<Verdict>>,
+    votes: Vec<Verdict>,
+}
+
+/// A finalized round whose outcome differed from our verdict.
+#[derive(Debug, Clone)]
+pub struct Disagreement {
+    pub heartbeat_key: B256,
+    pub round: u8,
+    pub ours: Verdict,
+    pub outcome: Verdict,
+}
+
+/// How our verdicts compare to what the rest of the committee decided.
+#[derive(Debug, Clone, Default)]
+pub struct AgreementReport {
+    /// Rounds we computed a verdict for.
+    pub verified: u64,
+    /// Finalized rounds with a conclusive outcome we could compare against.
+    pub compared: u64,
+    pub agreed: u64,
+    /// Finalized rounds without a conclusive outcome.
+    pub no_outcome: u64,
+    /// Votes of other operators compared against our verdict.
+    pub votes_compared: u64,
+    pub votes_agreed: u64,
+    /// Consecutive finalized rounds in which we were in the minority.
+    pub minority_streak: u64,
+    /// The longest minority streak seen so far.
+    pub max_minority_streak: u64,
+    /// The most recent disagreements with a round outcome, oldest first.
+    pub disagreements: VecDeque<Disagreement>,
+}
+
+impl AgreementReport {
+    fn rate(agreed: u64, total: u64) -> String {
+        if total == 0 {
+            "n/a".to_string()
+        } else {
+            format!("{:.1}%", agreed as f64 * 100.0 / total as f64)
+        }
+    }
+
+    /// One-line summary of the agreement with round outcomes.
+    pub fn summary(&self) -> String {
+        format!(
+            "{}/{} ({})",
+            self.agreed,
+            self.compared,
+            Self::rate(self.agreed, self.compared)
+        )
+    }
+}
+
+impl fmt::Display for AgreementReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Consensus agreement report")?;
+        writeln!(f, "  verified rounds:     {}", self.verified)?;
+        writeln!(
+            f,
+            "  round outcomes:      {}/{} agreed ({}), {} without outcome",
+            self.agreed,
+            self.compared,
+            Self::rate(self.agreed, self.compared),
+            self.no_outcome
+        )?;
+        writeln!(
+            f,
+            "  operator votes:      {}/{} agreed ({})",
+            self.votes_agreed,
+            self.votes_compared,
+            Self::rate(self.votes_agreed, self.votes_compared)
+        )?;
+        writeln!(
+            f,
+            "  minority streak:     {} (max {})",
+            self.minority_streak, self.max_minority_streak
+        )?;
+        for d in &self.disagreements {
+            writeln!(
+                f,
+                "  disagreement: htx={} round={} ours={:?} outcome={:?}",
+                d.heartbeat_key, d.round, d.ours, d.outcome
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    rounds: HashMap<RoundKey, TrackedRound>,
+    report: AgreementReport,
+}
+
+/// Records the verdicts of the rounds this node takes part in and compares them against the
+/// other members' votes and the round outcomes, warning when the node keeps ending up in the
+/// minority.
+#[derive(Debug)]
+pub struct ConsensusTracker {
+    /// Consecutive minority rounds after which a warning is raised, 0 disables the warning.
+    minority_warn_threshold: u64,
+    inner: Mutex<Inner>,
+}
+
+impl ConsensusTracker {
+    pub fn new(minority_warn_threshold: u64) -> Self {
+        Self {
+            minority_warn_threshold,
+            inner: Default::default(),
+        }
+    }
+
+    /// Start tracking a round we are about to verify.
+    pub fn round_started(&self, heartbeat_key: B256, round: u8) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.rounds.len() >= MAX_TRACKED_ROUNDS {
+            let oldest = inner
+                .rounds
+                .iter()
+                .min_by_key(|(_, r)| r.started)
+                .map(|(key, _)| *key);
+            if let Some(oldest) = oldest {
+                inner.rounds.remove(&oldest);
+            }
+        }
+        inner
+            .rounds
+            .entry((heartbeat_key, round))
+            .or_insert_with(|| TrackedRound {
+                started: Instant::now(),
+                verdict: None,
+                outcome: None,
+                votes: Vec::new(),
+            });
+    }
+
+    /// Record our verdict for a round.
+    pub fn record_verdict(&self, heartbeat_key: B256, round: u8, verdict: Verdict) {
+        let mut inner = self.inner.lock().unwrap();
+        let Inner { rounds, report } = &mut *inner;
+        let Some(entry) = rounds.get_mut(&(heartbeat_key, round)) else {
+            return;
+        };
+        entry.verdict = Some(verdict);
+        report.verified += 1;
+        for vote in &entry.votes {
+            Self::tally_vote(report, verdict, *vote);
+        }
+        if let Some(outcome) = entry.outcome {
+            self.tally_outcome(report, heartbeat_key, round, verdict, outcome);
+            rounds.remove(&(heartbeat_key, round));
+        }
+    }
+
+    /// Record a vote cast by another member of a tracked round.
+    pub fn observe_vote(&self, heartbeat_key: B256, round: u8, code: u8) {
+        let Some(vote) = Verdict::from_code(code) else {
+            return;
+        };
+        let mut inner = self.inner.lock().unwrap();
+        let Inner { rounds, report } = &mut *inner;
+        let Some(entry) = rounds.get_mut(&(heartbeat_key, round)) else {
+            return;
+        };
+        match entry.verdict {
+            Some(ours) => Self::tally_vote(report, ours, vote),
+            None => entry.votes.push(vote),
+        }
+    }
+
+    /// Record the outcome of a tracked round.
+    pub fn observe_finalized(&self, heartbeat_key: B256, round: u8, outcome: u8) {
+        let outcome = Verdict::from_code(outcome);
+        let mut inner = self.inner.lock().unwrap();
+        let Inner { rounds, report } = &mut *inner;
+        let Some(entry) = rounds.get_mut(&(heartbeat_key, round)) else {
+            return;
+        };
+        match entry.verdict {
+            Some(ours) => {
+                self.tally_outcome(report, heartbeat_key, round, ours, outcome);
+                rounds.remove(&(heartbeat_key, round));
+            }
+            None => entry.outcome = Some(outcome),
+        }
+    }
+
+    /// A snapshot of the agreement report so far.
+    pub fn report(&self) -> AgreementReport {
+        self.inner.lock().unwrap().report.clone()
+    }
+
+    fn tally_vote(report: &mut AgreementReport, ours: Verdict, vote: Verdict) {
+        report.votes_compared += 1;
+        if ours == vote {
+            report.votes_agreed += 1;
+        }
+    }
+
+    fn tally_outcome(
+        &self,
+        report: &mut AgreementReport,
+        heartbeat_key: B256,
+        round: u8,
+        ours: Verdict,
+        outcome: Option<Verdict>,
+    ) {
+        let Some(outcome) = outcome else {
+            report.no_outcome += 1;
+            return;
+        };
+        report.compared += 1;
+        if ours == outcome {
+            report.agreed += 1;
+            report.minority_streak = 0;
+            info!(htx_id = ?heartbeat_key, round, verdict = ?ours, "Round finalized in agreement with our verdict");
+            return;
+        }
+
+        report.minority_streak += 1;
+        report.max_minority_streak = report.max_minority_streak.max(report.minority_streak);
+        if report.disagreements.len() >= MAX_DISAGREEMENTS {
+            report.disagreements.pop_front();
+        }
+        report.disagreements.push_back(Disagreement {
+            heartbeat_key,
+            round,
+            ours,
+            outcome,
+        });
+        warn!(htx_id = ?heartbeat_key, round, ours = ?ours, outcome = ?outcome, "Round finalized against our verdict");
+        if self.minority_warn_threshold > 0
+            && report.minority_streak >= self.minority_warn_threshold
+        {
+            warn!(
+                streak = report.minority_streak,
+                agreement = %report.summary(),
+                "⚠️ Node was in the minority of its last {} rounds, check the cert cache, clock and verifier before it gets slashed or jailed",
+                report.minority_streak
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_agreement_report() {
+        let tracker = ConsensusTracker::new(0);
+        let (a, b, c) = (
+            B256::repeat_byte(1),
+            B256::repeat_byte(2),
+            B256::repeat_byte(3),
+        );
+        tracker.round_started(a, 1);
+        tracker.round_started(b, 1);
+        tracker.round_started(c, 1);
+
+        // Votes and outcome arrive before our verdict
+        tracker.observe_vote(a, 1, 1);
+        tracker.observe_finalized(a, 1, 1);
+        tracker.record_verdict(a, 1, Verdict::Success);
+
+        // Our verdict arrives first and disagrees with the outcome
+        tracker.record_verdict(b, 1, Verdict::Failure);
+        tracker.observe_vote(b, 1, 1);
+        tracker.observe_finalized(b, 1, 1);
+
+        // Finalized without outcome, and a round we never tracked
+        tracker.record_verdict(c, 1, Verdict::Inconclusive);
+        tracker.observe_finalized(c, 1, 0);
+        tracker.observe_finalized(B256::repeat_byte(4), 1, 1);
+
+        let report = tracker.report();
+        assert_eq!(report.verified, 3);
+        assert_eq!(report.compared, 2);
+        assert_eq!(report.agreed, 1);
+        assert_eq!(report.no_outcome, 1);
+        assert_eq!(report.votes_compared, 2);
+        assert_eq!(report.votes_agreed, 1);
+        assert_eq!(report.disagreements.len(), 1);
+        assert_eq!(report.disagreements[0].heartbeat_key, b);
+        assert!(tracker.inner.lock().unwrap().rounds.is_empty());
+        assert!(report.to_string().contains("1/2 agreed (50.0%)"));
+    }
+
+    #[test]
+    fn test_minority_streak() {
+        let tracker = ConsensusTracker::new(2);
+        for i in 0..3 {
+            let key = B256::repeat_byte(i);
+            tracker.round_started(key, 1);
+            tracker.record_verdict(key, 1, Verdict::Failure);
+            tracker.observe_finalized(key, 1, 1);
+        }
+        assert_eq!(tracker.report().minority_streak, 3);
+
+        let key = B256::repeat_byte(9);
+        tracker.round_started(key, 1);
+        tracker.record_verdict(key, 1, Verdict::Success);
+        tracker.observe_finalized(key, 1, 1);
+        let report = tracker.report();
+        assert_eq!(report.minority_streak, 0);
+        assert_eq!(report.max_minority_streak, 3);
+    }
+}
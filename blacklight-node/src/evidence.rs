@@ -0,0 +1,394 @@
+//! Evidence bundles persisted for Failure verdicts.
+//!
+//! Each bundle is a JSON file named `<heartbeat_key>-r<round>-<operator>.json`, after the
+//! identity that reached the verdict:
+//!
+//! ```json
+//! {
+//!   "format": "blacklight-evidence/v1",
+//!   "content_hash": "<sha256 of the serialized `evidence` object, hex>",
+//!   "evidence": {
+//!     "heartbeat_key": "0x…",
+//!     "round": 1,
+//!     "created_at": 1760000000,
+//!     "node_version": "0.9.0",
+//!     "error": "measurement not found in builder index",
+//!     "raw_htx": "<hex>",
+//!     "nillion": {
+//!       "report_url": "…", "report": { … }, "nilcc_version": "…", "cpu_count": 4,
+//!       "vm_type": "Cpu", "report_measurement": "<hex>", "expected_measurement": "<hex>",
+//!       "builder_index_url": "…", "builder_index": { … }, "certificates": { "<file>": "<hex>" }
+//!     },
+//!     "phala": {
+//!       "quote": "<hex>", "event_log": "…", "app_compose_hash": "<hex>",
+//!       "attested_compose_hash": "<hex>", "collateral": { … }
+//!     }
+//!   }
+//! }
+//! ```
+//!
+//! The `evidence` object is serialized with sorted keys, so the content hash can be recomputed
+//! from the file alone. Fields that were not reached before verification failed are `null`.
+//! Everything is captured while verifying, so the bundle holds exactly what was judged: the report
+//! as served to the report fetcher, the certificates of the chain the report was checked against
+//! and the Phala collateral the quote was verified with. Bundles are written after the verdict is
+//! submitted.
+
+use crate::verification::{builder_index_contains, compose_hash_matches};
+use crate::version::VERSION;
+use alloy::hex;
+use alloy::primitives::{Address, B256};
+use anyhow::{Context, Result, bail};
+use blacklight_contract_clients::htx::{Htx, PhalaHtx};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const EVIDENCE_FORMAT: &str = "blacklight-evidence/v1";
+
+/// Data gathered while verifying a nilCC HTX.
+#[derive(Debug, Clone, Default)]
+pub struct NillionEvidence {
+    pub report_url: String,
+    pub report: Option<Value>,
+    pub nilcc_version: Option<String>,
+    pub cpu_count: Option<u32>,
+    pub vm_type: Option<String>,
+    pub report_measurement: Option<Vec<u8>>,
+    pub expected_measurement: Option<Vec<u8>>,
+    pub builder_index_url: String,
+    pub builder_index: Option<Value>,
+    pub certificates: Option<Value>,
+}
+
+/// Data gathered while verifying a Phala HTX.
+#[derive(Debug, Clone, Default)]
+pub struct PhalaEvidence {
+    pub quote: Option<Vec<u8>>,
+    pub event_log: String,
+    pub app_compose_hash: Option<String>,
+    pub attested_compose_hash: Option<String>,
+    pub collateral: Option<Value>,
+}
+
+/// Everything known about a verification, filled in as it progresses.
+#[derive(Debug, Clone, Default)]
+pub struct Evidence {
+    pub nillion: Option<NillionEvidence>,
+    pub phala: Option<PhalaEvidence>,
+}
+
+impl Evidence {
+    /// The nilCC section, created on first use.
+    pub fn nillion(&mut self) -> &mut NillionEvidence {
+        self.nillion.get_or_insert_with(Default::default)
+    }
+
+    /// The Phala section, created on first use.
+    pub fn phala(&mut self) -> &mut PhalaEvidence {
+        self.phala.get_or_insert_with(Default::default)
+    }
+
+    /// Build the bundle for a failed verification of `raw_htx`.
+    pub fn into_bundle(self, heartbeat_key: B256, round: u8, raw_htx: &[u8], error: &str) -> Value {
+        let created_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let nillion = self.nillion.map(|n| {
+            json!({
+                "report_url": n.report_url,
+                "report": n.report,
+                "nilcc_version": n.nilcc_version,
+                "cpu_count": n.cpu_count,
+                "vm_type": n.vm_type,
+                "report_measurement": n.report_measurement.map(hex::encode),
+                "expected_measurement": n.expected_measurement.map(hex::encode),
+                "builder_index_url": n.builder_index_url,
+                "builder_index": n.builder_index,
+                "certificates": n.certificates,
+            })
+        });
+        let phala = self.phala.map(|p| {
+            json!({
+                "quote": p.quote.map(hex::encode),
+                "event_log": p.event_log,
+                "app_compose_hash": p.app_compose_hash,
+                "attested_compose_hash": p.attested_compose_hash,
+                "collateral": p.collateral,
+            })
+        });
+        let evidence = json!({
+            "heartbeat_key": heartbeat_key.to_string(),
+            "round": round,
+            "created_at": created_at,
+            "node_version": VERSION,
+            "error": error,
+            "raw_htx": hex::encode(raw_htx),
+            "nillion": nillion,
+            "phala": phala,
+        });
+        json!({
+            "format": EVIDENCE_FORMAT,
+            "content_hash": content_hash(&evidence),
+            "evidence": evidence,
+        })
+    }
+}
+
+fn content_hash(evidence: &Value) -> String {
+    let bytes = serde_json::to_vec(evidence).expect("JSON values always serialize");
+    hex::encode(Sha256::digest(bytes))
+}
+
+/// Snapshot the files of the certificate cache as `{ "<file name>": "<hex>" }`.
+pub fn snapshot_certificates(cert_cache: &Path) -> Result<Value> {
+    read_certificates(cert_cache, |_| true)
+}
+
+/// The certificates of the chain a report of chip `chip_id` is verified against, in the format of
+/// [`snapshot_certificates`].
+///
+/// Cached VCEKs are named after the chip they belong to, so the chain is the VCEK whose file name
+/// has this chip's ID, plus the AMD root and signing keys, which name no chip.
+pub fn chain_certificates(cert_cache: &Path, chip_id: &[u8]) -> Result<Value> {
+    let chip_id = hex::encode(chip_id);
+    read_certificates(cert_cache, |name| {
+        let name = name.to_lowercase();
+        name.contains(&chip_id) || !names_chip(&name)
+    })
+}
+
+/// Whether a file name contains a chip ID, a run of at least 128 hex digits.
+fn names_chip(name: &str) -> bool {
+    name.split(|c: char| !c.is_ascii_hexdigit())
+        .any(|run| run.len() >= 128)
+}
+
+fn read_certificates(cert_cache: &Path, keep: impl Fn(&str) -> bool) -> Result<Value> {
+    let mut certificates = serde_json::Map::new();
+    for entry in fs::read_dir(cert_cache).context("Failed to read certificate cache")? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if entry.file_type()?.is_file() && keep(&name) {
+            let contents = fs::read(entry.path())?;
+            certificates.insert(name, Value::String(hex::encode(contents)));
+        }
+    }
+    Ok(Value::Object(certificates))
+}
+
+/// Writes evidence bundles into a directory.
+#[derive(Debug, Clone)]
+pub struct EvidenceStore {
+    dir: PathBuf,
+}
+
+impl EvidenceStore {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Write the bundle of `operator`'s verdict, never replacing an existing bundle.
+    pub fn write(
+        &self,
+        heartbeat_key: B256,
+        round: u8,
+        operator: Address,
+        bundle: &Value,
+    ) -> Result<PathBuf> {
+        fs::create_dir_all(&self.dir).context("Failed to create evidence directory")?;
+        let path = self
+            .dir
+            .join(format!("{heartbeat_key}-r{round}-{operator}.json"));
+        let contents = serde_json::to_vec_pretty(bundle)?;
+        OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .and_then(|mut file| file.write_all(&contents))
+            .with_context(|| format!("Failed to write evidence bundle {}", path.display()))?;
+        Ok(path)
+    }
+}
+
+/// The result of re-checking a bundle offline.
+#[derive(Debug, Default)]
+pub struct Recheck {
+    /// Checks that could be repeated, with whether they passed.
+    pub checks: Vec<(&'static str, bool)>,
+}
+
+impl Recheck {
+    pub fn failed(&self) -> bool {
+        self.checks.iter().any(|(_, passed)| !passed)
+    }
+}
+
+/// Verify the content hash of a bundle and repeat the checks that need no network access.
+///
+/// AMD report signatures can't be re-checked offline; Phala quotes are verified against the
+/// stored collateral as of the time the bundle was created.
+pub fn recheck(bundle: &Value) -> Result<Recheck> {
+    if bundle["format"] != EVIDENCE_FORMAT {
+        bail!("unsupported evidence format {}", bundle["format"]);
+    }
+    let evidence = &bundle["evidence"];
+    if bundle["content_hash"].as_str() != Some(&content_hash(evidence)) {
+        bail!("content hash mismatch, the bundle was modified");
+    }
+
+    let mut result = Recheck::default();
+    if let Some(nillion) = evidence["nillion"].as_object() {
+        let report = nillion["report_measurement"].as_str();
+        let expected = nillion["expected_measurement"].as_str();
+        if let (Some(report), Some(expected)) = (report, expected) {
+            result
+                .checks
+                .push(("report measurement matches expected", report == expected));
+        }
+        if let (Some(report), Some(index)) = (report, nillion.get("builder_index"))
+            && !index.is_null()
+        {
+            result.checks.push((
+                "measurement in builder index",
+                builder_index_contains(index, report),
+            ));
+        }
+    }
+    if let Some(phala) = evidence["phala"].as_object() {
+        let raw_htx = hex::decode(evidence["raw_htx"].as_str().unwrap_or_default())
+            .context("invalid raw_htx")?;
+        if let Ok(Htx::Phala(PhalaHtx::V1(htx))) = serde_json::from_slice::<Htx>(&raw_htx) {
+            result.checks.push((
+                "compose hash matches event log",
+                compose_hash_matches(&htx.app_compose, &htx.attest_data.event_log).is_ok(),
+            ));
+        }
+        if let (Some(quote), Some(collateral)) = (phala["quote"].as_str(), phala.get("collateral"))
+            && !collateral.is_null()
+        {
+            let quote = hex::decode(quote).context("invalid quote")?;
+            let collateral: dcap_qvl::QuoteCollateralV3 =
+                serde_json::from_value(collateral.clone()).context("invalid collateral")?;
+            let created_at = evidence["created_at"].as_u64().unwrap_or_default();
+            result.checks.push((
+                "quote verifies against stored collateral",
+                dcap_qvl::verify::verify(&quote, &collateral, created_at).is_ok(),
+            ));
+        }
+    }
+    Ok(result)
+}
+
+/// Re-check an evidence bundle file and print the outcome.
+pub fn run_verify(path: &Path) -> Result<()> {
+    let contents = fs::read(path)
+        .with_context(|| format!("Failed to read evidence bundle {}", path.display()))?;
+    let bundle: Value = serde_json::from_slice(&contents).context("Invalid evidence bundle")?;
+    let result = recheck(&bundle)?;
+    println!("content hash: ok");
+    println!("recorded error: {}", bundle["evidence"]["error"]);
+    for (check, passed) in &result.checks {
+        println!("{check}: {}", if *passed { "pass" } else { "FAIL" });
+    }
+    if result.checks.is_empty() {
+        println!("no offline checks apply to this bundle");
+    } else if result.failed() {
+        println!("result: failure reproduced offline");
+    } else {
+        println!("result: failure not reproduced by the offline checks");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundle_roundtrip() {
+        let mut evidence = Evidence::default();
+        let nillion = evidence.nillion();
+        nillion.report_measurement = Some(vec![0xaa; 48]);
+        nillion.expected_measurement = Some(vec![0xaa; 48]);
+        nillion.builder_index = Some(json!({ "v1": hex::encode([0xbb; 48]) }));
+
+        let bundle = evidence.into_bundle(B256::repeat_byte(1), 2, b"{}", "not in index");
+        let result = recheck(&bundle).unwrap();
+        assert_eq!(
+            result.checks,
+            vec![
+                ("report measurement matches expected", true),
+                ("measurement in builder index", false),
+            ]
+        );
+        assert!(result.failed());
+
+        let mut tampered = bundle.clone();
+        tampered["evidence"]["error"] = json!("something else");
+        assert!(recheck(&tampered).is_err());
+    }
+
+    #[test]
+    fn test_chain_certificates() {
+        let dir = std::env::temp_dir().join(format!(
+            "blacklight-evidence-certs-test-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let chip = [0xab; 64];
+        fs::write(dir.join("ark.pem"), b"ark").unwrap();
+        fs::write(dir.join(format!("vcek-{}.der", hex::encode(chip))), b"ours").unwrap();
+        fs::write(
+            dir.join(format!("vcek-{}.der", hex::encode([0xcd; 64]))),
+            b"other",
+        )
+        .unwrap();
+
+        let certificates = chain_certificates(&dir, &chip).unwrap();
+        let mut names: Vec<_> = certificates.as_object().unwrap().keys().cloned().collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                "ark.pem".to_string(),
+                format!("vcek-{}.der", hex::encode(chip))
+            ]
+        );
+        assert_eq!(
+            snapshot_certificates(&dir)
+                .unwrap()
+                .as_object()
+                .unwrap()
+                .len(),
+            3
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_store_write() {
+        let dir =
+            std::env::temp_dir().join(format!("blacklight-evidence-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let store = EvidenceStore::new(dir.clone());
+        let bundle = Evidence::default().into_bundle(B256::ZERO, 1, b"{}", "error");
+        let first = Address::repeat_byte(1);
+        let path = store.write(B256::ZERO, 1, first, &bundle).unwrap();
+        assert!(path.ends_with(format!("{}-r1-{first}.json", B256::ZERO)));
+        let written: Value = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+        assert!(recheck(&written).is_ok());
+
+        // Another identity's verdict gets its own bundle, and bundles are never replaced
+        let second = Address::repeat_byte(2);
+        let other = store.write(B256::ZERO, 1, second, &bundle).unwrap();
+        assert_ne!(path, other);
+        assert!(store.write(B256::ZERO, 1, first, &bundle).is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,244 @@
+use crate::metrics;
+use reqwest::{Client, Url};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+use tracing::{info, warn};
+
+/// Timeout for a single probe request.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Upper bound on the number of builder hosts that are probed, so HTX contents can't grow the
+/// probe set without limit. Past it, the host seen least recently is dropped.
+const MAX_BUILDER_HOSTS: usize = 32;
+
+/// Builder hosts no verified HTX has used for this long are no longer probed.
+const BUILDER_IDLE_TTL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// The last known state of an external service.
+#[derive(Debug, Clone)]
+pub struct ProbeStatus {
+    pub up: bool,
+    /// When the service entered its current state.
+    pub since: Instant,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    /// Probed URL by dependency name.
+    targets: BTreeMap<String, String>,
+    /// When each tracked builder was last used by a verified HTX, by dependency name.
+    builders: HashMap<String, Instant>,
+    statuses: BTreeMap<String, ProbeStatus>,
+}
+
+impl Inner {
+    /// Stop probing the builder `name`.
+    fn untrack(&mut self, name: &str) {
+        self.builders.remove(name);
+        self.targets.remove(name);
+        self.statuses.remove(name);
+    }
+
+    /// Stop probing builders idle for longer than `ttl`.
+    fn expire_builders(&mut self, ttl: Duration) {
+        let idle: Vec<_> = self
+            .builders
+            .iter()
+            .filter(|(_, seen)| seen.elapsed() > ttl)
+            .map(|(name, _)| name.clone())
+            .collect();
+        for name in idle {
+            info!(
+                dependency = name,
+                "Builder host is idle, no longer probing it"
+            );
+            self.untrack(&name);
+        }
+    }
+}
+
+/// Tracks the availability of the external services the verifiers depend on.
+///
+/// A service counts as down when it can't be reached or answers with a server error. Any other
+/// response, including client errors such as a bucket denying listings, means it is up.
+#[derive(Debug, Default)]
+pub struct DependencyHealth {
+    inner: Mutex<Inner>,
+}
+
+impl DependencyHealth {
+    /// Create a tracker for the given `(name, url)` targets.
+    pub fn new(targets: impl IntoIterator<Item = (String, String)>) -> Self {
+        let inner = Inner {
+            targets: targets.into_iter().collect(),
+            ..Default::default()
+        };
+        Self {
+            inner: Mutex::new(inner),
+        }
+    }
+
+    /// Start or keep probing the host serving a builder measurement index. Only called with the
+    /// builders of HTXs that verified, so unverified HTX contents choose no probe targets.
+    pub fn track_builder(&self, builder_url: &str) {
+        let Ok(url) = Url::parse(builder_url) else {
+            return;
+        };
+        let Some(host) = url.host_str() else {
+            return;
+        };
+        let name = format!("builder:{host}");
+        let mut inner = self.inner.lock().unwrap();
+        if !inner.builders.contains_key(&name)
+            && inner.builders.len() >= MAX_BUILDER_HOSTS
+            && let Some(oldest) = inner
+                .builders
+                .iter()
+                .min_by_key(|(_, seen)| **seen)
+                .map(|(name, _)| name.clone())
+        {
+            inner.untrack(&oldest);
+        }
+        inner.builders.insert(name.clone(), Instant::now());
+        inner
+            .targets
+            .entry(name)
+            .or_insert_with(|| format!("{}://{host}/", url.scheme()));
+    }
+
+    /// The dependencies currently known to be down, with how long they have been down.
+    pub fn outages(&self) -> Vec<(String, Duration)> {
+        let inner = self.inner.lock().unwrap();
+        inner
+            .statuses
+            .iter()
+            .filter(|(_, status)| !status.up)
+            .map(|(name, status)| (name.clone(), status.since.elapsed()))
+            .collect()
+    }
+
+    /// A description of active outages to attach to an Inconclusive verdict.
+    pub fn outage_summary(&self) -> String {
+        let outages = self.outages();
+        if outages.is_empty() {
+            return "none".to_string();
+        }
+        outages
+            .iter()
+            .map(|(name, down_for)| format!("{name} (down {}s)", down_for.as_secs()))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Record the result of probing a dependency, logging state changes.
+    pub fn record(&self, name: &str, result: Result<(), String>) {
+        let mut inner = self.inner.lock().unwrap();
+        let up = result.is_ok();
+        metrics::get().health.set_up(name, up);
+        let was_up = inner.statuses.get(name).map(|status| status.up);
+        if was_up == Some(up) {
+            return;
+        }
+        match (result, was_up) {
+            (Err(error), _) => warn!(dependency = name, error = %error, "⚠️ Dependency is down"),
+            (Ok(()), Some(false)) => info!(dependency = name, "Dependency recovered"),
+            (Ok(()), _) => info!(dependency = name, "Dependency is up"),
+        }
+        inner.statuses.insert(
+            name.to_string(),
+            ProbeStatus {
+                up,
+                since: Instant::now(),
+            },
+        );
+    }
+
+    /// Probe every target once, after dropping idle builders.
+    pub async fn probe_all(&self, http: &Client) {
+        let targets = {
+            let mut inner = self.inner.lock().unwrap();
+            inner.expire_builders(BUILDER_IDLE_TTL);
+            inner.targets.clone()
+        };
+        for (name, url) in targets {
+            let result = match http.get(&url).timeout(PROBE_TIMEOUT).send().await {
+                Ok(response) if response.status().is_server_error() => {
+                    Err(format!("HTTP {}", response.status()))
+                }
+                Ok(_) => Ok(()),
+                Err(e) => Err(e.to_string()),
+            };
+            self.record(&name, result);
+        }
+    }
+
+    /// Probe all targets every `interval` until shutdown.
+    pub async fn run(self: Arc<Self>, http: Client, interval: Duration, shutdown: Arc<Notify>) {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => self.probe_all(&http).await,
+                _ = shutdown.notified() => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outages_follow_probe_results() {
+        let health = DependencyHealth::new([(
+            "amd_kds".to_string(),
+            "https://kdsintf.amd.com/".to_string(),
+        )]);
+        assert_eq!(health.outage_summary(), "none");
+
+        health.record("amd_kds", Err("connection refused".to_string()));
+        health.record("intel_pcs", Ok(()));
+        let outages = health.outages();
+        assert_eq!(outages.len(), 1);
+        assert_eq!(outages[0].0, "amd_kds");
+        assert!(health.outage_summary().starts_with("amd_kds (down "));
+
+        health.record("amd_kds", Ok(()));
+        assert_eq!(health.outage_summary(), "none");
+    }
+
+    #[test]
+    fn test_track_builder_hosts() {
+        let health = DependencyHealth::default();
+        health.track_builder("https://builder.example.com/index.json");
+        health.track_builder("https://builder.example.com/other.json");
+        health.track_builder("not a url");
+        let targets = health.inner.lock().unwrap().targets.clone();
+        assert_eq!(targets.len(), 1);
+        assert_eq!(
+            targets
+                .get("builder:builder.example.com")
+                .map(String::as_str),
+            Some("https://builder.example.com/")
+        );
+    }
+
+    #[test]
+    fn test_builder_hosts_expire() {
+        let health = DependencyHealth::default();
+        for i in 0..=MAX_BUILDER_HOSTS {
+            health.track_builder(&format!("https://builder{i}.example.com/index.json"));
+        }
+        // The builder seen least recently made room for the last one
+        let mut inner = health.inner.lock().unwrap();
+        assert_eq!(inner.targets.len(), MAX_BUILDER_HOSTS);
+        assert!(!inner.targets.contains_key("builder:builder0.example.com"));
+        assert!(inner.targets.contains_key("builder:builder32.example.com"));
+
+        inner.expire_builders(Duration::ZERO);
+        assert!(inner.targets.is_empty());
+        assert!(inner.builders.is_empty());
+    }
+}
@@ -0,0 +1,225 @@
+use anyhow::{Context, Result};
+use reqwest::{Certificate, Client, Proxy, Url};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Settings for the outbound HTTP client shared by the verifiers.
+///
+/// The verification libraries' own report and collateral requests are routed through this
+/// client by the [`crate::relay::Relay`].
+#[derive(Debug, Clone)]
+pub struct HttpClientConfig {
+    /// Proxy URL all requests are routed through.
+    pub proxy: Option<String>,
+    /// PEM files with additional root certificates to trust.
+    pub ca_certs: Vec<PathBuf>,
+    /// The user agent sent with every request.
+    pub user_agent: String,
+    /// Maximum number of requests in flight to a single host.
+    pub max_connections_per_host: usize,
+    /// Timeout for establishing a connection.
+    pub connect_timeout: Duration,
+    /// How long idle connections are kept in the pool.
+    pub idle_timeout: Duration,
+}
+
+impl HttpClientConfig {
+    /// Build the HTTP client described by this configuration.
+    pub fn build(&self) -> Result<HttpClient> {
+        let mut builder = Client::builder()
+            .user_agent(self.user_agent.clone())
+            .pool_max_idle_per_host(self.max_connections_per_host)
+            .pool_idle_timeout(self.idle_timeout)
+            .connect_timeout(self.connect_timeout);
+        if let Some(proxy) = &self.proxy {
+            let proxy = Proxy::all(proxy).context("Invalid proxy URL")?;
+            builder = builder.proxy(proxy);
+        }
+        for path in &self.ca_certs {
+            let pem = std::fs::read(path).with_context(|| {
+                format!("Failed to read CA certificates from {}", path.display())
+            })?;
+            let certs = Certificate::from_pem_bundle(&pem)
+                .with_context(|| format!("Invalid CA certificates in {}", path.display()))?;
+            for cert in certs {
+                builder = builder.add_root_certificate(cert);
+            }
+        }
+        let client = builder.build().context("Failed to build HTTP client")?;
+        Ok(HttpClient::new(client, self.max_connections_per_host))
+    }
+}
+
+/// A response read in full.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Exchange {
+    pub url: String,
+    pub status: u16,
+    pub content_type: Option<String>,
+    pub body: Vec<u8>,
+}
+
+/// A response whose body is read as it arrives, holding one of its host's slots until dropped.
+#[derive(Debug)]
+pub struct Streamed {
+    response: reqwest::Response,
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+impl Streamed {
+    pub fn status(&self) -> u16 {
+        self.response.status().as_u16()
+    }
+
+    pub fn content_type(&self) -> Option<String> {
+        self.response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+    }
+
+    pub fn content_length(&self) -> Option<u64> {
+        self.response.content_length()
+    }
+
+    /// The next chunk of the body, `None` once it is read.
+    pub async fn chunk(&mut self) -> reqwest::Result<Option<impl AsRef<[u8]> + use<>>> {
+        self.response.chunk().await
+    }
+
+    /// Read the whole response to `url`.
+    pub async fn read(self, url: &str) -> reqwest::Result<Exchange> {
+        let status = self.status();
+        let content_type = self.content_type();
+        let body = self.response.bytes().await?.to_vec();
+        Ok(Exchange {
+            url: url.to_string(),
+            status,
+            content_type,
+            body,
+        })
+    }
+}
+
+/// The shared outbound HTTP client, with at most `max_connections_per_host` requests in flight to
+/// any one host. Requests over the limit wait for a slot.
+#[derive(Debug, Clone)]
+pub struct HttpClient {
+    client: Client,
+    max_connections_per_host: usize,
+    hosts: Arc<Mutex<HashMap<String, Arc<Semaphore>>>>,
+}
+
+impl HttpClient {
+    pub fn new(client: Client, max_connections_per_host: usize) -> Self {
+        Self {
+            client,
+            max_connections_per_host: max_connections_per_host.max(1),
+            hosts: Default::default(),
+        }
+    }
+
+    /// The underlying client, for requests to the node's own fixed endpoints.
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// GET `url` and read the whole response, holding one of the host's slots meanwhile.
+    pub async fn get(&self, url: &str) -> reqwest::Result<Exchange> {
+        self.send(url, &[]).await?.read(url).await
+    }
+
+    /// GET `url` with `headers`, holding one of the host's slots until the response is dropped.
+    pub async fn send(&self, url: &str, headers: &[(String, String)]) -> reqwest::Result<Streamed> {
+        let permit = self.permit(url).await;
+        let mut request = self.client.get(url);
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+        Ok(Streamed {
+            response: request.send().await?,
+            _permit: permit,
+        })
+    }
+
+    /// A connection slot of the host of `url`. Hosts without requests in flight are forgotten,
+    /// so URLs from HTX contents can't grow the map.
+    async fn permit(&self, url: &str) -> Option<OwnedSemaphorePermit> {
+        let url = Url::parse(url).ok()?;
+        let host = format!("{}:{}", url.host_str()?, url.port_or_known_default()?);
+        let semaphore = {
+            let mut hosts = self.hosts.lock().unwrap();
+            hosts.retain(|_, semaphore| Arc::strong_count(semaphore) > 1);
+            hosts
+                .entry(host)
+                .or_insert_with(|| Arc::new(Semaphore::new(self.max_connections_per_host)))
+                .clone()
+        };
+        semaphore.acquire_owned().await.ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> HttpClientConfig {
+        HttpClientConfig {
+            proxy: None,
+            ca_certs: Vec::new(),
+            user_agent: "blacklight-node/test".to_string(),
+            max_connections_per_host: 4,
+            connect_timeout: Duration::from_secs(5),
+            idle_timeout: Duration::from_secs(90),
+        }
+    }
+
+    #[test]
+    fn test_build_with_proxy() {
+        let config = HttpClientConfig {
+            proxy: Some("http://proxy.internal:3128".to_string()),
+            ..config()
+        };
+        assert!(config.build().is_ok());
+    }
+
+    #[test]
+    fn test_invalid_proxy() {
+        let config = HttpClientConfig {
+            proxy: Some("not a url".to_string()),
+            ..config()
+        };
+        assert!(config.build().is_err());
+    }
+
+    #[test]
+    fn test_missing_ca_file() {
+        let config = HttpClientConfig {
+            ca_certs: vec![PathBuf::from("/nonexistent/ca.pem")],
+            ..config()
+        };
+        assert!(config.build().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_connections_per_host() {
+        let http = HttpClient::new(Client::new(), 2);
+        let first = http.permit("https://example.com/a").await.unwrap();
+        let _second = http.permit("https://example.com:443/b").await.unwrap();
+        // The host is at its limit, other hosts are not
+        let third = http.permit("https://example.com/c");
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), third)
+                .await
+                .is_err()
+        );
+        assert!(http.permit("https://other.example.com/").await.is_some());
+
+        drop(first);
+        assert!(http.permit("https://example.com/c").await.is_some());
+    }
+}
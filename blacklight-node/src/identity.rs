@@ -0,0 +1,189 @@
+//! Operator identities run by a single node process.
+//!
+//! The identities share the node's websocket connection, event subscription and verifier. Each
+//! signs with its own wallet, so it has its own nonces and transaction lock, and keeps its own
+//! round claims, maintenance and jail state, consensus tracking, and submissions for status
+//! reporting. An identity can be stopped on its own, e.g. once its wallet runs out of ETH, while
+//! the others keep running.
+
+use crate::args::NodeConfig;
+use crate::consensus::ConsensusTracker;
+use crate::jail::JailMonitor;
+use crate::maintenance::Maintenance;
+use crate::rounds::RoundClaims;
+use crate::runway::Submissions;
+use alloy::primitives::Address;
+use anyhow::{Result, bail};
+use blacklight_contract_clients::{BlacklightClient, NonceManager};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::sync::Notify;
+
+/// An operator identity and the state that outlives reconnections.
+#[derive(Debug, Clone)]
+pub struct Identity {
+    /// The node's configuration, signing as this identity
+    pub config: NodeConfig,
+    pub address: Address,
+    pub claims: RoundClaims,
+    pub maintenance: Arc<Maintenance>,
+    pub jail: JailMonitor,
+    pub consensus: Arc<ConsensusTracker>,
+    pub submissions: Arc<Submissions>,
+    /// Notified when the node shuts down or this identity stops, ending its background tasks
+    pub shutdown: Arc<Notify>,
+    /// The number of identities of the node still running
+    running: Arc<AtomicUsize>,
+}
+
+impl Identity {
+    /// One identity per configured signer, the node's own first, each warning after
+    /// `minority_warn_threshold` consecutive minority rounds.
+    pub fn all(config: &NodeConfig, minority_warn_threshold: u64) -> Result<Vec<Self>> {
+        let configs = identity_configs(config)?;
+        let running = Arc::new(AtomicUsize::new(configs.len()));
+        configs
+            .into_iter()
+            .map(|config| {
+                let address = config.signer.address()?;
+                let claims = RoundClaims::new();
+                let maintenance = Arc::new(Maintenance::default());
+                let consensus = Arc::new(ConsensusTracker::new(minority_warn_threshold));
+                let jail = JailMonitor::new(claims.clone(), maintenance.clone());
+                Ok(Self {
+                    config,
+                    address,
+                    claims,
+                    maintenance,
+                    jail,
+                    consensus,
+                    submissions: Arc::new(Submissions::new()),
+                    shutdown: Default::default(),
+                    running: running.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Stop taking assignments and end this identity's background tasks, for good. Returns
+    /// whether it was the last identity of the node still running.
+    pub fn stop(&self) -> bool {
+        if !self.claims.stop() {
+            return false;
+        }
+        self.shutdown.notify_waiters();
+        self.running.fetch_sub(1, Ordering::SeqCst) == 1
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.claims.is_stopped()
+    }
+
+    /// Sign as this identity over `client`'s connection.
+    pub fn connect(&self, client: &Arc<BlacklightClient>) -> Result<ConnectedIdentity> {
+        let client = if client.signer_address() == self.address {
+            client.clone()
+        } else {
+            let nonces = Arc::new(NonceManager::new(self.address));
+            Arc::new(client.with_signer(self.config.signer.clone(), nonces)?)
+        };
+        Ok(ConnectedIdentity {
+            identity: self.clone(),
+            client,
+        })
+    }
+}
+
+/// An identity with a client on the node's current connection.
+#[derive(Clone)]
+pub struct ConnectedIdentity {
+    pub identity: Identity,
+    pub client: Arc<BlacklightClient>,
+}
+
+/// The identities that are among a round's `members`.
+pub fn assigned(identities: &[ConnectedIdentity], members: &[Address]) -> Vec<ConnectedIdentity> {
+    identities
+        .iter()
+        .filter(|identity| members.contains(&identity.identity.address))
+        .cloned()
+        .collect()
+}
+
+/// One configuration per identity, each signing as its identity. Fails if an identity is
+/// configured twice.
+fn identity_configs(config: &NodeConfig) -> Result<Vec<NodeConfig>> {
+    let mut addresses = HashSet::new();
+    std::iter::once(&config.signer)
+        .chain(&config.identities)
+        .enumerate()
+        .map(|(index, signer)| {
+            let address = signer.address()?;
+            if !addresses.insert(address) {
+                bail!("Operator identity {address} is configured more than once");
+            }
+            Ok(NodeConfig {
+                signer: signer.clone(),
+                identities: Vec::new(),
+                was_wallet_created: index == 0 && config.was_wallet_created,
+                ..config.clone()
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::signers::local::PrivateKeySigner;
+    use blacklight_contract_clients::{
+        DEFAULT_MAX_REPLACEMENTS, DEFAULT_RECEIPT_TIMEOUT, FeeConfig, SignerConfig,
+    };
+
+    fn signer(byte: u8) -> (SignerConfig, Address) {
+        let key = format!("0x{}", alloy::hex::encode([byte; 32]));
+        let address = key.parse::<PrivateKeySigner>().unwrap().address();
+        (SignerConfig::Local(key), address)
+    }
+
+    fn config(identities: Vec<SignerConfig>) -> NodeConfig {
+        NodeConfig {
+            rpc_url: "http://localhost:8545".to_string(),
+            manager_contract_address: Address::ZERO,
+            staking_contract_address: Address::ZERO,
+            token_contract_address: Address::ZERO,
+            signer: signer(1).0,
+            identities,
+            fees: FeeConfig::default(),
+            receipt_timeout: DEFAULT_RECEIPT_TIMEOUT,
+            max_replacements: DEFAULT_MAX_REPLACEMENTS,
+            was_wallet_created: true,
+        }
+    }
+
+    #[test]
+    fn test_identities() {
+        let identities = Identity::all(&config(vec![signer(2).0, signer(3).0]), 3).unwrap();
+        let addresses: Vec<_> = identities.iter().map(|i| i.address).collect();
+        assert_eq!(addresses, [signer(1).1, signer(2).1, signer(3).1]);
+        assert!(identities[0].config.was_wallet_created);
+        assert!(!identities[1].config.was_wallet_created);
+        assert!(identities.iter().all(|i| i.config.identities.is_empty()));
+        // Each identity compares its own verdicts against the committee
+        assert!(!Arc::ptr_eq(
+            &identities[0].consensus,
+            &identities[1].consensus
+        ));
+
+        // Stopping an identity leaves the others running
+        assert!(!identities[1].stop());
+        assert!(!identities[1].stop());
+        assert!(identities[1].is_stopped() && !identities[0].is_stopped());
+        assert!(!identities[0].stop());
+        assert!(identities[2].stop());
+
+        // An identity may not be run twice, it would race itself for nonces
+        assert!(Identity::all(&config(vec![signer(2).0, signer(1).0]), 3).is_err());
+    }
+}
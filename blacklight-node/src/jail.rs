@@ -0,0 +1,169 @@
+//! Handling of jailing by the staking contract.
+//!
+//! While the operator is jailed its verdicts would revert with `OperatorJailed`, so no new
+//! assignments are taken until the jail ends. The operator is then reactivated, unless the node
+//! is in maintenance, in which case leaving maintenance reactivates it.
+//!
+//! The cause of a jailing is read from the jailing policy's `OperatorJailed` event for the
+//! operator.
+
+use crate::args::NodeConfig;
+use crate::maintenance::Maintenance;
+use crate::rounds::RoundClaims;
+use anyhow::Result;
+use blacklight_contract_clients::BlacklightClient;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::Notify;
+use tracing::{error, info, warn};
+
+/// Whether an operator is jailed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JailStatus {
+    Free,
+    /// Jailed for another `remaining`.
+    Jailed {
+        until: u64,
+        remaining: Duration,
+    },
+}
+
+impl JailStatus {
+    /// The status of an operator whose jail ends at `jailed_until`, at unix time `now`.
+    pub fn at(jailed_until: u64, now: u64) -> Self {
+        if jailed_until > now {
+            JailStatus::Jailed {
+                until: jailed_until,
+                remaining: Duration::from_secs(jailed_until - now),
+            }
+        } else {
+            JailStatus::Free
+        }
+    }
+}
+
+/// Follows the jail status of the node's operator.
+#[derive(Debug, Clone)]
+pub struct JailMonitor {
+    claims: RoundClaims,
+    maintenance: Arc<Maintenance>,
+}
+
+impl JailMonitor {
+    pub fn new(claims: RoundClaims, maintenance: Arc<Maintenance>) -> Self {
+        Self {
+            claims,
+            maintenance,
+        }
+    }
+
+    /// Whether the node is known to be jailed.
+    pub fn is_jailed(&self) -> bool {
+        self.claims.is_suspended()
+    }
+
+    /// Check the jail status, suspending assignments when jailed and reactivating the operator
+    /// once the jail has ended.
+    pub async fn check(&self, client: &BlacklightClient) -> Result<()> {
+        let operator = client.signer_address();
+        let jailed_until = client.staking.jailed_until(operator).await?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        match JailStatus::at(jailed_until, now) {
+            JailStatus::Jailed { until, remaining } => {
+                if !self.is_jailed() {
+                    self.claims.suspend();
+                    let jailing =
+                        async { client.jailing_policy().await?.last_jailing(operator).await };
+                    match jailing.await {
+                        Ok(Some(jailing)) => warn!(
+                            until,
+                            remaining_secs = remaining.as_secs(),
+                            htx_id = ?jailing.heartbeat_key,
+                            round = jailing.round,
+                            reason = %jailing.reason,
+                            "⛓️ Operator is jailed, verdict submission suspended"
+                        ),
+                        Ok(None) => warn!(
+                            until,
+                            remaining_secs = remaining.as_secs(),
+                            cause = "unknown",
+                            "⛓️ Operator is jailed, verdict submission suspended"
+                        ),
+                        Err(e) => warn!(
+                            until,
+                            remaining_secs = remaining.as_secs(),
+                            cause = "unknown",
+                            error = %e,
+                            "⛓️ Operator is jailed, verdict submission suspended"
+                        ),
+                    }
+                }
+            }
+            JailStatus::Free if self.is_jailed() => {
+                if self.maintenance.is_active() {
+                    info!(
+                        "Jail ended while in maintenance, the operator is reactivated when leaving it"
+                    );
+                } else if !client.staking.is_active_operator(operator).await? {
+                    let tx_hash = client.staking.reactivate_operator().await?;
+                    info!(tx_hash = ?tx_hash, "Operator reactivated after jail");
+                }
+                self.claims.resume();
+                info!("⛓️ Jail ended, taking assignments again");
+            }
+            JailStatus::Free => {}
+        }
+        Ok(())
+    }
+
+    /// Check the jail status every `interval` until shutdown.
+    pub async fn run(self, config: NodeConfig, interval: Duration, shutdown: Arc<Notify>) {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.tick().await;
+        let mut client = None;
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {
+                    let result = async {
+                        if client.is_none() {
+                            client = Some(config.connect().await?);
+                        }
+                        match &client {
+                            Some(client) => self.check(client).await,
+                            None => Ok(()),
+                        }
+                    }
+                    .await;
+                    if let Err(e) = result {
+                        // Reconnect on the next check
+                        client = None;
+                        error!(error = %e, "Failed to check jail status");
+                    }
+                }
+                _ = shutdown.notified() => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jail_status() {
+        assert_eq!(JailStatus::at(0, 1_000), JailStatus::Free);
+        assert_eq!(JailStatus::at(1_000, 1_000), JailStatus::Free);
+        assert_eq!(
+            JailStatus::at(1_600, 1_000),
+            JailStatus::Jailed {
+                until: 1_600,
+                remaining: Duration::from_secs(600)
+            }
+        );
+    }
+}
@@ -1,20 +1,38 @@
-use alloy::primitives::Address;
 use alloy::primitives::utils::{format_ether, format_units};
-use anyhow::Result;
-use args::{CliArgs, NodeConfig, validate_node_requirements};
+use alloy::primitives::{Address, U256};
+use alloy::providers::DynProvider;
+use anyhow::{Context, Result};
+use args::{
+    CliArgs, Command, EvidenceCommand, KeystoreCommand, NodeConfig, OtelConfig,
+    validate_node_requirements,
+};
+use artifacts::ArtifactCache;
 use blacklight_contract_clients::{
-    BlacklightClient, ContractConfig,
-    heartbeat_manager::{RoundStartedEvent, Verdict},
+    BlacklightClient,
+    heartbeat_manager::{HeartbeatManagerClient, RoundStartedEvent, Verdict},
     htx::Htx,
 };
 use clap::Parser;
+use consensus::ConsensusTracker;
+use evidence::Evidence;
+use futures_util::future::join_all;
+use health::DependencyHealth;
+use identity::{ConnectedIdentity, Identity};
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::{MetricExporterBuilder, WithExportConfig};
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::metrics::{PeriodicReader, SdkMeterProvider};
+use relay::Relay;
+use rounds::Claim;
+use runway::{Runway, RunwayLevel, RunwayThresholds, Submissions};
+use std::env;
+use std::path::Path;
 use std::sync::Arc;
-use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Duration;
 use tokio::sync::Notify;
 use tracing::{debug, error, info, warn};
 use tracing_subscriber::{EnvFilter, fmt, prelude::*};
-use verification::HtxVerifier;
+use verification::{HtxVerifier, library_prefixes};
 
 use crate::args::MIN_ETH_BALANCE;
 
@@ -24,11 +42,27 @@ const INITIAL_RECONNECT_DELAY: Duration = Duration::from_secs(1);
 const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
 
 mod args;
+mod artifacts;
+mod consensus;
+mod evidence;
+mod health;
+mod http;
+mod identity;
+mod jail;
+mod maintenance;
+mod memo;
+mod metrics;
+mod relay;
+mod rotation;
+mod rounds;
+mod runway;
+mod staking;
+mod tape;
 mod verification;
 mod version;
 mod wallet;
 
-use version::{VERSION, validate_node_version};
+use version::{VERSION, validate_node_version, watch_node_version};
 
 // ============================================================================
 // Signal Handling
@@ -75,20 +109,174 @@ async fn setup_shutdown_handler(shutdown_notify: Arc<Notify>) {
 // Status Reporting
 // ============================================================================
 
-/// Print status information (ETH balance, staked balance, verified HTXs)
-async fn print_status(client: &BlacklightClient, verified_count: u64) -> Result<()> {
+/// Print status information (ETH balance and runway, staked balance, verified HTXs, consensus
+/// agreement), returning the ETH balance
+async fn print_status(
+    client: &BlacklightClient,
+    submissions: &Submissions,
+    consensus: &ConsensusTracker,
+) -> Result<(U256, Runway)> {
     let eth_balance = client.get_balance().await?;
     let node_address = client.signer_address();
     let staked_balance = client.staking.stake_of(node_address).await?;
+    let runway = submissions.runway(eth_balance);
 
     info!(
-        "📊 STATUS | ETH: {} | STAKED: {} NIL | Verified HTXs: {}",
+        node_address = %node_address,
+        "📊 STATUS | ETH: {} (runway: {}) | STAKED: {} NIL | Verified HTXs: {} | Agreement: {}",
         format_ether(eth_balance),
+        runway,
         format_units(staked_balance, 6)?,
-        verified_count
+        submissions.count(),
+        consensus.report().summary()
     );
 
-    Ok(())
+    Ok((eth_balance, runway))
+}
+
+/// Report status every `interval`, alerting as the ETH runway gets short.
+async fn run_status_reporter(
+    config: NodeConfig,
+    submissions: Arc<Submissions>,
+    consensus: Arc<ConsensusTracker>,
+    interval: Duration,
+    thresholds: RunwayThresholds,
+    shutdown_notify: Arc<Notify>,
+) {
+    let address = config.signer.address().unwrap_or_default();
+    let mut ticker = tokio::time::interval(interval);
+    ticker.tick().await;
+    let mut client = None;
+    let mut level = RunwayLevel::Healthy;
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {
+                let result = async {
+                    if client.is_none() {
+                        client = Some(config.connect().await?);
+                    }
+                    let client = client.as_ref().expect("client was just connected");
+                    print_status(client, &submissions, &consensus).await
+                }
+                .await;
+                let (balance, runway) = match result {
+                    Ok(status) => status,
+                    Err(e) => {
+                        // Reconnect on the next report
+                        client = None;
+                        warn!(error = %e, "Failed to fetch status information");
+                        continue;
+                    }
+                };
+
+                metrics::get().eth.set_funds(balance, address);
+                if let Runway::Estimated { remaining, .. } = runway {
+                    metrics::get().eth.set_runway(remaining, address);
+                }
+                let new_level = runway.level(&thresholds);
+                if new_level != level {
+                    alert_runway(new_level, balance, runway);
+                    level = new_level;
+                }
+            }
+            _ = shutdown_notify.notified() => break,
+        }
+    }
+}
+
+/// Stop `identity` once its ETH balance, checked after every submitted verdict, falls below the
+/// minimum needed to keep submitting, and initiate shutdown once no identity is left running.
+async fn check_min_balance(
+    client: &BlacklightClient,
+    identity: &Identity,
+    shutdown_notify: &Notify,
+) {
+    let node_address = identity.address;
+    let balance = match client.get_balance().await {
+        Ok(balance) => balance,
+        Err(e) => {
+            warn!(node_address = %node_address, error = %e, "Failed to check balance after transaction");
+            return;
+        }
+    };
+    if balance >= MIN_ETH_BALANCE || identity.is_stopped() {
+        return;
+    }
+    error!(
+        node_address = %node_address,
+        balance = %format_ether(balance),
+        min_required = %format_ether(MIN_ETH_BALANCE),
+        "⚠️ ETH balance below minimum threshold. Stopping the identity..."
+    );
+    let last = identity.stop();
+    // Leave the committee rather than be assigned rounds that go unanswered
+    match client.staking.deactivate_operator().await {
+        Ok(tx_hash) => {
+            info!(node_address = %node_address, tx_hash = ?tx_hash, "Stopped identity deactivated")
+        }
+        Err(e) => {
+            error!(node_address = %node_address, error = %e, "Failed to deactivate stopped identity")
+        }
+    }
+    if last {
+        error!("No identity left running. Initiating shutdown...");
+        shutdown_notify.notify_waiters();
+    }
+}
+
+/// Log a change of the ETH runway alert level
+fn alert_runway(level: RunwayLevel, balance: U256, runway: Runway) {
+    let spend_per_day = match runway {
+        Runway::Estimated { wei_per_day, .. } => format_ether(U256::from(wei_per_day as u128)),
+        Runway::Unknown => "n/a".to_string(),
+    };
+    match level {
+        RunwayLevel::Healthy => info!(
+            balance = %format_ether(balance),
+            runway = %runway,
+            "💰 ETH runway is sufficient again"
+        ),
+        RunwayLevel::Low => warn!(
+            balance = %format_ether(balance),
+            runway = %runway,
+            spend_per_day = %spend_per_day,
+            "⚠️ ETH runway is getting short, please top up the node wallet"
+        ),
+        RunwayLevel::Critical => error!(
+            balance = %format_ether(balance),
+            runway = %runway,
+            spend_per_day = %spend_per_day,
+            min_required = %format_ether(MIN_ETH_BALANCE),
+            "🚨 ETH runway is critically short, the node shuts down below the minimum balance"
+        ),
+    }
+}
+
+/// Configure the global meter provider to export metrics to an OTEL collector
+fn setup_otel(config: &OtelConfig) -> Result<SdkMeterProvider> {
+    let service_name =
+        env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| env!("CARGO_PKG_NAME").to_string());
+    let attributes = vec![KeyValue::new("service.version", VERSION)];
+    let resource = Resource::builder()
+        .with_service_name(service_name)
+        .with_attributes(attributes)
+        .build();
+    let exporter = MetricExporterBuilder::new()
+        .with_tonic()
+        .with_endpoint(config.endpoint.clone())
+        .with_timeout(config.export_timeout)
+        .build()
+        .context("Failed to build metrics exporter")?;
+
+    let reader = PeriodicReader::builder(exporter)
+        .with_interval(config.export_interval)
+        .build();
+    let provider = SdkMeterProvider::builder()
+        .with_resource(resource)
+        .with_reader(reader)
+        .build();
+    opentelemetry::global::set_meter_provider(provider.clone());
+    Ok(provider)
 }
 
 // ============================================================================
@@ -96,34 +284,65 @@ async fn print_status(client: &BlacklightClient, verified_count: u64) -> Result<
 // ============================================================================
 
 /// Process a single HTX assignment - verifies and submits result
+///
+/// In shadow mode the verdict is only recorded in `consensus` and never submitted.
 async fn process_htx_assignment(
     client: Arc<BlacklightClient>,
     event: RoundStartedEvent,
     verifier: &HtxVerifier,
-    verified_counter: Arc<AtomicU64>,
-    shutdown_notify: Arc<Notify>,
+    submissions: Arc<Submissions>,
     node_address: Address,
+    consensus: Arc<ConsensusTracker>,
+    shadow: bool,
 ) -> Result<()> {
     let htx_id = event.heartbeatKey;
+    let round = event.round;
+    consensus.round_started(htx_id, round);
+    let mut timer = verifier.start_timer(event.deadline);
+    if timer.remaining().is_zero() {
+        warn!(htx_id = ?htx_id, deadline = event.deadline, "Round deadline leaves no time for verification");
+    }
     // Parse the HTX data - UnifiedHtx automatically detects provider field
-    let verification_result = match serde_json::from_slice::<Htx>(&event.rawHTX) {
-        Ok(htx) => match htx {
-            Htx::Nillion(htx) => {
-                info!(htx_id = ?htx_id, "Detected nilCC HTX");
-                verifier.verify_nillion_htx(&htx).await
-            }
-            Htx::Phala(htx) => {
-                info!(htx_id = ?htx_id, "Detected Phala HTX");
-                verifier.verify_phala_htx(&htx).await
+    let parsed = serde_json::from_slice::<Htx>(&event.rawHTX);
+    let mut evidence = Evidence::default();
+    let (verification_result, fresh) = match parsed {
+        // Identities assigned the same HTX share a single verification
+        Ok(htx) => {
+            let verify = async {
+                match htx {
+                    Htx::Nillion(htx) => {
+                        info!(htx_id = ?htx_id, "Detected nilCC HTX");
+                        verifier
+                            .verify_nillion_htx(&htx, &mut timer, &mut evidence)
+                            .await
+                    }
+                    Htx::Phala(htx) => {
+                        info!(htx_id = ?htx_id, "Detected Phala HTX");
+                        verifier
+                            .verify_phala_htx(&htx, &mut timer, &mut evidence)
+                            .await
+                    }
+                }
+            };
+            let (result, fresh) = verifier.verify_shared(&event.rawHTX, verify).await;
+            if !fresh {
+                info!(htx_id = ?htx_id, node_address = %node_address, "Reusing verdict of a verification of the same HTX");
             }
-        },
+            (result, fresh)
+        }
         Err(e) => {
             error!(htx_id = ?htx_id, error = %e, "Failed to parse HTX data");
+            if shadow {
+                consensus.record_verdict(htx_id, round, Verdict::Failure);
+                info!(htx_id = ?htx_id, round, verdict = "failure", "🕶️ SHADOW verdict recorded");
+                return Ok(());
+            }
             // If we parse invalid data, it could be a malicious node, so Failure and it doesn't get rewarded
             client
                 .manager
                 .respond_htx(event, Verdict::Failure, node_address)
                 .await?;
+            consensus.record_verdict(htx_id, round, Verdict::Failure);
             info!(htx_id = ?htx_id, "✅ HTX verification submitted");
             return Ok(());
         }
@@ -132,80 +351,92 @@ async fn process_htx_assignment(
         Ok(_) => Verdict::Success,
         Err(ref e) => e.verdict(),
     };
+    // Evidence of fresh Failure verdicts is written once the verdict is out
+    let evidence = match &verification_result {
+        Err(e) if fresh && verdict == Verdict::Failure => {
+            Some((evidence, event.rawHTX.to_vec(), e.message()))
+        }
+        _ => None,
+    };
+    let save_evidence = |evidence: Option<(Evidence, Vec<u8>, String)>| {
+        if let Some((evidence, raw_htx, error)) = evidence {
+            verifier.save_evidence(evidence, htx_id, round, node_address, raw_htx, error);
+        }
+    };
+
+    // In shadow mode the verdict is only recorded for the agreement report
+    if shadow {
+        consensus.record_verdict(htx_id, round, verdict);
+        save_evidence(evidence);
+        match verification_result {
+            Ok(_) => {
+                info!(htx_id = ?htx_id, round, verdict = "success", timings = %timer, "🕶️ SHADOW verdict recorded")
+            }
+            Err(e) => {
+                info!(htx_id = ?htx_id, round, verdict = ?verdict, error = %e, timings = %timer, "🕶️ SHADOW verdict recorded")
+            }
+        }
+        return Ok(());
+    }
 
     // Submit the verification result
-    match client
+    let submitted = client
         .manager
         .respond_htx(event, verdict, node_address)
-        .await
-    {
+        .await;
+    save_evidence(evidence);
+    match submitted {
         Ok(tx_hash) => {
-            let count = verified_counter.fetch_add(1, Ordering::SeqCst) + 1;
+            consensus.record_verdict(htx_id, round, verdict);
 
             match (verdict, verification_result) {
                 (Verdict::Success, Ok(_)) => {
-                    info!(tx_hash=?tx_hash, "✅ VALID HTX verification submitted");
+                    info!(tx_hash=?tx_hash, timings=%timer, "✅ VALID HTX verification submitted");
                 }
                 (Verdict::Failure, Err(e)) => {
-                    info!(tx_hash=?tx_hash, error=?e, verdict="failure", "❌ INVALID HTX verification submitted");
+                    info!(tx_hash=?tx_hash, error=?e, verdict="failure", timings=%timer, "❌ INVALID HTX verification submitted");
                 }
                 (Verdict::Inconclusive, Err(e)) => {
-                    info!(tx_hash=?tx_hash, error=?e, verdict="inconclusive", "⚠️ INCONCLUSIVE HTX verification submitted");
+                    let outages = verifier.health().outage_summary();
+                    info!(tx_hash=?tx_hash, error=?e, verdict="inconclusive", timings=%timer, outages=%outages, "⚠️ INCONCLUSIVE HTX verification submitted");
                 }
                 (_, _) => {
                     error!(tx_hash=?tx_hash, verdict=?verdict, "Unexpected verification state");
                 }
             }
 
-            if let Err(e) = print_status(&client, count).await {
-                warn!(error = %e, "Failed to fetch status information");
-            }
-
-            // Validate node version against protocol requirement and initiate shutdown if incompatible
-            if let Err(e) = validate_node_version(&client).await {
-                error!(error = %e, "Node version validation failed. Initiating shutdown...");
-                shutdown_notify.notify_waiters();
-                return Err(anyhow::anyhow!(
-                    "Node version is incompatible with protocol requirement"
-                ));
-            }
-
-            // Check if balance is below minimum threshold
-            match client.get_balance().await {
-                Ok(balance) => {
-                    if balance < MIN_ETH_BALANCE {
-                        error!(
-                            balance = %format_ether(balance),
-                            min_required = %format_ether(MIN_ETH_BALANCE),
-                            "⚠️ ETH balance below minimum threshold. Initiating shutdown..."
-                        );
-                        shutdown_notify.notify_waiters();
-                        return Err(anyhow::anyhow!("Insufficient ETH balance"));
-                    }
+            // Track what verdicts cost for the runway estimate
+            let cost = match client.get_transaction_cost(tx_hash).await {
+                Ok(cost) => {
+                    metrics::get().eth.add_spent(cost, node_address);
+                    Some(cost)
                 }
                 Err(e) => {
-                    warn!(error = %e, "Failed to check balance after transaction");
+                    debug!(tx_hash = ?tx_hash, error = %e, "Failed to get verdict submission cost");
+                    None
                 }
-            }
+            };
+            submissions.record(cost);
 
             Ok(())
         }
         Err(e) => {
             error!(htx_id = ?htx_id, error = %e, "Failed to respond to HTX");
-            Err(e)
+            Err(e.into())
         }
     }
 }
 
-/// Process backlog of historical assignments
+/// Process backlog of historical assignments of an identity
 async fn process_assignment_backlog(
-    client: Arc<BlacklightClient>,
-    node_address: Address,
+    identity: &ConnectedIdentity,
     verifier: &HtxVerifier,
-    verified_counter: Arc<AtomicU64>,
-    shutdown_notify: Arc<Notify>,
+    shutdown_notify: &Arc<Notify>,
 ) -> Result<()> {
-    info!("Checking for pending assignments from before connection");
+    let ConnectedIdentity { identity, client } = identity;
+    let node_address = identity.address;
+    let claims = &identity.claims;
+    info!(node_address = %node_address, "Checking for pending assignments from before connection");
 
     let assigned_events = client.manager.get_htx_assigned_events().await?;
     let pending: Vec<_> = assigned_events
@@ -225,30 +456,55 @@ async fn process_assignment_backlog(
 
     for event in pending {
         let htx_id = event.heartbeatKey;
+        let round = event.round;
 
-        // Check if already responded
-        match client.manager.get_node_vote(htx_id, node_address).await {
-            Ok(Some(_)) => {
-                debug!(htx_id = ?htx_id, "Already responded HTX, skipping");
+        // Check if already responded in this round
+        match client
+            .manager
+            .get_node_vote(htx_id, round, node_address)
+            .await
+        {
+            Ok(vote) if vote.has_voted() => {
+                debug!(htx_id = ?htx_id, round, "Already responded HTX, skipping");
             }
-            Ok(None) => {
-                info!(htx_id = ?htx_id, "📥 HTX received (backlog)");
+            Ok(_) => {
+                let guard = match claims.claim(htx_id, round, event.deadline) {
+                    Claim::Claimed(guard) => guard,
+                    Claim::AlreadyClaimed => {
+                        debug!(htx_id = ?htx_id, round, "HTX round already being processed, skipping");
+                        continue;
+                    }
+                    Claim::Closed | Claim::Suspended => {
+                        info!("Not taking new assignments, leaving the rest of the backlog");
+                        break;
+                    }
+                };
+                info!(htx_id = ?htx_id, round, "📥 HTX received (backlog)");
                 let client_clone = client.clone();
                 let verifier = verifier.clone();
-                let counter = verified_counter.clone();
-                let shutdown_clone = shutdown_notify.clone();
+                let submissions = identity.submissions.clone();
+                let consensus = identity.consensus.clone();
+                let identity = identity.clone();
+                let shutdown_notify = shutdown_notify.clone();
                 tokio::spawn(async move {
-                    if let Err(e) = process_htx_assignment(
-                        client_clone,
+                    match process_htx_assignment(
+                        client_clone.clone(),
                         event,
                         &verifier,
-                        counter,
-                        shutdown_clone,
+                        submissions,
                         node_address,
+                        consensus,
+                        false,
                     )
                     .await
                     {
-                        error!(htx_id = ?htx_id, error = %e, "Failed to process pending HTX");
+                        Ok(()) => {
+                            check_min_balance(&client_clone, &identity, &shutdown_notify).await
+                        }
+                        Err(e) => {
+                            guard.release();
+                            error!(htx_id = ?htx_id, round, error = %e, "Failed to process pending HTX");
+                        }
                     }
                 });
             }
@@ -266,19 +522,41 @@ async fn process_assignment_backlog(
 // Node Registration
 // ============================================================================
 
-/// Register node with the contract if not already registered
-async fn register_node_if_needed(client: &BlacklightClient, node_address: Address) -> Result<()> {
+/// Register node with the contract if not already registered, and update its metadata URI if
+/// it changed
+async fn register_node_if_needed(
+    client: &BlacklightClient,
+    node_address: Address,
+    metadata_uri: &str,
+) -> Result<()> {
     info!(node_address = %node_address, "Checking node registration");
 
     let is_registered = client.staking.is_active_operator(node_address).await?;
 
     if is_registered {
         info!("Node already registered");
+        if metadata_uri.is_empty() {
+            return Ok(());
+        }
+        match client.staking.metadata_uri(node_address).await {
+            Ok(registered) if registered == metadata_uri => {}
+            Ok(_) => {
+                let tx_hash = client
+                    .staking
+                    .register_operator(metadata_uri.to_string())
+                    .await?;
+                info!(tx_hash = ?tx_hash, "📇 Operator metadata updated");
+            }
+            Err(e) => warn!(error = %e, "Failed to read the registered operator metadata"),
+        }
         return Ok(());
     }
 
     info!("Registering node with contract");
-    let tx_hash = client.staking.register_operator("".to_string()).await?;
+    let tx_hash = client
+        .staking
+        .register_operator(metadata_uri.to_string())
+        .await?;
     info!(tx_hash = ?tx_hash, "Node registered successfully");
 
     Ok(())
@@ -296,16 +574,11 @@ async fn create_client_with_retry(
     let mut reconnect_delay = INITIAL_RECONNECT_DELAY;
     let max_reconnect_delay = MAX_RECONNECT_DELAY;
 
-    let contract_config = ContractConfig::new(
-        config.rpc_url.clone(),
-        config.manager_contract_address,
-        config.staking_contract_address,
-        config.token_contract_address,
-    );
+    let contract_config = config.verdict_contract_config();
 
     loop {
         let client_result =
-            BlacklightClient::new(contract_config.clone(), config.private_key.clone()).await;
+            BlacklightClient::new(contract_config.clone(), config.signer.clone()).await;
 
         match client_result {
             Ok(client) => {
@@ -334,74 +607,238 @@ async fn create_client_with_retry(
 // Event Listening
 // ============================================================================
 
-/// Listen for HTX assignment events and process them
+/// Listen for HTX assignment events and process them for every assigned identity
 async fn run_event_listener(
     client: Arc<BlacklightClient>,
-    node_address: Address,
+    identities: Arc<[ConnectedIdentity]>,
     shutdown_notify: Arc<Notify>,
     verifier: &HtxVerifier,
-    verified_counter: Arc<AtomicU64>,
 ) -> Result<()> {
-    let client_for_callback = client.clone();
-    let counter_for_callback = verified_counter.clone();
+    let identities_for_callback = identities.clone();
     let shutdown_for_callback = shutdown_notify.clone();
 
     let manager = Arc::new(client.manager.clone());
-    let listen_future = manager.listen_htx_assigned_for_node(node_address, move |event| {
-        let client = client_for_callback.clone();
-        let counter = counter_for_callback.clone();
-        let shutdown_clone = shutdown_for_callback.clone();
+    let listen_future = manager.clone().listen_htx_assigned_events(move |event| {
+        let assigned = identity::assigned(&identities_for_callback, &event.members);
+        let verifier = verifier.clone();
+        let shutdown_notify = shutdown_for_callback.clone();
 
         async move {
-            let htx_id = event.heartbeatKey;
-            let node_addr = client.signer_address();
-            let verifier = verifier.clone();
-            tokio::spawn(async move {
-                // Check if already responded
-                match client.manager.get_node_vote(htx_id, node_addr).await {
-                    Ok(Some(_)) => (),
-                    Ok(None) => {
-                        info!(htx_id = ?htx_id, "📥 HTX received");
-                        if let Err(e) = process_htx_assignment(
-                            client,
-                            event,
-                            &verifier,
-                            counter,
-                            shutdown_clone,
-                            node_address,
-                        )
-                        .await
-                        {
-                            error!(htx_id = ?htx_id, error = %e, "Failed to process real-time HTX");
-                        }
-                    }
-                    Err(e) => {
-                        error!(htx_id = ?htx_id, error = %e, "Failed to get assignment for HTX");
-                    }
-                }
-            });
-
+            // The identities share the verification of the HTX and submit concurrently
+            for identity in assigned {
+                tokio::spawn(handle_assignment(
+                    identity,
+                    event.clone(),
+                    verifier.clone(),
+                    shutdown_notify.clone(),
+                ));
+            }
             Ok(())
         }
     });
 
+    let trackers = identities
+        .iter()
+        .map(|identity| {
+            (
+                identity.identity.address,
+                identity.identity.consensus.clone(),
+            )
+        })
+        .collect();
+
     // Listen for either events or shutdown signal
     tokio::select! {
         result = listen_future => {
             result?;
             Ok(())
         },
+        result = track_consensus(manager, trackers) => result,
+        result = watch_node_version(client, shutdown_notify.clone()) => result,
         _ = shutdown_notify.notified() => {
             info!("Shutdown signal received during event listening");
-            Err(anyhow::anyhow!("Shutdown requested"))
+            Err(ShutdownRequested.into())
         }
     }
 }
 
+/// Verify and answer a real-time assignment as `identity`, unless it already voted in the round
+async fn handle_assignment(
+    identity: ConnectedIdentity,
+    event: RoundStartedEvent,
+    verifier: HtxVerifier,
+    shutdown_notify: Arc<Notify>,
+) {
+    let ConnectedIdentity { identity, client } = identity;
+    let htx_id = event.heartbeatKey;
+    let round = event.round;
+    let node_address = identity.address;
+
+    // Check if already responded in this round
+    match client
+        .manager
+        .get_node_vote(htx_id, round, node_address)
+        .await
+    {
+        Ok(vote) if vote.has_voted() => (),
+        Ok(_) => {
+            let guard = match identity.claims.claim(htx_id, round, event.deadline) {
+                Claim::Claimed(guard) => guard,
+                Claim::AlreadyClaimed => {
+                    debug!(htx_id = ?htx_id, round, node_address = %node_address, "HTX round already being processed, skipping");
+                    return;
+                }
+                Claim::Closed => {
+                    info!(htx_id = ?htx_id, round, node_address = %node_address, "Not taking new assignments, skipping");
+                    return;
+                }
+                Claim::Suspended => {
+                    warn!(htx_id = ?htx_id, round, node_address = %node_address, "⛓️ Node is jailed, skipping assignment");
+                    return;
+                }
+            };
+            info!(htx_id = ?htx_id, round, node_address = %node_address, "📥 HTX received");
+            match process_htx_assignment(
+                client.clone(),
+                event,
+                &verifier,
+                identity.submissions.clone(),
+                node_address,
+                identity.consensus.clone(),
+                false,
+            )
+            .await
+            {
+                Ok(()) => check_min_balance(&client, &identity, &shutdown_notify).await,
+                Err(e) => {
+                    guard.release();
+                    error!(htx_id = ?htx_id, round, node_address = %node_address, error = %e, "Failed to process real-time HTX");
+                }
+            }
+        }
+        Err(e) => {
+            error!(htx_id = ?htx_id, node_address = %node_address, error = %e, "Failed to get assignment for HTX");
+        }
+    }
+}
+
+/// Follow the other members' votes and the round outcomes of the rounds each identity takes
+/// part in, with the identities' addresses and consensus trackers
+async fn track_consensus(
+    manager: Arc<HeartbeatManagerClient<DynProvider>>,
+    trackers: Arc<[(Address, Arc<ConsensusTracker>)]>,
+) -> Result<()> {
+    let votes_trackers = trackers.clone();
+    let votes_future = manager.clone().listen_htx_responded_events(move |event| {
+        for (address, consensus) in votes_trackers.iter() {
+            if *address != event.operator {
+                consensus.observe_vote(event.heartbeatKey, event.round, event.verdict);
+            }
+        }
+        async { Ok(()) }
+    });
+
+    let finalized_future = manager.listen_round_finalized_events(move |event| {
+        for (_, consensus) in trackers.iter() {
+            consensus.observe_finalized(event.heartbeatKey, event.round, event.outcome);
+        }
+        async { Ok(()) }
+    });
+
+    tokio::select! {
+        result = votes_future => result,
+        result = finalized_future => result,
+    }
+}
+
+/// Listen for rounds in shadow mode, verifying them and tracking how the network votes
+async fn run_shadow_listener(
+    client: Arc<BlacklightClient>,
+    node_address: Address,
+    shutdown_notify: Arc<Notify>,
+    verifier: &HtxVerifier,
+    consensus: Arc<ConsensusTracker>,
+    all_rounds: bool,
+) -> Result<()> {
+    let manager = Arc::new(client.manager.clone());
+
+    let started_client = client.clone();
+    let started_consensus = consensus.clone();
+    let rounds_future = manager.clone().listen_htx_assigned_events(move |event| {
+        let client = started_client.clone();
+        let consensus = started_consensus.clone();
+        let verifier = verifier.clone();
+
+        async move {
+            if !all_rounds && !event.members.contains(&node_address) {
+                return Ok(());
+            }
+            let htx_id = event.heartbeatKey;
+            info!(htx_id = ?htx_id, round = event.round, "📥 HTX received (shadow)");
+            tokio::spawn(async move {
+                if let Err(e) = process_htx_assignment(
+                    client,
+                    event,
+                    &verifier,
+                    Arc::new(Submissions::new()),
+                    node_address,
+                    consensus,
+                    true,
+                )
+                .await
+                {
+                    error!(htx_id = ?htx_id, error = %e, "Failed to process HTX in shadow mode");
+                }
+            });
+            Ok(())
+        }
+    });
+
+    tokio::select! {
+        result = rounds_future => result,
+        result = track_consensus(manager, Arc::new([(node_address, consensus)])) => result,
+        result = watch_node_version(client, shutdown_notify.clone()) => result,
+        _ = shutdown_notify.notified() => {
+            info!("Shutdown signal received during event listening");
+            Err(ShutdownRequested.into())
+        }
+    }
+}
+
+/// Log the agreement report of every identity, and write them to `path` if one is configured
+fn emit_agreement_report(identities: &[Identity], path: Option<&Path>) {
+    let mut contents = String::new();
+    for identity in identities {
+        let report = identity.consensus.report();
+        info!(node_address = %identity.address, "📊 {}", report.to_string().trim_end());
+        if identities.len() > 1 {
+            contents.push_str(&format!("operator {}\n", identity.address));
+        }
+        contents.push_str(&report.to_string());
+    }
+    if let Some(path) = path
+        && let Err(e) = std::fs::write(path, contents)
+    {
+        warn!(path = %path.display(), error = %e, "Failed to write agreement report");
+    }
+}
+
 // ============================================================================
 // Shutdown
 // ============================================================================
 
+/// Error ending the event listener because the node is shutting down
+#[derive(Debug)]
+pub struct ShutdownRequested;
+
+impl std::fmt::Display for ShutdownRequested {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Shutdown requested")
+    }
+}
+
+impl std::error::Error for ShutdownRequested {}
+
 /// Deactivate node from contract on shutdown
 async fn deactivate_node_on_shutdown(
     config: &NodeConfig,
@@ -416,14 +853,7 @@ async fn deactivate_node_on_shutdown(
 
     info!(node_address = %addr, "Deactivating node from contract");
 
-    let contract_config = ContractConfig::new(
-        config.rpc_url.clone(),
-        config.manager_contract_address,
-        config.staking_contract_address,
-        config.token_contract_address,
-    );
-
-    let client = BlacklightClient::new(contract_config, config.private_key.clone()).await?;
+    let client = BlacklightClient::new(config.contract_config(), config.signer.clone()).await?;
     let tx_hash = client.staking.deactivate_operator().await?;
     info!(tx_hash = ?tx_hash, "Node deactivated successfully");
 
@@ -457,33 +887,153 @@ async fn main() -> Result<()> {
         .init();
 
     // Load configuration
-    let cli_args = CliArgs::parse();
-    let verifier = HtxVerifier::new(cli_args.artifact_cache.clone(), cli_args.cert_cache.clone())?;
+    let mut cli_args = CliArgs::parse();
+    let artifacts = Arc::new(ArtifactCache::new(
+        cli_args.artifact_cache.clone(),
+        cli_args.artifact_manifest()?,
+    ));
+    match cli_args.command.take() {
+        Some(Command::Artifacts(command)) => return artifacts::run_command(&artifacts, command),
+        Some(Command::Evidence(EvidenceCommand::Verify { path })) => {
+            return evidence::run_verify(&path);
+        }
+        Some(Command::Keystore(KeystoreCommand::Migrate)) => {
+            return args::migrate_keystore(&cli_args.keys);
+        }
+        Some(Command::Mnemonic(command)) => {
+            return args::run_mnemonic_command(&cli_args.mnemonic, command);
+        }
+        Some(Command::Staking(command)) => {
+            let config = NodeConfig::load(cli_args).await?;
+            let client = config.connect().await?;
+            return staking::run_command(&client, command).await;
+        }
+        Some(Command::Metadata) => return args::print_metadata(&cli_args.metadata).await,
+        Some(Command::RotateKey {
+            allow_plaintext_key,
+        }) => {
+            let keys = cli_args.keys.clone();
+            let mnemonic = cli_args.mnemonic.clone();
+            let metadata_uri = cli_args.metadata.metadata_uri().await?;
+            let config = NodeConfig::load(cli_args).await?;
+            return rotation::run(
+                &config,
+                &keys,
+                &mnemonic,
+                &metadata_uri,
+                allow_plaintext_key,
+            )
+            .await;
+        }
+        Some(Command::Tape(command)) => {
+            return tape::run_command(
+                artifacts,
+                &cli_args.artifacts_url,
+                cli_args.timeouts.verification_timeouts(),
+                cli_args.http.client_config().build()?,
+                command,
+            )
+            .await;
+        }
+        None => {}
+    }
+    let http_client = cli_args.http.client_config().build()?;
+    let health = Arc::new(DependencyHealth::new([
+        ("amd_kds".to_string(), cli_args.health.amd_kds_url.clone()),
+        (
+            "intel_pcs".to_string(),
+            cli_args.health.intel_pcs_url.clone(),
+        ),
+        ("artifacts".to_string(), cli_args.artifacts_url.clone()),
+    ]));
+    let probe_interval = Duration::from_secs(cli_args.health.health_probe_interval_secs);
+    let relay = Relay::start(
+        http_client.clone(),
+        library_prefixes(&cli_args.artifacts_url),
+    )
+    .await?;
+    let verifier = HtxVerifier::new(
+        artifacts,
+        relay.rewrite(&cli_args.artifacts_url),
+        cli_args.cert_cache.clone(),
+        cli_args.timeouts.verification_timeouts(),
+        http_client.clone(),
+    )?
+    .with_relay(relay)
+    .with_health(health.clone())
+    .with_evidence_dir(cli_args.evidence_dir.clone())
+    .with_caches(
+        cli_args.caches.measurement_cache_size,
+        Duration::from_secs(cli_args.caches.verdict_cache_ttl_secs),
+    );
+    let prefetch_versions = cli_args.prefetch_artifacts.clone();
+    let shadow_args = cli_args.shadow.clone();
+    let consensus_args = cli_args.consensus.clone();
+    let shutdown_args = cli_args.shutdown.clone();
+    let jail_args = cli_args.jail.clone();
+    let status_args = cli_args.status.clone();
+    let metadata_uri = cli_args.metadata.metadata_uri().await?;
+    let otel_config = cli_args.metrics.otel_config();
     let config = NodeConfig::load(cli_args).await?;
 
     // Create initial client to validate requirements
-    let contract_config = ContractConfig::new(
-        config.rpc_url.clone(),
-        config.manager_contract_address,
-        config.staking_contract_address,
-        config.token_contract_address,
-    );
     let validation_client =
-        BlacklightClient::new(contract_config, config.private_key.clone()).await?;
+        Arc::new(BlacklightClient::new(config.contract_config(), config.signer.clone()).await?);
 
-    // Validate node has sufficient ETH and staked NIL tokens
-    validate_node_requirements(
-        &validation_client,
-        &config.rpc_url,
-        config.was_wallet_created,
-    )
-    .await?;
+    // The shadow node verifies as its own identity only
+    let mut identities = Identity::all(&config, consensus_args.minority_warn_threshold)?;
+    if shadow_args.shadow {
+        identities.truncate(1);
+    }
+
+    // Validate every identity has sufficient ETH and staked NIL tokens, a shadow node needs
+    // neither
+    if !shadow_args.shadow {
+        for identity in &identities {
+            let connected = identity.connect(&validation_client)?;
+            validate_node_requirements(
+                &connected.client,
+                &config.rpc_url,
+                identity.config.was_wallet_created,
+            )
+            .await?;
+        }
+    }
+    if identities.len() > 1 {
+        info!(
+            count = identities.len(),
+            "Running multiple operator identities"
+        );
+    }
 
     // Validate node version against protocol requirement
     validate_node_version(&validation_client).await?;
 
+    verifier.prefetch_artifacts(&prefetch_versions).await;
+
+    let metrics = match &otel_config {
+        Some(config) => {
+            info!("Exporting metrics to {}", config.endpoint);
+            let handle = setup_otel(config).context("Failed to configure metrics")?;
+            Some(handle)
+        }
+        None => {
+            info!("Metric exports disabled");
+            None
+        }
+    };
+
     info!(version = VERSION, "Node initialized");
-    info!("Press Ctrl+C to gracefully shutdown and deactivate");
+    if shadow_args.shadow {
+        info!(
+            all_rounds = shadow_args.shadow_all_rounds,
+            "🕶️ Running in shadow mode: verdicts are recorded but not submitted"
+        );
+    } else {
+        info!(
+            "Press Ctrl+C to gracefully shutdown, send SIGUSR1/SIGUSR2 to enter/leave maintenance"
+        );
+    }
 
     // Setup graceful shutdown handler
     let shutdown_notify = Arc::new(Notify::new());
@@ -492,11 +1042,73 @@ async fn main() -> Result<()> {
         setup_shutdown_handler(shutdown_notify_clone).await;
     });
 
-    // Counter for verified HTXs (for status reporting)
-    let verified_counter = Arc::new(AtomicU64::new(0));
+    // Probe the services verification depends on in the background
+    if !probe_interval.is_zero() {
+        tokio::spawn(health.run(
+            http_client.client().clone(),
+            probe_interval,
+            shutdown_notify.clone(),
+        ));
+    }
+
+    // Periodically report how our verdicts compare to the rest of the committee
+    if consensus_args.agreement_report_interval_secs > 0 {
+        let identities = identities.clone();
+        let interval = Duration::from_secs(consensus_args.agreement_report_interval_secs);
+        let path = consensus_args.agreement_report_path.clone();
+        let shutdown = shutdown_notify.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await;
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => emit_agreement_report(&identities, path.as_deref()),
+                    _ = shutdown.notified() => break,
+                }
+            }
+        });
+    }
+
+    // Each identity drains the rounds it is processing before shutting down or entering
+    // maintenance, follows its jail status and reports its own status, until the node shuts down
+    // or the identity stops
+    let drain_timeout = Duration::from_secs(shutdown_args.drain_timeout_secs);
+    if !shadow_args.shadow {
+        for identity in &identities {
+            let (node_shutdown, identity_shutdown) =
+                (shutdown_notify.clone(), identity.shutdown.clone());
+            tokio::spawn(async move {
+                node_shutdown.notified().await;
+                identity_shutdown.notify_waiters();
+            });
+            tokio::spawn(identity.maintenance.clone().run(
+                identity.config.clone(),
+                identity.claims.clone(),
+                drain_timeout,
+                identity.shutdown.clone(),
+            ));
+            if jail_args.jail_check_interval_secs > 0 {
+                tokio::spawn(identity.jail.clone().run(
+                    identity.config.clone(),
+                    Duration::from_secs(jail_args.jail_check_interval_secs),
+                    identity.shutdown.clone(),
+                ));
+            }
+            if status_args.status_interval_secs > 0 {
+                tokio::spawn(run_status_reporter(
+                    identity.config.clone(),
+                    identity.submissions.clone(),
+                    identity.consensus.clone(),
+                    Duration::from_secs(status_args.status_interval_secs),
+                    status_args.runway_thresholds(),
+                    identity.shutdown.clone(),
+                ));
+            }
+        }
+    }
 
     // Main reconnection loop
-    let mut node_address: Option<Address> = None;
+    let mut registered = false;
     let mut reconnect_delay = INITIAL_RECONNECT_DELAY;
     let max_reconnect_delay = MAX_RECONNECT_DELAY;
 
@@ -505,50 +1117,79 @@ async fn main() -> Result<()> {
 
         // Create client with retry logic
         let client = match create_client_with_retry(&config, &shutdown_notify).await {
-            Ok(client) => client,
+            Ok(client) => Arc::new(client),
             Err(_) => break, // Shutdown requested or unrecoverable error
         };
 
-        let current_address = client.signer_address();
-        node_address = Some(current_address);
+        let listener_result = if shadow_args.shadow {
+            // Shadow mode neither registers nor picks up past assignments
+            let current_address = client.signer_address();
+            run_shadow_listener(
+                client,
+                current_address,
+                shutdown_notify.clone(),
+                &verifier,
+                identities[0].consensus.clone(),
+                shadow_args.shadow_all_rounds,
+            )
+            .await
+        } else {
+            // Every identity signs over the same connection, with its own nonces
+            let connected = match identities
+                .iter()
+                .map(|identity| identity.connect(&client))
+                .collect::<Result<Vec<_>>>()
+            {
+                Ok(connected) => connected,
+                Err(e) => {
+                    error!(error = %e, "Failed to set up the operator identities");
+                    std::process::exit(1);
+                }
+            };
+            registered = true;
 
-        // Register node if needed
-        if let Err(e) = register_node_if_needed(&client, current_address).await {
-            error!(error = %e, reconnect_delay = ?reconnect_delay, "Failed to register node. Retrying...");
+            for identity in &connected {
+                let ConnectedIdentity { identity, client } = identity;
+                if let Err(e) = identity.jail.check(client).await {
+                    warn!(node_address = %identity.address, error = %e, "Failed to check jail status");
+                }
 
-            // Exit the loop
-            std::process::exit(1);
-        }
+                // Register node if needed, a node stopped, in maintenance or in jail stays
+                // deactivated
+                if identity.is_stopped() {
+                    info!(node_address = %identity.address, "Identity is stopped, not taking assignments");
+                } else if identity.maintenance.is_active() {
+                    info!(node_address = %identity.address, "🔧 Node is in maintenance, not taking assignments");
+                } else if identity.jail.is_jailed() {
+                    info!(node_address = %identity.address, "⛓️ Node is jailed, waiting for the jail to end");
+                } else if let Err(e) =
+                    register_node_if_needed(client, identity.address, &metadata_uri).await
+                {
+                    error!(node_address = %identity.address, error = %e, "Failed to register node");
+
+                    // Exit the loop
+                    std::process::exit(1);
+                }
+            }
 
-        let client_arc = Arc::new(client);
+            // Process any backlog of assignments
+            for identity in &connected {
+                if let Err(e) =
+                    process_assignment_backlog(identity, &verifier, &shutdown_notify).await
+                {
+                    error!(node_address = %identity.identity.address, error = %e, "Failed to query historical assignments");
+                }
+            }
 
-        // Process any backlog of assignments
-        if let Err(e) = process_assignment_backlog(
-            client_arc.clone(),
-            current_address,
-            &verifier,
-            verified_counter.clone(),
-            shutdown_notify.clone(),
-        )
-        .await
-        {
-            error!(error = %e, "Failed to query historical assignments");
-        }
+            // Start listening for events
+            run_event_listener(client, connected.into(), shutdown_notify.clone(), &verifier).await
+        };
 
-        // Start listening for events
-        match run_event_listener(
-            client_arc,
-            current_address,
-            shutdown_notify.clone(),
-            &verifier,
-            verified_counter.clone(),
-        )
-        .await
-        {
+        match listener_result {
             Ok(_) => {
                 warn!(reconnect_delay = ?reconnect_delay, "WebSocket listener exited normally. Reconnecting...");
             }
-            Err(e) if e.to_string().contains("Shutdown") => {
+            Err(e) if e.downcast_ref::<ShutdownRequested>().is_some() => {
                 break; // Graceful shutdown
             }
             Err(e) => {
@@ -567,9 +1208,61 @@ async fn main() -> Result<()> {
         }
     }
 
-    // Graceful shutdown - deactivate node from contract
-    if let Err(e) = deactivate_node_on_shutdown(&config, node_address).await {
-        error!(error = %e, "Failed to deactivate node gracefully");
+    emit_agreement_report(&identities, consensus_args.agreement_report_path.as_deref());
+
+    if let Some(metrics) = metrics {
+        info!("Shutting down metrics exporter");
+        let _ = metrics.shutdown();
+    }
+
+    // A shadow node never registered, so there is nothing to deactivate
+    if shadow_args.shadow {
+        info!("Shutdown complete");
+        return Ok(());
+    }
+
+    // Let in-flight verifications submit their verdicts before leaving the committee
+    let in_flight: usize = identities
+        .iter()
+        .map(|identity| identity.claims.in_flight())
+        .sum();
+    if in_flight > 0 {
+        info!(
+            in_flight,
+            "Waiting for in-flight verifications before shutting down"
+        );
+    }
+    let remaining: usize = join_all(
+        identities
+            .iter()
+            .map(|identity| identity.claims.drain(drain_timeout)),
+    )
+    .await
+    .into_iter()
+    .sum();
+    if remaining > 0 {
+        warn!(
+            remaining,
+            "⚠️ Shutting down with verifications still in flight, their rounds will go unanswered"
+        );
+    }
+
+    // Graceful shutdown - deactivate every identity from contract
+    for identity in &identities {
+        let node_address = identity.address;
+        if shutdown_args.skip_deactivation {
+            info!(node_address = %node_address, "Skipping deactivation, the node stays active for a quick restart");
+        } else if identity.is_stopped() {
+            info!(node_address = %node_address, "Identity was stopped and deactivated already");
+        } else if identity.maintenance.is_active() {
+            info!(node_address = %node_address, "Node is in maintenance and already deactivated");
+        } else if identity.jail.is_jailed() {
+            info!(node_address = %node_address, "Node is jailed and already inactive, skipping deactivation");
+        } else if let Err(e) =
+            deactivate_node_on_shutdown(&identity.config, registered.then_some(node_address)).await
+        {
+            error!(node_address = %node_address, error = %e, "Failed to deactivate node gracefully");
+        }
     }
 
     info!("Shutdown complete");
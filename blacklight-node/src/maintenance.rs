@@ -0,0 +1,139 @@
+//! Maintenance mode: pause participation without exiting the process.
+//!
+//! `SIGUSR1` enters maintenance: no new assignments are taken, in-flight verifications are
+//! drained and the operator is deactivated. `SIGUSR2` reactivates the operator and resumes taking
+//! assignments. The node stays connected and keeps tracking consensus and dependency health
+//! while in maintenance.
+
+use crate::args::NodeConfig;
+use crate::rounds::RoundClaims;
+use anyhow::Result;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::sync::{Mutex, Notify};
+use tracing::{error, info, warn};
+
+/// Whether the node is in maintenance, and the transitions in and out of it.
+#[derive(Debug, Default)]
+pub struct Maintenance {
+    active: AtomicBool,
+    /// Serializes transitions so a signal can't interrupt one halfway.
+    transition: Mutex<()>,
+}
+
+impl Maintenance {
+    /// Whether the node is in maintenance, in which case its operator is deactivated.
+    pub fn is_active(&self) -> bool {
+        self.active.load(Ordering::SeqCst)
+    }
+
+    /// Stop taking assignments, wait for in-flight verifications for up to `drain_timeout` and
+    /// deactivate the operator.
+    pub async fn enter(
+        &self,
+        config: &NodeConfig,
+        claims: &RoundClaims,
+        drain_timeout: Duration,
+    ) -> Result<()> {
+        let _guard = self.transition.lock().await;
+        if self.is_active() {
+            info!("Node is already in maintenance");
+            return Ok(());
+        }
+
+        info!(
+            in_flight = claims.in_flight(),
+            "🔧 Entering maintenance, waiting for in-flight verifications"
+        );
+        let remaining = claims.drain(drain_timeout).await;
+        if remaining > 0 {
+            warn!(
+                remaining,
+                "⚠️ Entering maintenance with verifications still in flight"
+            );
+        }
+
+        let result = async {
+            let client = config.connect().await?;
+            Ok(client.staking.deactivate_operator().await?)
+        }
+        .await;
+        match result {
+            Ok(tx_hash) => {
+                self.active.store(true, Ordering::SeqCst);
+                info!(tx_hash = ?tx_hash, "🔧 Node deactivated, in maintenance");
+                Ok(())
+            }
+            Err(e) => {
+                claims.reopen();
+                Err(e)
+            }
+        }
+    }
+
+    /// Reactivate the operator and take assignments again.
+    pub async fn leave(&self, config: &NodeConfig, claims: &RoundClaims) -> Result<()> {
+        let _guard = self.transition.lock().await;
+        if !self.is_active() {
+            info!("Node is not in maintenance");
+            return Ok(());
+        }
+
+        let client = config.connect().await?;
+        let tx_hash = client.staking.reactivate_operator().await?;
+        self.active.store(false, Ordering::SeqCst);
+        claims.reopen();
+        info!(tx_hash = ?tx_hash, "🔧 Node reactivated, leaving maintenance");
+        Ok(())
+    }
+
+    /// Enter maintenance on `SIGUSR1` and leave it on `SIGUSR2` until shutdown.
+    pub async fn run(
+        self: Arc<Self>,
+        config: NodeConfig,
+        claims: RoundClaims,
+        drain_timeout: Duration,
+        shutdown: Arc<Notify>,
+    ) {
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{SignalKind, signal};
+
+            let (mut enter, mut leave) = match (
+                signal(SignalKind::user_defined1()),
+                signal(SignalKind::user_defined2()),
+            ) {
+                (Ok(enter), Ok(leave)) => (enter, leave),
+                _ => {
+                    error!("Failed to register maintenance signal handlers");
+                    return;
+                }
+            };
+
+            loop {
+                tokio::select! {
+                    _ = enter.recv() => {
+                        info!("Maintenance requested (SIGUSR1)");
+                        if let Err(e) = self.enter(&config, &claims, drain_timeout).await {
+                            error!(error = %e, "Failed to enter maintenance");
+                        }
+                    }
+                    _ = leave.recv() => {
+                        info!("End of maintenance requested (SIGUSR2)");
+                        if let Err(e) = self.leave(&config, &claims).await {
+                            error!(error = %e, "Failed to leave maintenance");
+                        }
+                    }
+                    _ = shutdown.notified() => break,
+                }
+            }
+        }
+
+        #[cfg(not(unix))]
+        {
+            let _ = (config, claims, drain_timeout, shutdown);
+            warn!("Maintenance signals are only supported on unix");
+        }
+    }
+}
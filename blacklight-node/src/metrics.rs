@@ -0,0 +1,115 @@
+use alloy::primitives::{Address, U256};
+use opentelemetry::{
+    KeyValue, global,
+    metrics::{Counter, Gauge, Meter},
+};
+use std::sync::LazyLock;
+use std::time::Duration;
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(|| {
+    let meter = global::meter("blacklight-node");
+    Metrics::new(&meter)
+});
+
+pub(crate) fn get() -> &'static Metrics {
+    &METRICS
+}
+
+const WEI_PER_ETH: f64 = 1e18;
+
+pub(crate) struct Metrics {
+    pub(crate) eth: EthMetrics,
+    pub(crate) health: HealthMetrics,
+    // A private guard to prevent this type from being constructed outside of this module.
+    _private: (),
+}
+
+impl Metrics {
+    fn new(meter: &Meter) -> Self {
+        let eth = EthMetrics::new(meter);
+        let health = HealthMetrics::new(meter);
+        Self {
+            eth,
+            health,
+            _private: (),
+        }
+    }
+}
+
+pub(crate) struct EthMetrics {
+    funds: Gauge<f64>,
+    spent: Counter<f64>,
+    runway: Gauge<f64>,
+}
+
+impl EthMetrics {
+    fn new(meter: &Meter) -> Self {
+        let funds = meter
+            .f64_gauge("blacklight.node.eth.total")
+            .with_description("Total amount of ETH available in the node wallet")
+            .with_unit("ETH")
+            .build();
+        let spent = meter
+            .f64_counter("blacklight.node.eth.spent")
+            .with_description("ETH spent on verdict submissions")
+            .with_unit("ETH")
+            .build();
+        let runway = meter
+            .f64_gauge("blacklight.node.eth.runway")
+            .with_description("Estimated time until the ETH balance runs out")
+            .with_unit("h")
+            .build();
+        Self {
+            funds,
+            spent,
+            runway,
+        }
+    }
+
+    pub(crate) fn set_funds(&self, amount: U256, operator: Address) {
+        self.funds.record(
+            f64::from(amount) / WEI_PER_ETH,
+            &operator_attributes(operator),
+        );
+    }
+
+    pub(crate) fn add_spent(&self, amount: U256, operator: Address) {
+        self.spent.add(
+            f64::from(amount) / WEI_PER_ETH,
+            &operator_attributes(operator),
+        );
+    }
+
+    pub(crate) fn set_runway(&self, remaining: Duration, operator: Address) {
+        self.runway.record(
+            remaining.as_secs_f64() / 3600.0,
+            &operator_attributes(operator),
+        );
+    }
+}
+
+pub(crate) struct HealthMetrics {
+    up: Gauge<u64>,
+}
+
+impl HealthMetrics {
+    fn new(meter: &Meter) -> Self {
+        let up = meter
+            .u64_gauge("blacklight.node.dependency.up")
+            .with_description("Whether an external service verification depends on is up")
+            .build();
+        Self { up }
+    }
+
+    pub(crate) fn set_up(&self, dependency: &str, up: bool) {
+        self.up.record(
+            u64::from(up),
+            &[KeyValue::new("dependency", dependency.to_string())],
+        );
+    }
+}
+
+/// Attributes telling apart the identities a node runs
+fn operator_attributes(operator: Address) -> [KeyValue; 1] {
+    [KeyValue::new("operator", operator.to_string())]
+}
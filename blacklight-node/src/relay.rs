@@ -0,0 +1,493 @@
+//! Local relay making the HTTP requests of the verification libraries with the node's client.
+//!
+//! The nilCC report fetcher and artifact downloader and dcap-qvl's collateral fetcher build HTTP
+//! clients of their own and only take URLs, so they know nothing of the configured proxy, CA
+//! roots, user agent or connection limits. Their URLs are rewritten to a local server instead,
+//! `https://host/path` becoming `http://127.0.0.1:<port>/<token>/https/host/path`, and the relay
+//! makes the request with the shared [`HttpClient`]. The tape server is built on the same local
+//! server.
+//!
+//! The server is no open proxy for other local processes:
+//!
+//! - Requests must carry the random token the server was started with
+//! - Only URLs under the prefixes the server was started with, such as the artifact bucket, are
+//!   requested, and the URLs of an HTX while a [`Session`] verifying it allows them
+//!
+//! A session may also keep the responses to its requests for the verification's evidence. Its
+//! URLs carry the session, `http://127.0.0.1:<port>/<token>/session/<id>/https/host/path`.
+
+use crate::http::{Exchange, HttpClient, Streamed};
+use alloy::hex;
+use anyhow::{Context, Result, bail};
+use futures_util::future::BoxFuture;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::warn;
+
+/// Maximum size of a request head accepted by the local server.
+const MAX_REQUEST_HEAD: usize = 64 * 1024;
+
+/// Delay before accepting again after a failure, e.g. for lack of file descriptors, doubled up
+/// to [`MAX_ACCEPT_BACKOFF`] while failures last.
+const MIN_ACCEPT_BACKOFF: Duration = Duration::from_millis(50);
+const MAX_ACCEPT_BACKOFF: Duration = Duration::from_secs(2);
+
+/// Request headers that only concern the connection to the local server.
+const HOP_BY_HOP_HEADERS: &[&str] = &[
+    "host",
+    "connection",
+    "keep-alive",
+    "content-length",
+    "transfer-encoding",
+    "te",
+    "trailer",
+    "upgrade",
+    "proxy-authorization",
+    "proxy-connection",
+];
+
+/// Relays rewritten requests through the shared HTTP client.
+#[derive(Debug, Clone)]
+pub struct Relay {
+    server: LocalServer,
+}
+
+impl Relay {
+    /// Start relaying requests for URLs under the `allowed` prefixes on a local port.
+    pub async fn start(http: HttpClient, allowed: Vec<String>) -> Result<Self> {
+        let server = serve(allowed, move |request| {
+            let http = http.clone();
+            Box::pin(async move { relay(request, &http).await })
+        })
+        .await?;
+        Ok(Self { server })
+    }
+
+    /// The local URL `url` is relayed from.
+    pub fn rewrite(&self, url: &str) -> String {
+        self.server.rewrite(url)
+    }
+
+    /// Allow requests for `urls` until the session is dropped, keeping the responses if `keep`.
+    pub fn session(&self, urls: &[&str], keep: bool) -> Session {
+        self.server.session(urls, keep)
+    }
+}
+
+/// A request to the local server.
+#[derive(Debug, Clone)]
+pub struct Request {
+    /// The original URL
+    pub url: String,
+    /// The request headers, without those of the connection to the local server
+    pub headers: Vec<(String, String)>,
+    /// Whether the response is kept by a session, and so must be read in full
+    pub keep: bool,
+}
+
+/// The answer to a request.
+#[derive(Debug)]
+pub enum Reply {
+    /// A response read in full
+    Full(Exchange),
+    /// A response passed on as it arrives
+    Stream(Streamed),
+}
+
+/// The state of an open session.
+#[derive(Debug, Default)]
+struct SessionState {
+    allowed: Vec<String>,
+    keep: bool,
+    responses: Vec<Exchange>,
+}
+
+/// Open sessions by id.
+#[derive(Debug, Default)]
+struct Sessions {
+    next_id: u64,
+    open: HashMap<u64, SessionState>,
+}
+
+/// A local server answering requests for rewritten URLs.
+#[derive(Debug, Clone)]
+pub struct LocalServer {
+    addr: SocketAddr,
+    token: Arc<str>,
+    allowed: Arc<[String]>,
+    sessions: Arc<Mutex<Sessions>>,
+}
+
+impl LocalServer {
+    /// The local URL `url` is served at.
+    pub fn rewrite(&self, url: &str) -> String {
+        match url.split_once("://") {
+            Some((scheme, rest)) => format!("http://{}/{}/{scheme}/{rest}", self.addr, self.token),
+            None => url.to_string(),
+        }
+    }
+
+    /// Allow requests for `urls` until the session is dropped, keeping the responses if `keep`.
+    pub fn session(&self, urls: &[&str], keep: bool) -> Session {
+        let mut sessions = self.sessions.lock().unwrap();
+        let id = sessions.next_id;
+        sessions.next_id += 1;
+        let state = SessionState {
+            allowed: urls.iter().map(|url| url.to_string()).collect(),
+            keep,
+            responses: Vec::new(),
+        };
+        sessions.open.insert(id, state);
+        Session {
+            id,
+            server: self.clone(),
+        }
+    }
+
+    /// Whether `url` may be requested in `session`, and if so whether its response is kept.
+    fn admit(&self, session: Option<u64>, url: &str) -> Option<bool> {
+        let sessions = self.sessions.lock().unwrap();
+        let state = session.and_then(|id| sessions.open.get(&id));
+        let allowed = self.allowed.iter().any(|prefix| under_prefix(prefix, url))
+            || state.is_some_and(|state| state.allowed.iter().any(|allowed| allowed == url));
+        allowed.then(|| state.is_some_and(|state| state.keep))
+    }
+
+    /// Keep `exchange` for the session `id`, if it is still open.
+    fn keep(&self, id: u64, exchange: &Exchange) {
+        if let Some(state) = self.sessions.lock().unwrap().open.get_mut(&id) {
+            state.responses.push(exchange.clone());
+        }
+    }
+}
+
+/// Allows requests for an HTX's URLs, and keeps their responses if asked to, until dropped.
+#[derive(Debug)]
+pub struct Session {
+    id: u64,
+    server: LocalServer,
+}
+
+impl Session {
+    /// The local URL `url` is served at within this session.
+    pub fn rewrite(&self, url: &str) -> String {
+        match url.split_once("://") {
+            Some((scheme, rest)) => format!(
+                "http://{}/{}/session/{}/{scheme}/{rest}",
+                self.server.addr, self.server.token, self.id
+            ),
+            None => url.to_string(),
+        }
+    }
+
+    /// The last response kept for `url`.
+    pub fn response(&self, url: &str) -> Option<Exchange> {
+        let sessions = self.server.sessions.lock().unwrap();
+        let state = sessions.open.get(&self.id)?;
+        state.responses.iter().rev().find(|e| e.url == url).cloned()
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        if let Ok(mut sessions) = self.server.sessions.lock() {
+            sessions.open.remove(&self.id);
+        }
+    }
+}
+
+/// Make `request` with `http`, reading the response in full only when it is kept.
+async fn relay(request: Request, http: &HttpClient) -> Reply {
+    if request.keep {
+        return Reply::Full(fetch(request, http).await);
+    }
+    match http.send(&request.url, &request.headers).await {
+        Ok(streamed) => Reply::Stream(streamed),
+        Err(e) => Reply::Full(gateway_error(request.url, e)),
+    }
+}
+
+/// Make `request` and read the whole response, answering connection failures with a gateway
+/// error.
+pub async fn fetch(request: Request, http: &HttpClient) -> Exchange {
+    let result = match http.send(&request.url, &request.headers).await {
+        Ok(streamed) => streamed.read(&request.url).await,
+        Err(e) => Err(e),
+    };
+    result.unwrap_or_else(|e| gateway_error(request.url, e))
+}
+
+fn gateway_error(url: String, error: reqwest::Error) -> Exchange {
+    warn!(url = %url, error = %error, "Relayed request failed");
+    Exchange {
+        url,
+        status: 502,
+        content_type: None,
+        body: error.to_string().into_bytes(),
+    }
+}
+
+/// Serve GET requests for rewritten URLs under the `allowed` prefixes, or allowed by a session,
+/// on a local port, answering each with `respond`.
+pub async fn serve<F>(allowed: Vec<String>, respond: F) -> Result<LocalServer>
+where
+    F: Fn(Request) -> BoxFuture<'static, Reply> + Clone + Send + Sync + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .context("Failed to bind local HTTP server")?;
+    let server = LocalServer {
+        addr: listener.local_addr()?,
+        token: hex::encode(rand::random::<[u8; 16]>()).into(),
+        allowed: allowed.into(),
+        sessions: Default::default(),
+    };
+    let accepting = server.clone();
+    tokio::spawn(async move {
+        let mut backoff = MIN_ACCEPT_BACKOFF;
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => {
+                    backoff = MIN_ACCEPT_BACKOFF;
+                    stream
+                }
+                Err(e) => {
+                    warn!(error = %e, retry_in = ?backoff, "Local HTTP server failed to accept a connection");
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(MAX_ACCEPT_BACKOFF);
+                    continue;
+                }
+            };
+            let respond = respond.clone();
+            let server = accepting.clone();
+            tokio::spawn(async move {
+                if let Err(e) = handle(stream, &server, respond).await {
+                    warn!(error = %e, "Local HTTP server request failed");
+                }
+            });
+        }
+    });
+    Ok(server)
+}
+
+async fn handle<F>(mut stream: TcpStream, server: &LocalServer, respond: F) -> Result<()>
+where
+    F: Fn(Request) -> BoxFuture<'static, Reply>,
+{
+    let mut head = Vec::new();
+    let mut buf = [0u8; 4096];
+    while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+        let read = stream.read(&mut buf).await?;
+        if read == 0 || head.len() > MAX_REQUEST_HEAD {
+            bail!("incomplete request");
+        }
+        head.extend_from_slice(&buf[..read]);
+    }
+    let head = String::from_utf8_lossy(&head);
+    let mut lines = head.lines();
+    let mut request_line = lines.next().unwrap_or_default().split(' ');
+    let (method, path) = (request_line.next(), request_line.next().unwrap_or_default());
+
+    let Some(path) = strip_token(path, &server.token) else {
+        return write_exchange(&mut stream, &status_only(path, 404)).await;
+    };
+    let (session, path) = match session(path) {
+        Some((id, path)) => (Some(id), path),
+        None => (None, path),
+    };
+    let url = match (method, original_url(path)) {
+        (Some("GET"), Some(url)) => url,
+        _ => return write_exchange(&mut stream, &status_only(path, 405)).await,
+    };
+    let Some(keep) = server.admit(session, &url) else {
+        warn!(url = %url, "Local HTTP server refused a request for a URL it doesn't serve");
+        return write_exchange(&mut stream, &status_only(&url, 403)).await;
+    };
+    let headers = lines
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| line.split_once(':'))
+        .filter(|(name, _)| !HOP_BY_HOP_HEADERS.contains(&name.trim().to_lowercase().as_str()))
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .collect();
+
+    match respond(Request { url, headers, keep }).await {
+        Reply::Full(exchange) => {
+            if let Some(id) = session
+                && keep
+            {
+                server.keep(id, &exchange);
+            }
+            write_exchange(&mut stream, &exchange).await
+        }
+        Reply::Stream(mut streamed) => {
+            let head = response_head(
+                streamed.status(),
+                streamed.content_type().as_deref(),
+                streamed.content_length(),
+            );
+            stream.write_all(head.as_bytes()).await?;
+            while let Some(chunk) = streamed.chunk().await? {
+                stream.write_all(chunk.as_ref()).await?;
+            }
+            stream.shutdown().await?;
+            Ok(())
+        }
+    }
+}
+
+async fn write_exchange(stream: &mut TcpStream, exchange: &Exchange) -> Result<()> {
+    let head = response_head(
+        exchange.status,
+        exchange.content_type.as_deref(),
+        Some(exchange.body.len() as u64),
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(&exchange.body).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// The status line and headers of a response. Without a content length, the body ends with the
+/// connection.
+fn response_head(status: u16, content_type: Option<&str>, content_length: Option<u64>) -> String {
+    let mut head = format!(
+        "HTTP/1.1 {status} {}\r\nConnection: close\r\n",
+        reqwest::StatusCode::from_u16(status)
+            .ok()
+            .and_then(|s| s.canonical_reason())
+            .unwrap_or_default(),
+    );
+    if let Some(length) = content_length {
+        head.push_str(&format!("Content-Length: {length}\r\n"));
+    }
+    if let Some(content_type) = content_type {
+        head.push_str(&format!("Content-Type: {content_type}\r\n"));
+    }
+    head.push_str("\r\n");
+    head
+}
+
+fn status_only(url: &str, status: u16) -> Exchange {
+    Exchange {
+        url: url.to_string(),
+        status,
+        content_type: None,
+        body: Vec::new(),
+    }
+}
+
+/// The request path without the server's token, `/<token>/https/host/path` → `/https/host/path`.
+fn strip_token<'a>(path: &'a str, token: &str) -> Option<&'a str> {
+    let rest = path.strip_prefix('/')?.strip_prefix(token)?;
+    rest.starts_with('/').then_some(rest)
+}
+
+/// The session of a request path and the path without it,
+/// `/session/3/https/host/path` → `(3, /https/host/path)`.
+fn session(path: &str) -> Option<(u64, &str)> {
+    let rest = path.strip_prefix("/session/")?;
+    let (id, path) = rest.split_at(rest.find('/')?);
+    Some((id.parse().ok()?, path))
+}
+
+/// The original URL of a rewritten request path, `/https/host/path` → `https://host/path`.
+fn original_url(path: &str) -> Option<String> {
+    let (scheme, rest) = path.strip_prefix('/')?.split_once('/')?;
+    Some(format!("{scheme}://{rest}"))
+}
+
+/// Whether `url` is `prefix` or below it, so `https://host` doesn't cover `https://host.evil`.
+fn under_prefix(prefix: &str, url: &str) -> bool {
+    let Some(rest) = url.strip_prefix(prefix) else {
+        return false;
+    };
+    prefix.ends_with('/') || rest.is_empty() || rest.starts_with(['/', '?'])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::Client;
+
+    #[test]
+    fn test_original_url() {
+        assert_eq!(
+            original_url("/https/example.com/index.json?v=1").as_deref(),
+            Some("https://example.com/index.json?v=1")
+        );
+        assert_eq!(original_url("/nothing"), None);
+        assert_eq!(
+            session("/session/3/https/example.com/a"),
+            Some((3, "/https/example.com/a"))
+        );
+        assert_eq!(session("/https/example.com/a"), None);
+        assert_eq!(strip_token("/abc/https/x", "abc"), Some("/https/x"));
+        assert_eq!(strip_token("/abcd/https/x", "abc"), None);
+        assert!(under_prefix(
+            "https://bucket.example.com",
+            "https://bucket.example.com/a"
+        ));
+        assert!(!under_prefix(
+            "https://bucket.example.com",
+            "https://bucket.example.com.evil/a"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_relay_forwards() {
+        let upstream = serve(vec!["https://pccs.example.com".to_string()], |request| {
+            Box::pin(async move {
+                Reply::Full(Exchange {
+                    body: request.url.clone().into_bytes(),
+                    url: request.url,
+                    status: 200,
+                    content_type: Some("text/plain".to_string()),
+                })
+            })
+        })
+        .await
+        .unwrap();
+        let url = upstream.rewrite("https://pccs.example.com/tcb?fmspc=00");
+        let relay = Relay::start(
+            HttpClient::new(Client::new(), 2),
+            vec![format!("http://{}/", upstream.addr)],
+        )
+        .await
+        .unwrap();
+
+        // The relay requests the upstream URL with the shared client
+        let response = Client::new().get(relay.rewrite(&url)).send().await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(
+            response.text().await.unwrap(),
+            "https://pccs.example.com/tcb?fmspc=00"
+        );
+
+        // Requests without the token or for URLs the relay doesn't serve are refused
+        let untokened = format!("http://{}/https/pccs.example.com/tcb", relay.server.addr);
+        let response = Client::new().get(untokened).send().await.unwrap();
+        assert_eq!(response.status(), 404);
+        let other = relay.rewrite("https://other.example.com/");
+        let response = Client::new().get(other).send().await.unwrap();
+        assert_eq!(response.status(), 403);
+
+        // A session allows its URLs and keeps their responses until it is dropped
+        let report = upstream.rewrite("https://pccs.example.com/report.json");
+        let session = relay.session(&[&report], true);
+        let response = Client::new()
+            .get(session.rewrite(&report))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        let kept = session.response(&report).unwrap();
+        assert_eq!(kept.body, b"https://pccs.example.com/report.json");
+        assert!(relay.session(&[], true).response(&report).is_none());
+        drop(session);
+        assert!(relay.server.sessions.lock().unwrap().open.is_empty());
+    }
+}
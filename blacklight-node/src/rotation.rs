@@ -0,0 +1,451 @@
+//! Rotation of the operator to a new key, e.g. when the hot key is suspected compromised.
+//!
+//! The old operator is deactivated and its stake unstaked. Once the unbonding period is over,
+//! the stake is withdrawn, moved to the new key, staked and the new operator registered. The
+//! process takes days, so every completed step is recorded in the state file and running the
+//! command again resumes where it stopped.
+
+use crate::args::{MIN_ETH_BALANCE, MnemonicArgs, NodeConfig, STATE_FILE_NODE};
+use crate::staking::{format_nil, format_release, now, stake};
+use alloy::hex;
+use alloy::primitives::U256;
+use alloy::primitives::utils::format_ether;
+use alloy::signers::local::PrivateKeySigner;
+use anyhow::{Context, Result, anyhow, bail};
+use blacklight_contract_clients::{BlacklightClient, SignerConfig};
+use key_args::KeyArgs;
+use state_file::StateFile;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use tracing::{info, warn};
+
+// State file keys of an ongoing rotation
+const STEP_KEY: &str = "ROTATION_STEP";
+const NEW_ADDRESS_KEY: &str = "ROTATION_NEW_ADDRESS";
+const NEW_KEYSTORE_KEY: &str = "ROTATION_NEW_KEYSTORE_PATH";
+const NEW_PRIVATE_KEY_KEY: &str = "ROTATION_NEW_PRIVATE_KEY";
+const AMOUNT_KEY: &str = "ROTATION_AMOUNT";
+const RELEASE_TIME_KEY: &str = "ROTATION_RELEASE_TIME";
+
+/// The next step of a rotation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Step {
+    /// Deactivate the old operator so it gets no more assignments
+    Deactivate,
+    /// Request to unstake the old operator's stake
+    Unstake,
+    /// Wait for the unbonding period and withdraw the stake
+    Withdraw,
+    /// Transfer the withdrawn NIL to the new key
+    Transfer,
+    /// Stake the NIL to the new operator
+    Stake,
+    /// Register the new operator
+    Register,
+}
+
+impl Step {
+    const ALL: [Step; 6] = [
+        Step::Deactivate,
+        Step::Unstake,
+        Step::Withdraw,
+        Step::Transfer,
+        Step::Stake,
+        Step::Register,
+    ];
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Step::Deactivate => "deactivate",
+            Step::Unstake => "unstake",
+            Step::Withdraw => "withdraw",
+            Step::Transfer => "transfer",
+            Step::Stake => "stake",
+            Step::Register => "register",
+        }
+    }
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Step {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Step::ALL
+            .into_iter()
+            .find(|step| step.as_str() == s)
+            .ok_or_else(|| anyhow!("unknown rotation step '{s}'"))
+    }
+}
+
+/// An ongoing rotation, as recorded in the state file
+struct Rotation {
+    state_file: StateFile,
+    step: Step,
+    new_key: String,
+}
+
+impl Rotation {
+    /// Resume the rotation recorded in the state file, or start a new one with a new key.
+    ///
+    /// The new key is stored in plaintext only if there is no keystore passphrase and
+    /// `allow_plaintext_key` is set.
+    fn load_or_start(
+        state_file: StateFile,
+        keys: &KeyArgs,
+        allow_plaintext_key: bool,
+    ) -> Result<Self> {
+        if let Some(step) = state_file.load_value(STEP_KEY) {
+            let step = step.parse()?;
+            let new_key = match state_file.load_value(NEW_KEYSTORE_KEY) {
+                Some(path) => KeyArgs {
+                    private_key: None,
+                    keystore_path: Some(PathBuf::from(path)),
+                    ..keys.clone()
+                }
+                .private_key()?
+                .context("no key in the rotation keystore")?,
+                None => state_file
+                    .load_value(NEW_PRIVATE_KEY_KEY)
+                    .context("the new key of the rotation is missing from the state file")?,
+            };
+            info!("Resuming key rotation at step '{step}'");
+            return Ok(Self {
+                state_file,
+                step,
+                new_key,
+            });
+        }
+
+        // Never encrypt into the keystore of the key being rotated out
+        let keys = KeyArgs {
+            keystore_path: None,
+            ..keys.clone()
+        };
+        // The keystore becomes the node's keystore, so it is named after the rotation's start
+        let keystore = PathBuf::from(format!("blacklight_node.{}.keystore.json", now()));
+        if keystore.exists() {
+            bail!("keystore {} already exists", keystore.display());
+        }
+        let wallet = match keys.generate_keystore(&keystore)? {
+            Some((wallet, path)) => {
+                state_file.save_value(NEW_KEYSTORE_KEY, &path.display().to_string())?;
+                wallet
+            }
+            None => {
+                if !allow_plaintext_key {
+                    bail!(
+                        "No keystore passphrase given to encrypt the new key, set one or pass --allow-plaintext-key to store the key in plaintext in {STATE_FILE_NODE}"
+                    );
+                }
+                warn!(
+                    "No keystore passphrase given, storing the new private key in plaintext in {STATE_FILE_NODE}"
+                );
+                let wallet = PrivateKeySigner::random();
+                state_file.save_value(NEW_PRIVATE_KEY_KEY, &private_key_hex(&wallet))?;
+                wallet
+            }
+        };
+        state_file.save_value(NEW_ADDRESS_KEY, &wallet.address().to_string())?;
+        state_file.save_value(STEP_KEY, Step::Deactivate.as_str())?;
+        info!(
+            "🔑 Started key rotation to the new operator address {}",
+            wallet.address()
+        );
+        info!(
+            "Fund {} with at least {} ETH for gas before the stake step",
+            wallet.address(),
+            format_ether(MIN_ETH_BALANCE)
+        );
+        Ok(Self {
+            state_file,
+            step: Step::Deactivate,
+            new_key: private_key_hex(&wallet),
+        })
+    }
+
+    /// Record that `step` is next
+    fn advance(&mut self, step: Step) -> Result<()> {
+        self.state_file.save_value(STEP_KEY, step.as_str())?;
+        self.step = step;
+        Ok(())
+    }
+
+    fn amount(&self) -> Result<U256> {
+        let amount = self
+            .state_file
+            .load_value(AMOUNT_KEY)
+            .context("the rotated amount is missing from the state file")?;
+        Ok(amount.parse()?)
+    }
+
+    /// Make the new key the node's key and drop the rotation's state
+    fn finish(&self) -> Result<()> {
+        let mut state = self.state_file.load_all();
+        let new_address = state.remove(NEW_ADDRESS_KEY);
+        match state.remove(NEW_KEYSTORE_KEY) {
+            Some(path) => {
+                state.remove("PRIVATE_KEY");
+                state.insert("KEYSTORE_PATH".to_string(), path);
+                state.remove(NEW_PRIVATE_KEY_KEY);
+            }
+            None => {
+                state.remove("KEYSTORE_PATH");
+                state.insert("PRIVATE_KEY".to_string(), self.new_key.clone());
+                state.remove(NEW_PRIVATE_KEY_KEY);
+            }
+        }
+        if let Some(address) = new_address {
+            state.insert("PUBLIC_KEY".to_string(), address);
+        }
+        for key in [STEP_KEY, AMOUNT_KEY, RELEASE_TIME_KEY] {
+            state.remove(key);
+        }
+        self.state_file.save_all(&state)
+    }
+}
+
+/// Fail unless the node's key comes from the state file, `PRIVATE_KEY` or `KEYSTORE_PATH`. A
+/// remote signer or mnemonic takes precedence over the rotated key, so the node would keep
+/// signing with the old one.
+fn check_key_source(keys: &KeyArgs, mnemonic: &MnemonicArgs) -> Result<()> {
+    if keys.remote_signer_url.is_some() {
+        bail!(
+            "The node signs with the remote signer REMOTE_SIGNER_URL, rotate the key on the signing service instead"
+        );
+    }
+    let has_private_key = keys.private_key.is_some() || keys.keystore_path.is_some();
+    if !has_private_key && mnemonic.mnemonic.is_some() {
+        bail!(
+            "The node's key is derived from MNEMONIC, which would keep deriving the old key after the rotation"
+        );
+    }
+    Ok(())
+}
+
+/// Run the key rotation from the node's current key to a new one, resuming a previous run.
+///
+/// The new operator registers `metadata_uri`, or the old operator's URI if it is empty.
+pub async fn run(
+    config: &NodeConfig,
+    keys: &KeyArgs,
+    mnemonic: &MnemonicArgs,
+    metadata_uri: &str,
+    allow_plaintext_key: bool,
+) -> Result<()> {
+    check_key_source(keys, mnemonic)?;
+    let mut rotation =
+        Rotation::load_or_start(StateFile::new(STATE_FILE_NODE), keys, allow_plaintext_key)?;
+    let old = config.connect().await?;
+    let new = BlacklightClient::new(
+        config.contract_config(),
+        SignerConfig::Local(rotation.new_key.clone()),
+    )
+    .await?;
+    let old_address = old.signer_address();
+    let new_address = new.signer_address();
+    if old_address == new_address {
+        bail!("The node already uses the new key {new_address}, is the old key still configured?");
+    }
+    let decimals = old.token.decimals().await?;
+
+    loop {
+        match rotation.step {
+            Step::Deactivate => {
+                if old.staking.is_active_operator(old_address).await? {
+                    let tx_hash = old.staking.deactivate_operator().await?;
+                    info!("⏸️ Old operator {old_address} deactivated (tx: {tx_hash:?})");
+                }
+                rotation.advance(Step::Unstake)?;
+            }
+            Step::Unstake => {
+                let staked = old.staking.stake_of(old_address).await?;
+                let mut tranches = old.staking.unbonding_tranches(old_address).await?;
+                if !staked.is_zero() {
+                    let tx_hash = old.staking.request_unstake(old_address, staked).await?;
+                    info!(
+                        "Requested to unstake {} NIL from {old_address} (tx: {tx_hash:?})",
+                        format_nil(staked, decimals)
+                    );
+                    tranches = old.staking.unbonding_tranches(old_address).await?;
+                }
+                let amount: U256 = tranches.iter().map(|tranche| tranche.amount).sum();
+                let release_time = tranches.iter().map(|t| t.releaseTime).max().unwrap_or(0);
+                rotation
+                    .state_file
+                    .save_value(AMOUNT_KEY, &amount.to_string())?;
+                rotation
+                    .state_file
+                    .save_value(RELEASE_TIME_KEY, &release_time.to_string())?;
+                rotation.advance(Step::Withdraw)?;
+            }
+            Step::Withdraw => {
+                let release_time: u64 = rotation
+                    .state_file
+                    .load_value(RELEASE_TIME_KEY)
+                    .and_then(|time| time.parse().ok())
+                    .unwrap_or(0);
+                let now = now();
+                if release_time > now {
+                    info!(
+                        "⏳ The stake of {old_address} is unbonding and {}, run this command again then",
+                        format_release(release_time, now)
+                    );
+                    return Ok(());
+                }
+                if !old
+                    .staking
+                    .unbonding_tranches(old_address)
+                    .await?
+                    .is_empty()
+                {
+                    let tx_hash = old.staking.withdraw_unstaked(old_address).await?;
+                    info!("Withdrew the unbonded stake of {old_address} (tx: {tx_hash:?})");
+                }
+                rotation.advance(Step::Transfer)?;
+            }
+            Step::Transfer => {
+                // Only move what the new key doesn't hold yet, in case a previous run was
+                // interrupted after the transfer
+                let amount = rotation.amount()?;
+                let held = new.token.balance_of(new_address).await?
+                    + new.staking.stake_of(new_address).await?;
+                let missing = amount.saturating_sub(held);
+                if !missing.is_zero() {
+                    let tx_hash = old.token.transfer(new_address, missing).await?;
+                    info!(
+                        "Transferred {} NIL to {new_address} (tx: {tx_hash:?})",
+                        format_nil(missing, decimals)
+                    );
+                }
+                rotation.advance(Step::Stake)?;
+            }
+            Step::Stake => {
+                let eth_balance = new.get_balance().await?;
+                if eth_balance < MIN_ETH_BALANCE {
+                    info!(
+                        "💸 Fund {new_address} with at least {} ETH for gas, then run this command again",
+                        format_ether(MIN_ETH_BALANCE)
+                    );
+                    return Ok(());
+                }
+                let amount = rotation.amount()?;
+                let staked = new.staking.stake_of(new_address).await?;
+                let missing = amount.saturating_sub(staked);
+                if !missing.is_zero() {
+                    stake(&new, new_address, missing, decimals).await?;
+                }
+                rotation.advance(Step::Register)?;
+            }
+            Step::Register => {
+                if !new.staking.is_active_operator(new_address).await? {
+                    let metadata_uri = match metadata_uri {
+                        "" => old.staking.metadata_uri(old_address).await?,
+                        uri => uri.to_string(),
+                    };
+                    let tx_hash = new.staking.register_operator(metadata_uri).await?;
+                    info!("New operator {new_address} registered (tx: {tx_hash:?})");
+                }
+                rotation.finish()?;
+                info!("✅ Key rotation from {old_address} to {new_address} complete");
+                if keys.private_key.is_some() || keys.keystore_path.is_some() {
+                    warn!(
+                        "PRIVATE_KEY or KEYSTORE_PATH is set and still points to the old key, update it to the new key recorded in {STATE_FILE_NODE}"
+                    );
+                }
+                return Ok(());
+            }
+        }
+    }
+}
+
+fn private_key_hex(wallet: &PrivateKeySigner) -> String {
+    format!("0x{}", hex::encode(wallet.to_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_step_names() {
+        for step in Step::ALL {
+            assert_eq!(step.to_string().parse::<Step>().unwrap(), step);
+        }
+        assert!("done".parse::<Step>().is_err());
+        assert!(Step::Deactivate < Step::Register);
+    }
+
+    #[test]
+    fn test_key_source() {
+        let keys = KeyArgs {
+            private_key: None,
+            keystore_path: None,
+            keystore_password_file: None,
+            remote_signer_url: None,
+            remote_signer_address: None,
+        };
+        let mnemonic = MnemonicArgs {
+            mnemonic: None,
+            mnemonic_derivation_path: String::new(),
+            mnemonic_index: None,
+            mnemonic_base_index: 0,
+            mnemonic_alloc_root: None,
+            mnemonic_replica_id: None,
+        };
+        assert!(check_key_source(&keys, &mnemonic).is_ok());
+
+        let derived = MnemonicArgs {
+            mnemonic: Some("test test test".to_string()),
+            ..mnemonic.clone()
+        };
+        assert!(check_key_source(&keys, &derived).is_err());
+        // A private key takes precedence over the mnemonic
+        let local = KeyArgs {
+            private_key: Some("0x01".to_string()),
+            ..keys.clone()
+        };
+        assert!(check_key_source(&local, &derived).is_ok());
+
+        let remote = KeyArgs {
+            remote_signer_url: Some("http://signer".to_string()),
+            ..local
+        };
+        assert!(check_key_source(&remote, &mnemonic).is_err());
+    }
+
+    #[test]
+    fn test_finish_swaps_keys() {
+        let dir = std::env::temp_dir().join(format!("rotation-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let state_file = StateFile::new(dir.join("node.env"));
+        state_file.save_value("PRIVATE_KEY", "0xold").unwrap();
+        state_file.save_value("RPC_URL", "http://rpc").unwrap();
+        state_file.save_value(NEW_PRIVATE_KEY_KEY, "0xnew").unwrap();
+        state_file.save_value(NEW_ADDRESS_KEY, "0xaddress").unwrap();
+        state_file.save_value(STEP_KEY, "register").unwrap();
+        state_file.save_value(AMOUNT_KEY, "5").unwrap();
+
+        let rotation = Rotation {
+            state_file,
+            step: Step::Register,
+            new_key: "0xnew".to_string(),
+        };
+        assert_eq!(rotation.amount().unwrap(), U256::from(5));
+        rotation.finish().unwrap();
+
+        let state = rotation.state_file.load_all();
+        assert_eq!(state["PRIVATE_KEY"], "0xnew");
+        assert_eq!(state["PUBLIC_KEY"], "0xaddress");
+        assert_eq!(state["RPC_URL"], "http://rpc");
+        assert!(state.keys().all(|key| !key.starts_with("ROTATION_")));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,255 @@
+use crate::memo::LruCache;
+use alloy::primitives::B256;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::Notify;
+use tokio::time::Instant;
+
+/// Number of rounds remembered as claimed.
+const MAX_CLAIMED_ROUNDS: usize = 10_000;
+
+type RoundKey = (B256, u8);
+
+#[derive(Debug)]
+struct Inner {
+    claimed: LruCache<RoundKey, ()>,
+    /// Round deadline (unix seconds, 0 if unknown) of every round being processed.
+    in_flight: HashMap<RoundKey, u64>,
+    closed: bool,
+    suspended: bool,
+    stopped: bool,
+}
+
+/// The outcome of claiming a round.
+#[derive(Debug)]
+pub enum Claim {
+    /// The round is ours to process until the guard is dropped.
+    Claimed(RoundGuard),
+    /// The round was already claimed, e.g. it was both in the backlog and delivered live.
+    AlreadyClaimed,
+    /// The node is shutting down, stopped or in maintenance and takes no new assignments.
+    Closed,
+    /// The node is jailed and can't submit verdicts.
+    Suspended,
+}
+
+/// Rounds this node has started to process, so an assignment is verified only once, and the
+/// rounds still in flight, so shutdown can wait for them to be submitted.
+#[derive(Debug, Clone)]
+pub struct RoundClaims {
+    inner: Arc<Mutex<Inner>>,
+    idle: Arc<Notify>,
+}
+
+impl Default for RoundClaims {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RoundClaims {
+    pub fn new() -> Self {
+        let inner = Inner {
+            claimed: LruCache::new(MAX_CLAIMED_ROUNDS),
+            in_flight: HashMap::new(),
+            closed: false,
+            suspended: false,
+            stopped: false,
+        };
+        Self {
+            inner: Arc::new(Mutex::new(inner)),
+            idle: Default::default(),
+        }
+    }
+
+    /// Claim a round whose deadline is `deadline` (unix seconds, 0 if unknown).
+    pub fn claim(&self, heartbeat_key: B256, round: u8, deadline: u64) -> Claim {
+        let key = (heartbeat_key, round);
+        let mut inner = self.inner.lock().unwrap();
+        if inner.closed || inner.stopped {
+            return Claim::Closed;
+        }
+        if inner.suspended {
+            return Claim::Suspended;
+        }
+        if inner.claimed.get(&key).is_some() {
+            return Claim::AlreadyClaimed;
+        }
+        inner.claimed.insert(key, ());
+        inner.in_flight.insert(key, deadline);
+        Claim::Claimed(RoundGuard {
+            claims: self.clone(),
+            key,
+        })
+    }
+
+    /// The number of rounds being processed.
+    pub fn in_flight(&self) -> usize {
+        self.inner.lock().unwrap().in_flight.len()
+    }
+
+    /// Stop accepting new rounds.
+    pub fn close(&self) {
+        self.inner.lock().unwrap().closed = true;
+    }
+
+    /// Accept new rounds again after [`RoundClaims::close`] or [`RoundClaims::drain`].
+    pub fn reopen(&self) {
+        self.inner.lock().unwrap().closed = false;
+    }
+
+    /// Stop accepting new rounds while the node can't submit verdicts, independently of
+    /// [`RoundClaims::close`].
+    pub fn suspend(&self) {
+        self.inner.lock().unwrap().suspended = true;
+    }
+
+    pub fn resume(&self) {
+        self.inner.lock().unwrap().suspended = false;
+    }
+
+    pub fn is_suspended(&self) -> bool {
+        self.inner.lock().unwrap().suspended
+    }
+
+    /// Stop accepting new rounds for good, [`RoundClaims::reopen`] doesn't undo it. Returns
+    /// whether the claims were not stopped already.
+    pub fn stop(&self) -> bool {
+        !std::mem::replace(&mut self.inner.lock().unwrap().stopped, true)
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.inner.lock().unwrap().stopped
+    }
+
+    /// Stop accepting new rounds and wait for the ones in flight to finish, for at most
+    /// `timeout` and never past the latest of their deadlines. Returns the number of rounds
+    /// still in flight.
+    pub async fn drain(&self, timeout: Duration) -> usize {
+        self.close();
+        let limit = Instant::now() + timeout;
+        loop {
+            let notified = self.idle.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            let (count, until) = {
+                let inner = self.inner.lock().unwrap();
+                let deadlines = inner.in_flight.values();
+                let until = if deadlines.clone().any(|deadline| *deadline == 0) {
+                    limit
+                } else {
+                    deadlines
+                        .max()
+                        .map(|deadline| limit.min(instant_at(*deadline)))
+                        .unwrap_or(limit)
+                };
+                (inner.in_flight.len(), until)
+            };
+            if count == 0 {
+                return 0;
+            }
+            tokio::select! {
+                _ = notified => continue,
+                _ = tokio::time::sleep_until(until) => return self.in_flight(),
+            }
+        }
+    }
+}
+
+/// The instant of a unix timestamp in seconds.
+fn instant_at(unix_secs: u64) -> Instant {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    Instant::now() + Duration::from_secs(unix_secs).saturating_sub(now)
+}
+
+/// Marks a claimed round as in flight until dropped.
+#[derive(Debug)]
+pub struct RoundGuard {
+    claims: RoundClaims,
+    key: RoundKey,
+}
+
+impl RoundGuard {
+    /// Give up the round after its processing failed, so it can be claimed again.
+    pub fn release(self) {
+        self.claims.inner.lock().unwrap().claimed.remove(&self.key);
+    }
+}
+
+impl Drop for RoundGuard {
+    fn drop(&mut self) {
+        let mut inner = self.claims.inner.lock().unwrap();
+        inner.in_flight.remove(&self.key);
+        if inner.in_flight.is_empty() {
+            self.claims.idle.notify_waiters();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_claims_per_round() {
+        let claims = RoundClaims::new();
+        let key = B256::repeat_byte(1);
+        let Claim::Claimed(guard) = claims.claim(key, 0, 0) else {
+            panic!("round not claimed");
+        };
+        assert!(matches!(claims.claim(key, 0, 0), Claim::AlreadyClaimed));
+        // An escalated round is a new assignment
+        assert!(matches!(claims.claim(key, 1, 0), Claim::Claimed(_)));
+        assert_eq!(claims.in_flight(), 1);
+
+        guard.release();
+        assert_eq!(claims.in_flight(), 0);
+        assert!(matches!(claims.claim(key, 0, 0), Claim::Claimed(_)));
+
+        claims.suspend();
+        assert!(matches!(claims.claim(key, 2, 0), Claim::Suspended));
+        claims.resume();
+        assert!(matches!(claims.claim(key, 2, 0), Claim::Claimed(_)));
+
+        assert!(claims.stop());
+        assert!(!claims.stop());
+        claims.reopen();
+        assert!(matches!(claims.claim(key, 3, 0), Claim::Closed));
+    }
+
+    #[tokio::test]
+    async fn test_drain_waits_for_in_flight() {
+        let claims = RoundClaims::new();
+        let Claim::Claimed(guard) = claims.claim(B256::ZERO, 0, 0) else {
+            panic!("round not claimed");
+        };
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            drop(guard);
+        });
+        assert_eq!(claims.drain(Duration::from_secs(10)).await, 0);
+        assert!(matches!(
+            claims.claim(B256::repeat_byte(1), 0, 0),
+            Claim::Closed
+        ));
+        claims.reopen();
+        assert!(matches!(
+            claims.claim(B256::repeat_byte(1), 0, 0),
+            Claim::Claimed(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_drain_bounded_by_deadline() {
+        let claims = RoundClaims::new();
+        // The round's deadline has passed, so there is nothing to wait for
+        let _guard = claims.claim(B256::ZERO, 0, 1);
+        let started = std::time::Instant::now();
+        assert_eq!(claims.drain(Duration::from_secs(10)).await, 1);
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,208 @@
+//! Estimation of how long the ETH balance lasts at the rate verdicts are being submitted.
+
+use crate::args::MIN_ETH_BALANCE;
+use alloy::primitives::U256;
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Submissions older than this don't count towards the spend rate.
+const SPEND_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Maximum number of submissions kept for the spend rate.
+const MAX_SUBMISSIONS: usize = 10_000;
+
+const SECS_PER_DAY: f64 = 24.0 * 60.0 * 60.0;
+
+/// The verdicts this node submitted and what they cost.
+#[derive(Debug)]
+pub struct Submissions {
+    count: AtomicU64,
+    started: Instant,
+    /// Cost in wei of the submissions within the spend window, oldest first.
+    costs: Mutex<VecDeque<(Instant, U256)>>,
+}
+
+impl Default for Submissions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Submissions {
+    pub fn new() -> Self {
+        Self {
+            count: AtomicU64::new(0),
+            started: Instant::now(),
+            costs: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Record a submitted verdict, with its cost in wei if it could be looked up.
+    pub fn record(&self, cost: Option<U256>) {
+        self.record_at(Instant::now(), cost);
+    }
+
+    fn record_at(&self, at: Instant, cost: Option<U256>) {
+        self.count.fetch_add(1, Ordering::SeqCst);
+        let Some(cost) = cost else {
+            return;
+        };
+        let mut costs = self.costs.lock().unwrap();
+        costs.push_back((at, cost));
+        if costs.len() > MAX_SUBMISSIONS {
+            costs.pop_front();
+        }
+    }
+
+    /// The number of verdicts submitted since startup.
+    pub fn count(&self) -> u64 {
+        self.count.load(Ordering::SeqCst)
+    }
+
+    /// The ETH runway at the current spend rate, until the balance falls below the minimum the
+    /// node stops at.
+    pub fn runway(&self, balance: U256) -> Runway {
+        self.runway_at(balance, Instant::now())
+    }
+
+    fn runway_at(&self, balance: U256, now: Instant) -> Runway {
+        let mut costs = self.costs.lock().unwrap();
+        while costs
+            .front()
+            .is_some_and(|(at, _)| now.duration_since(*at) > SPEND_WINDOW)
+        {
+            costs.pop_front();
+        }
+        if costs.is_empty() {
+            return Runway::Unknown;
+        }
+
+        // Spread the spending over the whole observed period, so a burst of assignments right
+        // after startup doesn't look like the sustained rate
+        let observed = now.duration_since(self.started).min(SPEND_WINDOW);
+        let spent: f64 = costs.iter().map(|(_, cost)| f64::from(*cost)).sum();
+        let rate = spent / observed.as_secs_f64().max(1.0);
+        if rate <= 0.0 {
+            return Runway::Unknown;
+        }
+        let spendable = balance.saturating_sub(MIN_ETH_BALANCE);
+        Runway::Estimated {
+            remaining: Duration::try_from_secs_f64(f64::from(spendable) / rate)
+                .unwrap_or(Duration::MAX),
+            wei_per_day: rate * SECS_PER_DAY,
+        }
+    }
+}
+
+/// How long the ETH balance is expected to last.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Runway {
+    /// No verdict costs were observed recently.
+    Unknown,
+    Estimated {
+        remaining: Duration,
+        wei_per_day: f64,
+    },
+}
+
+impl Runway {
+    /// The alert level of this runway.
+    pub fn level(&self, thresholds: &RunwayThresholds) -> RunwayLevel {
+        match self {
+            Runway::Estimated { remaining, .. } if *remaining < thresholds.critical => {
+                RunwayLevel::Critical
+            }
+            Runway::Estimated { remaining, .. } if *remaining < thresholds.warn => RunwayLevel::Low,
+            _ => RunwayLevel::Healthy,
+        }
+    }
+}
+
+impl fmt::Display for Runway {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Runway::Unknown => write!(f, "n/a"),
+            Runway::Estimated { remaining, .. } => {
+                let hours = remaining.as_secs() / 3600;
+                if hours >= 48 {
+                    write!(f, "{}d", hours / 24)
+                } else {
+                    write!(f, "{hours}h")
+                }
+            }
+        }
+    }
+}
+
+/// Runways below which alerts are raised.
+#[derive(Debug, Clone, Copy)]
+pub struct RunwayThresholds {
+    pub warn: Duration,
+    pub critical: Duration,
+}
+
+/// How urgently the node needs funding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RunwayLevel {
+    Healthy,
+    Low,
+    Critical,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GWEI: u64 = 1_000_000_000;
+
+    #[test]
+    fn test_runway_from_spend_rate() {
+        let submissions = Submissions::new();
+        let start = submissions.started;
+        assert_eq!(submissions.runway_at(U256::ZERO, start), Runway::Unknown);
+
+        // 24 verdicts of 1000 gwei over a day
+        for hour in 0..24 {
+            let at = start + Duration::from_secs(hour * 3600);
+            submissions.record_at(at, Some(U256::from(1_000 * GWEI)));
+        }
+        submissions.record_at(start, None);
+        assert_eq!(submissions.count(), 25);
+
+        let now = start + Duration::from_secs(24 * 3600);
+        let balance = MIN_ETH_BALANCE + U256::from(7 * 24_000 * GWEI);
+        let Runway::Estimated { remaining, .. } = submissions.runway_at(balance, now) else {
+            panic!("runway not estimated");
+        };
+        assert!((remaining.as_secs_f64() - 7.0 * SECS_PER_DAY).abs() < 1.0);
+        let Runway::Estimated { remaining, .. } = submissions.runway_at(U256::MAX, now) else {
+            panic!("runway not estimated");
+        };
+        assert_eq!(remaining, Duration::MAX);
+
+        // Submissions age out of the window
+        let later = now + SPEND_WINDOW + Duration::from_secs(1);
+        assert_eq!(submissions.runway_at(balance, later), Runway::Unknown);
+    }
+
+    #[test]
+    fn test_runway_level() {
+        let thresholds = RunwayThresholds {
+            warn: Duration::from_secs(7 * 86_400),
+            critical: Duration::from_secs(86_400),
+        };
+        let runway = |days: u64| Runway::Estimated {
+            remaining: Duration::from_secs(days * 86_400),
+            wei_per_day: 1.0,
+        };
+        assert_eq!(runway(10).level(&thresholds), RunwayLevel::Healthy);
+        assert_eq!(runway(3).level(&thresholds), RunwayLevel::Low);
+        assert_eq!(runway(0).level(&thresholds), RunwayLevel::Critical);
+        assert_eq!(Runway::Unknown.level(&thresholds), RunwayLevel::Healthy);
+        assert_eq!(runway(10).to_string(), "10d");
+        assert_eq!(runway(1).to_string(), "24h");
+    }
+}
@@ -0,0 +1,267 @@
+//! Staking lifecycle subcommands, so operators can manage their stake from the node binary
+//! instead of the monitor.
+
+use crate::args::StakingCommand;
+use alloy::primitives::utils::{ParseUnits, format_units, parse_units};
+use alloy::primitives::{Address, U256};
+use anyhow::{Context, Result, bail};
+use blacklight_contract_clients::BlacklightClient;
+use blacklight_contract_clients::staking_operators::Tranche;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::info;
+
+/// Run a staking subcommand with the node's wallet
+pub async fn run_command(client: &BlacklightClient, command: StakingCommand) -> Result<()> {
+    let node = client.signer_address();
+    let decimals = client
+        .token
+        .decimals()
+        .await
+        .context("Failed to read NIL decimals")?;
+    match command {
+        StakingCommand::Stake { amount, operator } => {
+            let amount = parse_nil(&amount, decimals)?;
+            stake(client, operator.unwrap_or(node), amount, decimals).await
+        }
+        StakingCommand::Unstake { amount, operator } => {
+            let amount = parse_nil(&amount, decimals)?;
+            unstake(client, operator.unwrap_or(node), amount, decimals).await
+        }
+        StakingCommand::Withdraw { operator } => {
+            withdraw(client, operator.unwrap_or(node), decimals).await
+        }
+        StakingCommand::Reactivate => {
+            let tx_hash = client.staking.reactivate_operator().await?;
+            info!("✅ Operator {node} reactivated (tx: {tx_hash:?})");
+            Ok(())
+        }
+        StakingCommand::Deactivate => {
+            let tx_hash = client.staking.deactivate_operator().await?;
+            info!("⏸️ Operator {node} deactivated (tx: {tx_hash:?})");
+            Ok(())
+        }
+        StakingCommand::StakeStatus { operator } => {
+            print_status(client, operator.unwrap_or(node), decimals).await
+        }
+    }
+}
+
+/// Stake `amount` to `operator`, approving the staking contract first if its allowance is short
+pub(crate) async fn stake(
+    client: &BlacklightClient,
+    operator: Address,
+    amount: U256,
+    decimals: u8,
+) -> Result<()> {
+    let staker = client.signer_address();
+    let balance = client.token.balance_of(staker).await?;
+    if balance < amount {
+        bail!(
+            "Insufficient NIL balance: {} NIL, staking needs {} NIL",
+            format_nil(balance, decimals),
+            format_nil(amount, decimals)
+        );
+    }
+
+    let staking = client.staking.address();
+    let allowance = client.token.allowance(staker, staking).await?;
+    if allowance < amount {
+        info!(
+            "Approving the staking contract to spend {} NIL",
+            format_nil(amount, decimals)
+        );
+        let tx_hash = client.token.approve(staking, amount).await?;
+        info!("Approved (tx: {tx_hash:?})");
+    }
+
+    let tx_hash = client.staking.stake_to(operator, amount).await?;
+    info!(
+        "✅ Staked {} NIL to {operator} (tx: {tx_hash:?})",
+        format_nil(amount, decimals)
+    );
+    Ok(())
+}
+
+/// Request to unstake `amount` from `operator`
+async fn unstake(
+    client: &BlacklightClient,
+    operator: Address,
+    amount: U256,
+    decimals: u8,
+) -> Result<()> {
+    let staked = client.staking.stake_of(operator).await?;
+    if staked < amount {
+        bail!(
+            "Operator {operator} only has {} NIL staked",
+            format_nil(staked, decimals)
+        );
+    }
+    let tx_hash = client.staking.request_unstake(operator, amount).await?;
+    info!(
+        "✅ Requested to unstake {} NIL from {operator} (tx: {tx_hash:?})",
+        format_nil(amount, decimals)
+    );
+    for tranche in client.staking.unbonding_tranches(operator).await? {
+        info!(
+            "Unbonding {} NIL, {}",
+            format_nil(tranche.amount, decimals),
+            format_release(tranche.releaseTime, now())
+        );
+    }
+    Ok(())
+}
+
+/// Withdraw the released tranches unstaked from `operator`
+async fn withdraw(client: &BlacklightClient, operator: Address, decimals: u8) -> Result<()> {
+    let tranches = client.staking.unbonding_tranches(operator).await?;
+    let now = now();
+    let released = released_amount(&tranches, now);
+    if released.is_zero() {
+        match tranches.iter().map(|t| t.releaseTime).min() {
+            Some(release) => bail!(
+                "No unbonded NIL is withdrawable yet, the next tranche is {}",
+                format_release(release, now)
+            ),
+            None => bail!("No NIL is unbonding from {operator}"),
+        }
+    }
+    let tx_hash = client.staking.withdraw_unstaked(operator).await?;
+    info!(
+        "✅ Withdrew {} NIL unstaked from {operator} (tx: {tx_hash:?})",
+        format_nil(released, decimals)
+    );
+    Ok(())
+}
+
+/// Print an operator's stake, status and unbonding tranches
+async fn print_status(client: &BlacklightClient, operator: Address, decimals: u8) -> Result<()> {
+    let staker = client.signer_address();
+    let staked = client.staking.stake_of(operator).await?;
+    let total = client.staking.total_staked().await?;
+    let active = client.staking.is_active_operator(operator).await?;
+    let jailed_until = client.staking.jailed_until(operator).await?;
+    let balance = client.token.balance_of(staker).await?;
+    let allowance = client
+        .token
+        .allowance(staker, client.staking.address())
+        .await?;
+    let unbonding_staker = client.staking.unbonding_staker(operator).await?;
+    let tranches = client.staking.unbonding_tranches(operator).await?;
+    let now = now();
+
+    println!("Operator:        {operator}");
+    println!(
+        "Staked:          {} NIL (of {} NIL total)",
+        format_nil(staked, decimals),
+        format_nil(total, decimals)
+    );
+    println!("Active:          {}", if active { "yes" } else { "no" });
+    if jailed_until > now {
+        println!("Jailed:          {}", format_release(jailed_until, now));
+    }
+    println!(
+        "Wallet:          {staker}, {} NIL, {} NIL approved for staking",
+        format_nil(balance, decimals),
+        format_nil(allowance, decimals)
+    );
+    if unbonding_staker.is_zero() || tranches.is_empty() {
+        println!("Unbonding:       none");
+        return Ok(());
+    }
+    println!("Unbonding:       requested by {unbonding_staker}");
+    for tranche in &tranches {
+        println!(
+            "  {} NIL, {}",
+            format_nil(tranche.amount, decimals),
+            format_release(tranche.releaseTime, now)
+        );
+    }
+    Ok(())
+}
+
+/// Parse an amount of NIL into its base units
+fn parse_nil(amount: &str, decimals: u8) -> Result<U256> {
+    let ParseUnits::U256(amount) =
+        parse_units(amount, decimals).with_context(|| format!("Invalid NIL amount '{amount}'"))?
+    else {
+        bail!("Invalid NIL amount '{amount}', it must not be negative");
+    };
+    if amount.is_zero() {
+        bail!("NIL amount must not be zero");
+    }
+    Ok(amount)
+}
+
+pub(crate) fn format_nil(amount: U256, decimals: u8) -> String {
+    format_units(amount, decimals).unwrap_or_else(|_| amount.to_string())
+}
+
+/// The total amount of the tranches released at `now`
+fn released_amount(tranches: &[Tranche], now: u64) -> U256 {
+    tranches
+        .iter()
+        .filter(|tranche| tranche.releaseTime <= now)
+        .map(|tranche| tranche.amount)
+        .sum()
+}
+
+/// Describe when a release time (unix seconds) is reached, relative to `now`
+pub(crate) fn format_release(release: u64, now: u64) -> String {
+    if release <= now {
+        return format!("released at {release} (withdrawable)");
+    }
+    let remaining = release - now;
+    let (days, hours, minutes) = (
+        remaining / 86_400,
+        remaining % 86_400 / 3600,
+        remaining % 3600 / 60,
+    );
+    let remaining = if days > 0 {
+        format!("{days}d {hours}h")
+    } else if hours > 0 {
+        format!("{hours}h {minutes}m")
+    } else {
+        format!("{}m", minutes.max(1))
+    };
+    format!("releases at {release} (in {remaining})")
+}
+
+pub(crate) fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_nil() {
+        assert_eq!(parse_nil("1000", 6).unwrap(), U256::from(1_000_000_000u64));
+        assert_eq!(parse_nil("0.5", 6).unwrap(), U256::from(500_000u64));
+        assert!(parse_nil("0", 6).is_err());
+        assert!(parse_nil("-1", 6).is_err());
+        assert!(parse_nil("0.0000001", 6).is_err());
+        assert!(parse_nil("lots", 6).is_err());
+    }
+
+    #[test]
+    fn test_unbonding_tranches() {
+        let tranche = |amount: u64, release_time: u64| Tranche {
+            amount: U256::from(amount),
+            releaseTime: release_time,
+        };
+        let tranches = [tranche(5, 100), tranche(7, 200), tranche(11, 300)];
+        assert_eq!(released_amount(&tranches, 50), U256::ZERO);
+        assert_eq!(released_amount(&tranches, 200), U256::from(12));
+
+        assert_eq!(format_release(100, 200), "released at 100 (withdrawable)");
+        assert_eq!(
+            format_release(200 + 2 * 86_400 + 3 * 3600, 200),
+            "releases at 183800 (in 2d 3h)"
+        );
+        assert_eq!(format_release(230, 200), "releases at 230 (in 1m)");
+    }
+}
@@ -0,0 +1,506 @@
+//! Record/replay of the HTTP exchanges made while verifying an HTX.
+//!
+//! Verification inputs come from URLs in the HTX (reports, builder indexes), the artifact bucket
+//! and Intel's collateral service. In tape mode every one of these URLs is rewritten to a local
+//! server, the relay's, which serves the same URLs as it does. When recording, the server
+//! forwards each request to the original URL and stores the response; when replaying, it answers
+//! from the tape and never touches the network.
+//!
+//! AMD certificates are fetched from within the report verifier, so they can't be rewritten.
+//! Instead the certificate cache is stored in the tape after recording and restored before
+//! replaying. Phala quotes are verified at the time the tape was recorded, so collateral that has
+//! since expired still verifies.
+//!
+//! Artifacts that are already cached are not downloaded, so record with an empty
+//! `--artifact-cache` to get a self-contained tape.
+
+use crate::args::TapeCommand;
+use crate::artifacts::ArtifactCache;
+use crate::evidence::{Evidence, snapshot_certificates};
+use crate::http::{Exchange, HttpClient};
+use crate::relay::{self, LocalServer, Reply, Request, Session};
+use crate::verification::{HtxVerifier, VerificationTimeouts, library_prefixes};
+use alloy::hex;
+use anyhow::{Context, Result, bail};
+use blacklight_contract_clients::heartbeat_manager::Verdict;
+use blacklight_contract_clients::htx::Htx;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+pub const TAPE_FORMAT: &str = "blacklight-tape/v1";
+
+/// Whether a tape is being recorded or replayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TapeMode {
+    Record,
+    Replay,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    recorded_at: u64,
+    raw_htx: Vec<u8>,
+    verdict: Option<Verdict>,
+    exchanges: Vec<Exchange>,
+    certificates: Value,
+    /// Number of times each URL was served while replaying.
+    served: HashMap<String, usize>,
+}
+
+/// The exchanges of one HTX verification.
+#[derive(Debug)]
+pub struct Tape {
+    mode: TapeMode,
+    inner: Mutex<Inner>,
+}
+
+impl Tape {
+    /// Start recording the verification of `raw_htx`.
+    pub fn record(raw_htx: Vec<u8>) -> Self {
+        let recorded_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let inner = Inner {
+            recorded_at,
+            raw_htx,
+            certificates: json!({}),
+            ..Default::default()
+        };
+        Self {
+            mode: TapeMode::Record,
+            inner: Mutex::new(inner),
+        }
+    }
+
+    /// Load a recorded tape for replay.
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents =
+            fs::read(path).with_context(|| format!("Failed to read tape {}", path.display()))?;
+        let tape: Value = serde_json::from_slice(&contents).context("Invalid tape")?;
+        Self::from_json(&tape)
+    }
+
+    fn from_json(tape: &Value) -> Result<Self> {
+        if tape["format"] != TAPE_FORMAT {
+            bail!("unsupported tape format {}", tape["format"]);
+        }
+        let decode = |value: &Value| hex::decode(value.as_str().unwrap_or_default());
+        let mut exchanges = Vec::new();
+        for exchange in tape["exchanges"].as_array().into_iter().flatten() {
+            exchanges.push(Exchange {
+                url: exchange["url"].as_str().unwrap_or_default().to_string(),
+                status: exchange["status"].as_u64().unwrap_or_default() as u16,
+                content_type: exchange["content_type"].as_str().map(str::to_string),
+                body: decode(&exchange["body"]).context("invalid exchange body")?,
+            });
+        }
+        let inner = Inner {
+            recorded_at: tape["recorded_at"].as_u64().unwrap_or_default(),
+            raw_htx: decode(&tape["raw_htx"]).context("invalid raw_htx")?,
+            verdict: tape["verdict"]
+                .as_u64()
+                .and_then(|code| Verdict::from_code(code as u8)),
+            exchanges,
+            certificates: tape["certificates"].clone(),
+            served: HashMap::new(),
+        };
+        Ok(Self {
+            mode: TapeMode::Replay,
+            inner: Mutex::new(inner),
+        })
+    }
+
+    pub fn mode(&self) -> TapeMode {
+        self.mode
+    }
+
+    /// The time verification is pinned to, in unix seconds.
+    pub fn recorded_at(&self) -> u64 {
+        self.inner.lock().unwrap().recorded_at
+    }
+
+    pub fn raw_htx(&self) -> Vec<u8> {
+        self.inner.lock().unwrap().raw_htx.clone()
+    }
+
+    /// The verdict reached when the tape was recorded.
+    pub fn verdict(&self) -> Option<Verdict> {
+        self.inner.lock().unwrap().verdict
+    }
+
+    pub fn set_verdict(&self, verdict: Verdict) {
+        self.inner.lock().unwrap().verdict = Some(verdict);
+    }
+
+    pub fn set_certificates(&self, certificates: Value) {
+        self.inner.lock().unwrap().certificates = certificates;
+    }
+
+    /// Write the recorded certificates into `cert_cache`.
+    pub fn restore_certificates(&self, cert_cache: &Path) -> Result<()> {
+        let certificates = self.inner.lock().unwrap().certificates.clone();
+        fs::create_dir_all(cert_cache).context("Failed to create certificate cache")?;
+        for (name, contents) in certificates.as_object().into_iter().flatten() {
+            let contents = hex::decode(contents.as_str().unwrap_or_default())
+                .with_context(|| format!("invalid certificate {name}"))?;
+            fs::write(cert_cache.join(name), contents)
+                .with_context(|| format!("Failed to restore certificate {name}"))?;
+        }
+        Ok(())
+    }
+
+    fn push(&self, exchange: Exchange) {
+        self.inner.lock().unwrap().exchanges.push(exchange);
+    }
+
+    /// The response to replay for `url`: recorded responses to the same URL are served in
+    /// order, and the last one is repeated once they run out.
+    pub fn replay(&self, url: &str) -> Option<Exchange> {
+        let mut inner = self.inner.lock().unwrap();
+        let matching: Vec<_> = inner.exchanges.iter().filter(|e| e.url == url).collect();
+        let served = inner.served.get(url).copied().unwrap_or_default();
+        let exchange = matching.get(served).or(matching.last()).cloned().cloned();
+        *inner.served.entry(url.to_string()).or_default() += 1;
+        exchange
+    }
+
+    pub fn to_json(&self) -> Value {
+        let inner = self.inner.lock().unwrap();
+        let exchanges: Vec<_> = inner
+            .exchanges
+            .iter()
+            .map(|e| {
+                json!({
+                    "url": e.url,
+                    "status": e.status,
+                    "content_type": e.content_type,
+                    "body": hex::encode(&e.body),
+                })
+            })
+            .collect();
+        json!({
+            "format": TAPE_FORMAT,
+            "recorded_at": inner.recorded_at,
+            "raw_htx": hex::encode(&inner.raw_htx),
+            "verdict": inner.verdict.map(Verdict::code),
+            "exchanges": exchanges,
+            "certificates": inner.certificates,
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let contents = serde_json::to_vec_pretty(&self.to_json())?;
+        fs::write(path, contents)
+            .with_context(|| format!("Failed to write tape {}", path.display()))
+    }
+}
+
+/// Local HTTP server that records or replays the exchanges of a [`Tape`].
+#[derive(Debug, Clone)]
+pub struct TapeServer {
+    server: LocalServer,
+    tape: Arc<Tape>,
+}
+
+impl TapeServer {
+    /// Start serving `tape` on a local port for URLs under the `allowed` prefixes or allowed by a
+    /// session. Recorded requests are forwarded with `http`.
+    pub async fn start(tape: Arc<Tape>, http: HttpClient, allowed: Vec<String>) -> Result<Self> {
+        let served = tape.clone();
+        let server = relay::serve(allowed, move |request| {
+            let tape = served.clone();
+            let http = http.clone();
+            Box::pin(async move { Reply::Full(exchange(&tape, request, &http).await) })
+        })
+        .await
+        .context("Failed to start tape server")?;
+        Ok(Self { server, tape })
+    }
+
+    pub fn tape(&self) -> &Tape {
+        &self.tape
+    }
+
+    /// The local URL `url` is served at.
+    pub fn rewrite(&self, url: &str) -> String {
+        self.server.rewrite(url)
+    }
+
+    /// Allow requests for `urls` until the session is dropped, keeping the responses if `keep`.
+    pub fn session(&self, urls: &[&str], keep: bool) -> Session {
+        self.server.session(urls, keep)
+    }
+}
+
+/// Answer `request` from the tape, or record its response.
+async fn exchange(tape: &Tape, request: Request, http: &HttpClient) -> Exchange {
+    match tape.mode() {
+        TapeMode::Replay => tape.replay(&request.url).unwrap_or_else(|| {
+            warn!(url = %request.url, "Request not on tape");
+            Exchange {
+                url: request.url,
+                status: 502,
+                content_type: None,
+                body: b"not on tape".to_vec(),
+            }
+        }),
+        TapeMode::Record => {
+            // Connection failures are recorded and replayed as gateway errors
+            let exchange = relay::fetch(request, http).await;
+            tape.push(exchange.clone());
+            exchange
+        }
+    }
+}
+
+/// Verify the HTX on the tape and return the verdict.
+async fn verify(verifier: &HtxVerifier, raw_htx: &[u8]) -> Verdict {
+    let mut timer = verifier.start_timer(0);
+    let mut evidence = Evidence::default();
+    let result = match serde_json::from_slice::<Htx>(raw_htx) {
+        Ok(Htx::Nillion(htx)) => {
+            verifier
+                .verify_nillion_htx(&htx, &mut timer, &mut evidence)
+                .await
+        }
+        Ok(Htx::Phala(htx)) => {
+            verifier
+                .verify_phala_htx(&htx, &mut timer, &mut evidence)
+                .await
+        }
+        Err(e) => {
+            warn!(error = %e, "Failed to parse HTX data");
+            return Verdict::Failure;
+        }
+    };
+    match result {
+        Ok(()) => Verdict::Success,
+        Err(e) => {
+            info!(error = %e, timings = %timer, "Verification failed");
+            e.verdict()
+        }
+    }
+}
+
+/// Run a tape command with a verifier whose traffic goes through a tape server.
+pub async fn run_command(
+    artifacts: Arc<ArtifactCache>,
+    artifacts_url: &str,
+    timeouts: VerificationTimeouts,
+    http: HttpClient,
+    command: TapeCommand,
+) -> Result<()> {
+    let tape = Arc::new(command.tape()?);
+    let server =
+        TapeServer::start(tape.clone(), http.clone(), library_prefixes(artifacts_url)).await?;
+    // A fresh certificate cache, so recording fetches every certificate it needs and replaying
+    // never sees certificates that aren't on the tape
+    let cert_cache =
+        std::env::temp_dir().join(format!("blacklight-tape-certs-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&cert_cache);
+    fs::create_dir_all(&cert_cache).context("Failed to create certificate cache")?;
+    let verifier = HtxVerifier::new(
+        artifacts,
+        server.rewrite(artifacts_url),
+        cert_cache.clone(),
+        timeouts,
+        http,
+    )?
+    .with_tape(server);
+
+    let result = run(&verifier, &tape, &cert_cache, command).await;
+    let _ = fs::remove_dir_all(&cert_cache);
+    result
+}
+
+async fn run(
+    verifier: &HtxVerifier,
+    tape: &Tape,
+    cert_cache: &Path,
+    command: TapeCommand,
+) -> Result<()> {
+    match command {
+        TapeCommand::Record { output, .. } => {
+            let verdict = verify(verifier, &tape.raw_htx()).await;
+            tape.set_verdict(verdict);
+            tape.set_certificates(snapshot_certificates(cert_cache)?);
+            tape.save(&output)?;
+            println!("verdict: {verdict:?}");
+            println!("tape written to {}", output.display());
+        }
+        TapeCommand::Replay { .. } => {
+            tape.restore_certificates(cert_cache)?;
+            let verdict = verify(verifier, &tape.raw_htx()).await;
+            println!("verdict: {verdict:?}");
+            match tape.verdict() {
+                Some(recorded) if recorded == verdict => println!("matches the recorded verdict"),
+                Some(recorded) => bail!("verdict differs from the recorded verdict {recorded:?}"),
+                None => println!("tape has no recorded verdict"),
+            }
+        }
+    }
+    Ok(())
+}
+
+impl TapeCommand {
+    /// Load or start the tape for this command.
+    pub fn tape(&self) -> Result<Tape> {
+        match self {
+            TapeCommand::Record { htx, .. } => {
+                let raw_htx = fs::read(htx)
+                    .with_context(|| format!("Failed to read HTX {}", htx.display()))?;
+                Ok(Tape::record(raw_htx))
+            }
+            TapeCommand::Replay { tape } => Tape::load(tape),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::Client;
+    use std::time::Duration;
+
+    #[test]
+    fn test_tape_roundtrip() {
+        let tape = Tape::record(b"{}".to_vec());
+        tape.push(Exchange {
+            url: "https://example.com/a".to_string(),
+            status: 200,
+            content_type: Some("application/json".to_string()),
+            body: b"[1]".to_vec(),
+        });
+        tape.push(Exchange {
+            url: "https://example.com/a".to_string(),
+            status: 500,
+            content_type: None,
+            body: Vec::new(),
+        });
+        tape.set_verdict(Verdict::Inconclusive);
+
+        let replayed = Tape::from_json(&tape.to_json()).unwrap();
+        assert_eq!(replayed.mode(), TapeMode::Replay);
+        assert_eq!(replayed.raw_htx(), b"{}");
+        assert_eq!(replayed.verdict(), Some(Verdict::Inconclusive));
+        assert_eq!(
+            replayed.replay("https://example.com/a").unwrap().status,
+            200
+        );
+        assert_eq!(
+            replayed.replay("https://example.com/a").unwrap().status,
+            500
+        );
+        assert_eq!(
+            replayed.replay("https://example.com/a").unwrap().status,
+            500
+        );
+        assert!(replayed.replay("https://example.com/b").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_server_replays_offline() {
+        let tape = Tape::record(Vec::new());
+        tape.push(Exchange {
+            url: "https://builder.example.com/index.json".to_string(),
+            status: 200,
+            content_type: Some("application/json".to_string()),
+            body: br#"{"v1":"aa"}"#.to_vec(),
+        });
+        let tape = Arc::new(Tape::from_json(&tape.to_json()).unwrap());
+        let server = TapeServer::start(tape, HttpClient::new(Client::new(), 1), Vec::new())
+            .await
+            .unwrap();
+
+        let builder_url = "https://builder.example.com/index.json";
+        let missing_url = "https://builder.example.com/other.json";
+        let session = server.session(&[builder_url, missing_url], false);
+        let url = session.rewrite(builder_url);
+        assert!(url.starts_with("http://127.0.0.1:"));
+        let response = Client::new().get(&url).send().await.unwrap();
+        assert_eq!(response.status(), 200);
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["v1"], "aa");
+
+        let missing = session.rewrite(missing_url);
+        let response = Client::new().get(&missing).send().await.unwrap();
+        assert_eq!(response.status(), 502);
+
+        // URLs outside the session aren't served
+        let response = Client::new()
+            .get(server.rewrite(builder_url))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 403);
+    }
+
+    /// Replay a tape from `data/tapes` and return its recorded and replayed verdicts.
+    async fn replay_fixture(name: &str) -> (Option<Verdict>, Verdict) {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../data/tapes")
+            .join(name);
+        let tape = Arc::new(Tape::load(&path).unwrap());
+        let recorded = tape.verdict();
+        let dir = std::env::temp_dir().join(format!(
+            "blacklight-tape-test-{}-{name}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("certs")).unwrap();
+        tape.restore_certificates(&dir.join("certs")).unwrap();
+
+        let artifacts_url = "https://nilcc.s3.eu-west-1.amazonaws.com";
+        let http = HttpClient::new(Client::new(), 1);
+        let server = TapeServer::start(tape.clone(), http.clone(), library_prefixes(artifacts_url))
+            .await
+            .unwrap();
+        let timeouts = VerificationTimeouts {
+            report_fetch: Duration::from_secs(5),
+            artifact_download: Duration::from_secs(5),
+            measurement_generation: Duration::from_secs(5),
+            cert_fetch: Duration::from_secs(5),
+            builder_index: Duration::from_secs(5),
+            quote_collateral: Duration::from_secs(5),
+            submission_margin: Duration::from_secs(1),
+        };
+        let verifier = HtxVerifier::new(
+            Arc::new(ArtifactCache::new(
+                dir.join("artifacts"),
+                Default::default(),
+            )),
+            server.rewrite(artifacts_url),
+            dir.join("certs"),
+            timeouts,
+            http,
+        )
+        .unwrap()
+        .with_tape(server);
+
+        let verdict = verify(&verifier, &tape.raw_htx()).await;
+        let _ = fs::remove_dir_all(&dir);
+        (recorded, verdict)
+    }
+
+    #[tokio::test]
+    async fn test_replay_nilcc_tape() {
+        // The report endpoint answered 503 while recording
+        let (recorded, verdict) = replay_fixture("nilcc-report-unavailable.json").await;
+        assert_eq!(recorded, Some(Verdict::Inconclusive));
+        assert_eq!(verdict, Verdict::Inconclusive);
+    }
+
+    #[tokio::test]
+    async fn test_replay_phala_tape() {
+        // The compose hash matches, but the collateral service was unreachable while recording
+        let (recorded, verdict) = replay_fixture("phala-collateral-unavailable.json").await;
+        assert_eq!(recorded, Some(Verdict::Failure));
+        assert_eq!(verdict, Verdict::Failure);
+    }
+}
@@ -1,4 +1,12 @@
+use crate::artifacts::{ArtifactCache, Integrity};
+use crate::evidence::{Evidence, EvidenceStore, chain_certificates};
+use crate::health::DependencyHealth;
+use crate::http::HttpClient;
+use crate::memo::{LruCache, TtlCache};
+use crate::relay::{Relay, Session};
+use crate::tape::TapeServer;
 use alloy::hex;
+use alloy::primitives::{Address, B256};
 use anyhow::Context;
 use async_trait::async_trait;
 use attestation_verification::nilcc_artifacts::Artifacts;
@@ -13,16 +21,182 @@ use attestation_verification::{
 use attestation_verification::{VerificationError as ExtVerificationError, VmType};
 use blacklight_contract_clients::heartbeat_manager::Verdict;
 use blacklight_contract_clients::htx::{NillionHtx, PhalaHtx};
-use dcap_qvl::collateral::get_collateral_and_verify;
-use reqwest::Client;
+use dcap_qvl::collateral::get_collateral;
 use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::{Mutex, OnceCell, watch};
+use tracing::{info, warn};
 
-const ARTIFACTS_URL: &str = "https://nilcc.s3.eu-west-1.amazonaws.com";
+tokio::task_local! {
+    /// Reports artifact download progress of the current verification back to its caller.
+    static DOWNLOAD_PROGRESS: watch::Sender<DownloadProgress>;
+}
+
+/// A stage of the verification pipeline that is individually time-bounded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    ReportFetch,
+    ArtifactDownload,
+    MeasurementGeneration,
+    CertFetch,
+    BuilderIndex,
+    QuoteCollateral,
+}
+
+impl Stage {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Stage::ReportFetch => "report_fetch",
+            Stage::ArtifactDownload => "artifact_download",
+            Stage::MeasurementGeneration => "measurement_generation",
+            Stage::CertFetch => "cert_fetch",
+            Stage::BuilderIndex => "builder_index",
+            Stage::QuoteCollateral => "quote_collateral",
+        }
+    }
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
 
+/// Time limits applied to each stage of the verification pipeline.
+#[derive(Debug, Clone)]
+pub struct VerificationTimeouts {
+    pub report_fetch: Duration,
+    pub artifact_download: Duration,
+    pub measurement_generation: Duration,
+    pub cert_fetch: Duration,
+    pub builder_index: Duration,
+    pub quote_collateral: Duration,
+    /// Time reserved before the round deadline to submit the verdict.
+    pub submission_margin: Duration,
+}
+
+impl VerificationTimeouts {
+    /// The budget used when a round has no known deadline: every stage may use its full timeout.
+    fn total(&self) -> Duration {
+        self.report_fetch
+            + self.artifact_download
+            + self.measurement_generation
+            + self.cert_fetch
+            + self.builder_index
+            + self.quote_collateral
+    }
+}
+
+/// Tracks the overall time budget and per-stage timings of a single HTX verification.
+///
+/// Each stage runs under the smaller of its own timeout and what is left of the overall budget,
+/// and is cancelled once that limit elapses.
 #[derive(Debug)]
+pub struct StageTimer {
+    deadline: Instant,
+    timings: Vec<(Stage, Duration)>,
+}
+
+impl StageTimer {
+    pub fn new(budget: Duration) -> Self {
+        Self {
+            deadline: Instant::now() + budget,
+            timings: Vec::new(),
+        }
+    }
+
+    /// The time left before the overall budget is exhausted.
+    pub fn remaining(&self) -> Duration {
+        self.deadline.saturating_duration_since(Instant::now())
+    }
+
+    /// The time spent in each stage that has run so far, in execution order.
+    #[cfg(test)]
+    pub fn timings(&self) -> &[(Stage, Duration)] {
+        &self.timings
+    }
+
+    async fn run<T, F>(
+        &mut self,
+        stage: Stage,
+        timeout: Duration,
+        future: F,
+    ) -> Result<T, VerificationError>
+    where
+        F: Future<Output = Result<T, VerificationError>>,
+    {
+        let remaining = self.remaining();
+        let limit = timeout.min(remaining);
+        let started = Instant::now();
+        let result = tokio::time::timeout(limit, future).await;
+        self.timings.push((stage, started.elapsed()));
+        match result {
+            Ok(result) => result,
+            Err(_) if remaining < timeout => Err(VerificationError::DeadlineExceeded(stage)),
+            Err(_) => Err(VerificationError::StageTimeout(stage)),
+        }
+    }
+
+    /// Moves `elapsed` out of the time recorded for `from` into a new entry for `into`.
+    fn split(&mut self, from: Stage, into: Stage, elapsed: Duration) {
+        if let Some((_, duration)) = self.timings.iter_mut().find(|(stage, _)| *stage == from) {
+            *duration = duration.saturating_sub(elapsed);
+        }
+        self.timings.push((into, elapsed));
+    }
+}
+
+impl fmt::Display for StageTimer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut first = true;
+        for (stage, duration) in &self.timings {
+            if !first {
+                write!(f, " ")?;
+            }
+            write!(f, "{stage}={}ms", duration.as_millis())?;
+            first = false;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+enum DownloadProgress {
+    #[default]
+    Idle,
+    Started(Instant),
+    Finished(Duration),
+}
+
+/// Resolves once an artifact download reported through `progress` runs longer than `limit`.
+async fn artifact_download_watchdog(
+    mut progress: watch::Receiver<DownloadProgress>,
+    limit: Duration,
+) {
+    loop {
+        let state = *progress.borrow_and_update();
+        let changed = match state {
+            DownloadProgress::Started(started) => {
+                let expires_at = tokio::time::Instant::from_std(started + limit);
+                tokio::select! {
+                    _ = tokio::time::sleep_until(expires_at) => return,
+                    changed = progress.changed() => changed,
+                }
+            }
+            DownloadProgress::Idle | DownloadProgress::Finished(_) => progress.changed().await,
+        };
+        if changed.is_err() {
+            break;
+        }
+    }
+    std::future::pending::<()>().await
+}
+
+#[derive(Debug, Clone)]
 pub enum VerificationError {
     // Inconclusive errors - operational/infrastructure failures
     FetchReport(String),
@@ -31,6 +205,9 @@ pub enum VerificationError {
     PhalaEventLogParse(String),
     FetchCerts(String),
     DetectProcessor(String),
+    ArtifactIntegrity(String),
+    StageTimeout(Stage),
+    DeadlineExceeded(Stage),
 
     // Malicious errors - cryptographic verification failures
     VerifyReport(String),
@@ -58,7 +235,10 @@ impl VerificationError {
             | PhalaEventLogParse(_)
             | FetchCerts(_)
             | InvalidCertificate(_)
-            | DetectProcessor(_) => Verdict::Inconclusive,
+            | DetectProcessor(_)
+            | ArtifactIntegrity(_)
+            | StageTimeout(_)
+            | DeadlineExceeded(_) => Verdict::Inconclusive,
 
             // Failure - cryptographic verification failures (indicates potential tampering)
             VerifyReport(_)
@@ -92,6 +272,11 @@ impl VerificationError {
             FetchCerts(e) => format!("could not fetch AMD certificates: {e}"),
             DetectProcessor(e) => format!("could not detect processor type: {e}"),
             InvalidCertificate(e) => format!("invalid certificate obtained from AMD: {e}"),
+            ArtifactIntegrity(e) => format!("nilcc artifacts failed integrity check: {e}"),
+            StageTimeout(stage) => format!("{stage} timed out"),
+            DeadlineExceeded(stage) => {
+                format!("verification budget exhausted during {stage}")
+            }
 
             // Malicious errors
             VerifyReport(e) => format!("attestation report verification failed: {e}"),
@@ -115,28 +300,314 @@ impl std::error::Error for VerificationError {}
 pub struct HtxVerifier {
     report_fetcher: Arc<ReportFetcher>,
     report_verifier: Arc<ReportVerifier>,
-    artifact_cache: PathBuf,
+    artifacts: Arc<ArtifactCache>,
+    artifacts_url: String,
+    downloader: LockedDownloader,
     verify_lock: Arc<Mutex<()>>,
+    timeouts: VerificationTimeouts,
+    http: HttpClient,
+    measurements: Arc<std::sync::Mutex<LruCache<MeasurementKey, Vec<u8>>>>,
+    verdicts: Arc<std::sync::Mutex<VerdictCache>>,
+    in_flight: Arc<std::sync::Mutex<HashMap<[u8; 32], SharedVerification>>>,
+    health: Arc<DependencyHealth>,
+    cert_cache: PathBuf,
+    evidence_store: Option<EvidenceStore>,
+    tape: Option<TapeServer>,
+    relay: Option<Relay>,
+}
+
+/// The collateral service dcap-qvl uses when no PCCS URL is given.
+const PHALA_PCCS_URL: &str = "https://pccs.phala.network";
+
+/// The URL prefixes the verification libraries request besides the URLs of the HTX verified,
+/// which a relay or tape server must serve.
+pub fn library_prefixes(artifacts_url: &str) -> Vec<String> {
+    vec![artifacts_url.to_string(), PHALA_PCCS_URL.to_string()]
+}
+
+/// Verification results keyed by the SHA-256 hash of the raw HTX.
+type VerdictCache = TtlCache<[u8; 32], Result<(), VerificationError>>;
+
+/// The result of a verification in progress, awaited by every identity assigned the HTX.
+type SharedVerification = Arc<OnceCell<Result<(), VerificationError>>>;
+
+/// The inputs that fully determine a generated nilCC measurement.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct MeasurementKey {
+    docker_compose_hash: [u8; 32],
+    cpu_count: u32,
+    vm_type: String,
+    nilcc_version: String,
+    /// SHA-256 hash of the report metadata, serialized as JSON
+    metadata_hash: [u8; 32],
 }
 
 impl HtxVerifier {
-    pub fn new(artifact_cache: PathBuf, cert_cache: PathBuf) -> anyhow::Result<Self> {
+    pub fn new(
+        artifacts: Arc<ArtifactCache>,
+        artifacts_url: String,
+        cert_cache: PathBuf,
+        timeouts: VerificationTimeouts,
+        http: HttpClient,
+    ) -> anyhow::Result<Self> {
+        let downloader = LockedDownloader::new(artifacts.clone());
         let report_fetcher = ReportFetcher::new(
-            artifact_cache.clone(),
-            ARTIFACTS_URL.to_string(),
-            Box::new(LockedDownloader::default()),
+            artifacts.root().to_path_buf(),
+            artifacts_url.clone(),
+            Box::new(downloader.clone()),
         );
-        let fetcher =
-            DefaultCertificateFetcher::new(cert_cache).context("Creating certificate fetcher")?;
+        let fetcher = DefaultCertificateFetcher::new(cert_cache.clone())
+            .context("Creating certificate fetcher")?;
         let report_verifier = ReportVerifier::new(Arc::new(fetcher));
         Ok(Self {
             report_fetcher: Arc::new(report_fetcher),
             report_verifier: Arc::new(report_verifier),
-            artifact_cache,
+            artifacts,
+            artifacts_url,
+            downloader,
             verify_lock: Default::default(),
+            timeouts,
+            http,
+            measurements: Arc::new(std::sync::Mutex::new(LruCache::new(0))),
+            verdicts: Arc::new(std::sync::Mutex::new(TtlCache::new(Duration::ZERO))),
+            in_flight: Default::default(),
+            health: Default::default(),
+            cert_cache,
+            evidence_store: None,
+            tape: None,
+            relay: None,
         })
     }
 
+    /// Route the verification traffic through a tape server. The artifacts URL passed to
+    /// [`HtxVerifier::new`] must already be rewritten by the same server.
+    pub fn with_tape(mut self, tape: TapeServer) -> Self {
+        self.tape = Some(tape);
+        self
+    }
+
+    /// Make the report and collateral requests of the verification libraries through `relay`,
+    /// and so with the shared HTTP client. The artifacts URL passed to [`HtxVerifier::new`]
+    /// should be rewritten by the relay too, for artifact downloads to share the host's limit.
+    pub fn with_relay(mut self, relay: Relay) -> Self {
+        self.relay = Some(relay);
+        self
+    }
+
+    /// The URL to request an HTX's `url` at with the shared client, from the tape when one is in
+    /// use.
+    fn htx_url(&self, session: Option<&Session>, url: &str) -> String {
+        match (&self.tape, session) {
+            (Some(_), Some(session)) => session.rewrite(url),
+            _ => url.to_string(),
+        }
+    }
+
+    /// The URL the verification libraries request for `url`, rewritten to the tape or relay.
+    fn library_url(&self, url: &str) -> String {
+        match (&self.tape, &self.relay) {
+            (Some(tape), _) => tape.rewrite(url),
+            (None, Some(relay)) => relay.rewrite(url),
+            (None, None) => url.to_string(),
+        }
+    }
+
+    /// Persist an evidence bundle into `dir` for every Failure verdict.
+    pub fn with_evidence_dir(mut self, dir: Option<PathBuf>) -> Self {
+        self.evidence_store = dir.map(EvidenceStore::new);
+        self
+    }
+
+    /// Write the evidence of a failed verification to the evidence directory, if one is
+    /// configured, in the background.
+    pub fn save_evidence(
+        &self,
+        evidence: Evidence,
+        heartbeat_key: B256,
+        round: u8,
+        operator: Address,
+        raw_htx: Vec<u8>,
+        error: String,
+    ) {
+        let Some(store) = self.evidence_store.clone() else {
+            return;
+        };
+        tokio::task::spawn_blocking(move || {
+            let bundle = evidence.into_bundle(heartbeat_key, round, &raw_htx, &error);
+            match store.write(heartbeat_key, round, operator, &bundle) {
+                Ok(path) => {
+                    info!(htx_id = ?heartbeat_key, path = %path.display(), "Evidence bundle written")
+                }
+                Err(e) => {
+                    warn!(htx_id = ?heartbeat_key, error = %e, "Failed to write evidence bundle")
+                }
+            }
+        });
+    }
+
+    /// A session of the tape or relay allowing the HTX's `urls`, keeping the responses when
+    /// evidence is kept.
+    fn session(&self, urls: &[&str]) -> Option<Session> {
+        let keep = self.evidence_store.is_some();
+        match (&self.tape, &self.relay) {
+            (Some(tape), _) => Some(tape.session(urls, keep)),
+            (None, Some(relay)) => Some(relay.session(urls, keep)),
+            (None, None) => None,
+        }
+    }
+
+    /// Track the availability of the services verification depends on in `health`.
+    pub fn with_health(mut self, health: Arc<DependencyHealth>) -> Self {
+        self.health = health;
+        self
+    }
+
+    pub fn health(&self) -> &DependencyHealth {
+        &self.health
+    }
+
+    /// Keep up to `measurement_capacity` generated measurements, and reuse conclusive verdicts for
+    /// an identical raw HTX for `verdict_ttl`.
+    pub fn with_caches(mut self, measurement_capacity: usize, verdict_ttl: Duration) -> Self {
+        self.measurements = Arc::new(std::sync::Mutex::new(LruCache::new(measurement_capacity)));
+        self.verdicts = Arc::new(std::sync::Mutex::new(TtlCache::new(verdict_ttl)));
+        self
+    }
+
+    /// Run `verify` for a raw HTX, unless its verdict is cached or it is being verified already,
+    /// in which case the result of that verification is returned. The flag tells whether `verify`
+    /// ran.
+    ///
+    /// Every identity assigned an HTX verifies it through here, so it is verified once and each
+    /// identity goes on to submit as soon as the result is known.
+    pub async fn verify_shared<F>(
+        &self,
+        raw_htx: &[u8],
+        verify: F,
+    ) -> (Result<(), VerificationError>, bool)
+    where
+        F: Future<Output = Result<(), VerificationError>>,
+    {
+        let key: [u8; 32] = Sha256::digest(raw_htx).into();
+        if let Some(result) = self.verdicts.lock().unwrap().get(&key) {
+            return (result, false);
+        }
+        let shared = self
+            .in_flight
+            .lock()
+            .unwrap()
+            .entry(key)
+            .or_default()
+            .clone();
+        let mut ran = false;
+        let ran_here = &mut ran;
+        let result = shared
+            .get_or_init(move || async move {
+                *ran_here = true;
+                let result = verify.await;
+                self.remember_verdict(key, &result);
+                result
+            })
+            .await
+            .clone();
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if in_flight
+            .get(&key)
+            .is_some_and(|current| Arc::ptr_eq(current, &shared))
+        {
+            in_flight.remove(&key);
+        }
+        (result, ran)
+    }
+
+    /// Remember the verdict for a raw HTX. Inconclusive results are not cached since the
+    /// operational failure behind them may be gone on the next attempt.
+    fn remember_verdict(&self, key: [u8; 32], result: &Result<(), VerificationError>) {
+        if let Err(e) = result
+            && e.verdict() == Verdict::Inconclusive
+        {
+            return;
+        }
+        self.verdicts.lock().unwrap().insert(key, result.clone());
+    }
+
+    /// Download and verify the artifacts of the given nilCC versions ahead of time, so the first
+    /// HTX of each version doesn't pay for the download. Failures are logged and otherwise ignored.
+    pub async fn prefetch_artifacts(&self, versions: &[String]) {
+        for version in versions {
+            if self.artifacts.version_path(version).exists()
+                && matches!(
+                    self.check_artifacts(version).await,
+                    Ok(Integrity::Verified | Integrity::Unpinned)
+                )
+            {
+                info!(nilcc_version = %version, "nilcc artifacts already cached");
+                continue;
+            }
+
+            info!(nilcc_version = %version, "Prefetching nilcc artifacts");
+            let mut downloaded = true;
+            for vm_type in [VmType::Cpu, VmType::Gpu] {
+                if let Err(e) = self
+                    .downloader
+                    .download(
+                        version.clone(),
+                        vm_type,
+                        self.artifacts_url.clone(),
+                        &self.artifacts.version_path(version),
+                    )
+                    .await
+                {
+                    warn!(nilcc_version = %version, ?vm_type, error = %e, "Failed to prefetch nilcc artifacts");
+                    downloaded = false;
+                }
+            }
+            if !downloaded {
+                continue;
+            }
+
+            match self.check_artifacts(version).await {
+                Ok(Integrity::Corrupt { .. }) => {
+                    warn!(nilcc_version = %version, "Prefetched nilcc artifacts failed integrity check, removed from cache")
+                }
+                Ok(Integrity::Unpinned) if self.artifacts.requires_pins() => {
+                    warn!(nilcc_version = %version, "Prefetched nilcc version is not in the artifact manifest, HTXs using it will be refused")
+                }
+                Ok(integrity) => {
+                    info!(nilcc_version = %version, %integrity, "Prefetched nilcc artifacts")
+                }
+                Err(e) => {
+                    warn!(nilcc_version = %version, error = %e, "Failed to verify prefetched nilcc artifacts")
+                }
+            }
+        }
+    }
+
+    /// Check the cached artifacts of a version against the manifest, off the async runtime.
+    async fn check_artifacts(&self, version: &str) -> anyhow::Result<Integrity> {
+        let artifacts = self.artifacts.clone();
+        let version = version.to_string();
+        tokio::task::spawn_blocking(move || artifacts.ensure_verified(&version)).await?
+    }
+
+    /// Start a timer for verifying an HTX whose round closes at `round_deadline` (unix seconds).
+    ///
+    /// The budget is the time left until the deadline minus the submission margin. A zero
+    /// deadline means the round has no known deadline, in which case every stage gets its full
+    /// timeout.
+    pub fn start_timer(&self, round_deadline: u64) -> StageTimer {
+        if round_deadline == 0 {
+            return StageTimer::new(self.timeouts.total());
+        }
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let budget = Duration::from_secs(round_deadline)
+            .saturating_sub(now)
+            .saturating_sub(self.timeouts.submission_margin);
+        StageTimer::new(budget)
+    }
+
     /// Verify a nillion HTX by checking if the nilCC measurement exists in the builder index.
     ///
     /// Steps:
@@ -145,57 +616,54 @@ impl HtxVerifier {
     /// 3. Fetch the builder measurement index from the HTX's builder_measurement.url
     /// 4. Check if the measurement exists in the builder index (as object values or array elements)
     ///
+    /// Every step runs under its stage timeout and the overall budget of `timer`.
+    ///
     /// Returns Ok(()) if verification succeeds, Err(VerificationError) otherwise.
-    pub async fn verify_nillion_htx(&self, htx: &NillionHtx) -> Result<(), VerificationError> {
+    pub async fn verify_nillion_htx(
+        &self,
+        htx: &NillionHtx,
+        timer: &mut StageTimer,
+        evidence: &mut Evidence,
+    ) -> Result<(), VerificationError> {
         let NillionHtx::V1(htx) = htx;
-        let client = Client::builder()
-            .timeout(std::time::Duration::from_secs(10))
-            .connect_timeout(std::time::Duration::from_secs(5))
-            .build()
-            .expect("Failed to build HTTP client");
+        evidence.nillion().report_url = htx.workload_measurement.url.clone();
+        evidence.nillion().builder_index_url = htx.builder_measurement.url.clone();
+        let session = self.session(&[&htx.workload_measurement.url, &htx.builder_measurement.url]);
 
         let report = self
             .verify_nillion_report(
                 &htx.workload_measurement.url,
                 htx.workload_measurement.docker_compose_hash,
+                session.as_ref(),
+                timer,
+                evidence,
             )
             .await?;
 
         // Fetch builder measurement index
-        let builder_resp = client.get(&htx.builder_measurement.url).send().await;
-        let builder_json: serde_json::Value = match builder_resp.and_then(|r| r.error_for_status())
-        {
-            Ok(resp) => match resp.json().await {
-                Ok(v) => v,
-                Err(e) => return Err(VerificationError::BuilderJson(e.to_string())),
-            },
-            Err(e) => return Err(VerificationError::BuilderUrl(e.to_string())),
-        };
-
-        // Check if measurement exists in builder index
-        let mut matches_any = false;
-        let measurement_hex = hex::encode(report.measurement);
-        match builder_json {
-            serde_json::Value::Object(map) => {
-                for (_k, v) in map {
-                    if v.as_str() == Some(&measurement_hex) {
-                        matches_any = true;
-                        break;
-                    }
-                }
-            }
-            serde_json::Value::Array(arr) => {
-                for v in arr {
-                    if v.as_str() == Some(&measurement_hex) {
-                        matches_any = true;
-                        break;
-                    }
+        let builder_json: serde_json::Value = timer
+            .run(Stage::BuilderIndex, self.timeouts.builder_index, async {
+                let builder_resp = self
+                    .http
+                    .get(&self.htx_url(session.as_ref(), &htx.builder_measurement.url))
+                    .await
+                    .map_err(|e| VerificationError::BuilderUrl(e.to_string()))?;
+                if !(200..300).contains(&builder_resp.status) {
+                    return Err(VerificationError::BuilderUrl(format!(
+                        "HTTP status {} from {}",
+                        builder_resp.status, htx.builder_measurement.url
+                    )));
                 }
-            }
-            _ => {}
-        }
+                serde_json::from_slice(&builder_resp.body)
+                    .map_err(|e| VerificationError::BuilderJson(e.to_string()))
+            })
+            .await?;
 
-        if matches_any {
+        evidence.nillion().builder_index = Some(builder_json.clone());
+
+        // Check if measurement exists in builder index
+        if builder_index_contains(&builder_json, &hex::encode(report.measurement)) {
+            self.health.track_builder(&htx.builder_measurement.url);
             Ok(())
         } else {
             Err(VerificationError::NotInBuilderIndex)
@@ -206,12 +674,53 @@ impl HtxVerifier {
         &self,
         report_url: &str,
         docker_compose_hash: [u8; 32],
+        session: Option<&Session>,
+        timer: &mut StageTimer,
+        evidence: &mut Evidence,
     ) -> Result<AttestationReport, VerificationError> {
-        let bundle = self
-            .report_fetcher
-            .fetch_report(report_url)
-            .await
-            .map_err(|e| VerificationError::FetchReport(e.to_string()))?;
+        // Artifacts are downloaded from within the report fetch, so the downloader reports its
+        // progress back to us to time that stage separately and enforce its own timeout.
+        let (progress_tx, progress_rx) = watch::channel(DownloadProgress::Idle);
+        // The session keeps the report as served for the evidence
+        let fetch_url = match session {
+            Some(session) => session.rewrite(report_url),
+            None => report_url.to_string(),
+        };
+        let fetch =
+            DOWNLOAD_PROGRESS.scope(progress_tx, self.report_fetcher.fetch_report(&fetch_url));
+        let watchdog =
+            artifact_download_watchdog(progress_rx.clone(), self.timeouts.artifact_download);
+        let bundle = timer
+            .run(
+                Stage::ReportFetch,
+                self.timeouts.report_fetch + self.timeouts.artifact_download,
+                async {
+                    tokio::select! {
+                        result = fetch => {
+                            result.map_err(|e| VerificationError::FetchReport(e.to_string()))
+                        }
+                        _ = watchdog => Err(VerificationError::StageTimeout(Stage::ArtifactDownload)),
+                    }
+                },
+            )
+            .await;
+        match *progress_rx.borrow() {
+            DownloadProgress::Finished(elapsed) => {
+                timer.split(Stage::ReportFetch, Stage::ArtifactDownload, elapsed)
+            }
+            DownloadProgress::Started(started) => timer.split(
+                Stage::ReportFetch,
+                Stage::ArtifactDownload,
+                started.elapsed(),
+            ),
+            DownloadProgress::Idle => {}
+        }
+        if let Some(response) = session.and_then(|session| session.response(report_url)) {
+            let body = String::from_utf8_lossy(&response.body).into_owned();
+            evidence.nillion().report =
+                Some(serde_json::from_str(&body).unwrap_or(serde_json::Value::String(body)));
+        }
+        let bundle = bundle?;
         let ReportBundle {
             cpu_count,
             nilcc_version,
@@ -220,37 +729,123 @@ impl HtxVerifier {
             ..
         } = bundle;
 
-        let artifacts_path = self.artifact_cache.join(&nilcc_version);
-        let measurement = MeasurementGenerator::new(
-            docker_compose_hash,
-            cpu_count,
-            vm_type.into(),
-            &metadata,
-            &artifacts_path,
-        )
-        .generate()
-        .map_err(|e| VerificationError::MeasurementHash(e.to_string()))?;
-        // Hold the lock so we prevent writing to the filesystem twice when fetching/caching certs
-        let _guard = self.verify_lock.lock().await;
-        self.report_verifier
-            .verify_report(&bundle.report, &measurement)
-            .await
-            .map_err(|e: attestation_verification::VerificationError| {
-                match e {
-                    // Inconclusive errors - infrastructure/operational failures (outside of host control)
-                    ExtVerificationError::FetchCerts(ref inner) => {
-                        VerificationError::FetchCerts(inner.to_string())
-                    }
-                    ExtVerificationError::DetectProcessor(ref inner) => {
-                        VerificationError::DetectProcessor(inner.to_string())
-                    }
-                    ExtVerificationError::InvalidCertificate(ref inner) => {
-                        VerificationError::InvalidCertificate(inner.to_string())
+        // Artifacts are checked against the pinned manifest once after every download
+        let integrity = timer
+            .run(
+                Stage::ArtifactDownload,
+                self.timeouts.artifact_download,
+                async {
+                    self.check_artifacts(&nilcc_version)
+                        .await
+                        .map_err(|e| VerificationError::ArtifactIntegrity(format!("{e:#}")))
+                },
+            )
+            .await?;
+        match integrity {
+            Integrity::Corrupt { .. } => {
+                return Err(VerificationError::ArtifactIntegrity(format!(
+                    "nilcc {nilcc_version} artifacts are {integrity}, removed from cache"
+                )));
+            }
+            Integrity::Unpinned if self.artifacts.requires_pins() => {
+                return Err(VerificationError::ArtifactIntegrity(format!(
+                    "nilcc {nilcc_version} is not in the artifact manifest"
+                )));
+            }
+            Integrity::Verified | Integrity::Unpinned => {}
+        }
+
+        let vm_type: VmType = vm_type.into();
+        let nillion = evidence.nillion();
+        nillion.nilcc_version = Some(nilcc_version.clone());
+        nillion.cpu_count = Some(cpu_count);
+        nillion.vm_type = Some(format!("{vm_type:?}"));
+        nillion.report_measurement = Some(bundle.report.measurement.to_vec());
+        // Metadata that can't be serialized can't be keyed on, its measurement isn't cached
+        let key = serde_json::to_vec(&metadata)
+            .ok()
+            .map(|metadata| MeasurementKey {
+                docker_compose_hash,
+                cpu_count,
+                vm_type: format!("{vm_type:?}"),
+                nilcc_version: nilcc_version.clone(),
+                metadata_hash: Sha256::digest(metadata).into(),
+            });
+        let cached = key
+            .as_ref()
+            .and_then(|key| self.measurements.lock().unwrap().get(key));
+        let measurement = match cached {
+            Some(measurement) => measurement,
+            None => {
+                // Measurement generation is CPU bound, run it off the runtime so it can be timed out
+                let artifacts_path = self.artifacts.version_path(&nilcc_version);
+                let measurement = timer
+                    .run(
+                        Stage::MeasurementGeneration,
+                        self.timeouts.measurement_generation,
+                        async move {
+                            tokio::task::spawn_blocking(move || {
+                                MeasurementGenerator::new(
+                                    docker_compose_hash,
+                                    cpu_count,
+                                    vm_type,
+                                    &metadata,
+                                    &artifacts_path,
+                                )
+                                .generate()
+                                .map(|measurement| measurement.to_vec())
+                                .map_err(|e| VerificationError::MeasurementHash(e.to_string()))
+                            })
+                            .await
+                            .unwrap_or_else(|e| std::panic::resume_unwind(e.into_panic()))
+                        },
+                    )
+                    .await?;
+                if let Some(key) = key {
+                    self.measurements
+                        .lock()
+                        .unwrap()
+                        .insert(key, measurement.clone());
+                }
+                measurement
+            }
+        };
+
+        evidence.nillion().expected_measurement = Some(measurement.clone());
+
+        timer
+            .run(Stage::CertFetch, self.timeouts.cert_fetch, async {
+                // Hold the lock so we prevent writing to the filesystem twice when fetching/caching certs
+                let _guard = self.verify_lock.lock().await;
+                let verified = self
+                    .report_verifier
+                    .verify_report(&bundle.report, &measurement)
+                    .await;
+                // The certificates the report was just checked against
+                if self.evidence_store.is_some() {
+                    match chain_certificates(&self.cert_cache, &bundle.report.chip_id) {
+                        Ok(certificates) => evidence.nillion().certificates = Some(certificates),
+                        Err(e) => warn!(error = %e, "Failed to read certificates for evidence"),
                     }
-                    // Any other verification failures treated as malicious
-                    _ => VerificationError::VerifyReport(e.to_string()),
                 }
-            })?;
+                verified.map_err(|e: attestation_verification::VerificationError| {
+                    match e {
+                        // Inconclusive errors - infrastructure/operational failures (outside of host control)
+                        ExtVerificationError::FetchCerts(ref inner) => {
+                            VerificationError::FetchCerts(inner.to_string())
+                        }
+                        ExtVerificationError::DetectProcessor(ref inner) => {
+                            VerificationError::DetectProcessor(inner.to_string())
+                        }
+                        ExtVerificationError::InvalidCertificate(ref inner) => {
+                            VerificationError::InvalidCertificate(inner.to_string())
+                        }
+                        // Any other verification failures treated as malicious
+                        _ => VerificationError::VerifyReport(e.to_string()),
+                    }
+                })
+            })
+            .await?;
         Ok(bundle.report)
     }
 
@@ -260,35 +855,26 @@ impl HtxVerifier {
     /// 1. Calculate SHA-256 hash of app_compose
     /// 2. Extract attested hash from event_log (compose-hash event)
     /// 3. Verify hashes match
-    /// 4. Verify quote locally using dcap-qvl (get_collateral_and_verify)
+    /// 4. Verify quote locally using dcap-qvl, with collateral from Phala's PCCS
+    ///
+    /// The collateral fetch runs under its stage timeout and the overall budget of `timer`.
     ///
     /// Returns Ok(()) if verification succeeds, Err(VerificationError) otherwise.
-    pub async fn verify_phala_htx(&self, htx: &PhalaHtx) -> Result<(), VerificationError> {
+    pub async fn verify_phala_htx(
+        &self,
+        htx: &PhalaHtx,
+        timer: &mut StageTimer,
+        evidence: &mut Evidence,
+    ) -> Result<(), VerificationError> {
         let PhalaHtx::V1(htx) = htx;
-        // 1. Calculate SHA-256 hash of app_compose
-        let mut hasher = Sha256::new();
-        hasher.update(htx.app_compose.as_bytes());
-        let calculated_hash = hex::encode(hasher.finalize());
-
-        // 2. Extract attested hash from event_log
-        let events: Vec<serde_json::Value> = serde_json::from_str(&htx.attest_data.event_log)
-            .map_err(|e| VerificationError::PhalaEventLogParse(e.to_string()))?;
-
-        let compose_event = events
-            .iter()
-            .find(|e| e.get("event").and_then(|v| v.as_str()) == Some("compose-hash"))
-            .ok_or_else(|| {
-                VerificationError::PhalaEventLogParse("compose-hash event not found".to_string())
-            })?;
-
-        let attested_hash = compose_event
-            .get("event_payload")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| {
-                VerificationError::PhalaEventLogParse("event_payload not found".to_string())
-            })?;
-
-        // 3. Verify hashes match
+        evidence.phala().event_log = htx.attest_data.event_log.clone();
+        evidence.phala().quote = hex::decode(&htx.attest_data.quote).ok();
+
+        // 1-3. Check the SHA-256 hash of app_compose against the one attested in event_log
+        let (calculated_hash, attested_hash) =
+            compose_hashes(&htx.app_compose, &htx.attest_data.event_log)?;
+        evidence.phala().app_compose_hash = Some(calculated_hash.clone());
+        evidence.phala().attested_compose_hash = Some(attested_hash.clone());
         if calculated_hash != attested_hash {
             return Err(VerificationError::PhalaComposeHashMismatch);
         }
@@ -297,18 +883,103 @@ impl HtxVerifier {
         let quote_bytes = hex::decode(&htx.attest_data.quote)
             .map_err(|e| VerificationError::PhalaQuoteVerify(format!("invalid quote hex: {e}")))?;
 
-        get_collateral_and_verify(&quote_bytes, None)
-            .await
-            .map_err(|e| {
-                VerificationError::PhalaQuoteVerify(format!("quote verification failed: {e}"))
-            })?;
+        timer
+            .run(
+                Stage::QuoteCollateral,
+                self.timeouts.quote_collateral,
+                async {
+                    // Replays must verify against the collateral on the tape at the time it was
+                    // recorded
+                    let now = match &self.tape {
+                        Some(tape) => tape.tape().recorded_at(),
+                        None => SystemTime::now()
+                            .duration_since(UNIX_EPOCH)
+                            .unwrap_or_default()
+                            .as_secs(),
+                    };
+                    let pccs_url = self.library_url(PHALA_PCCS_URL);
+                    let verified = match get_collateral(&pccs_url, &quote_bytes).await {
+                        Ok(collateral) => {
+                            evidence.phala().collateral = serde_json::to_value(&collateral).ok();
+                            dcap_qvl::verify::verify(&quote_bytes, &collateral, now)
+                        }
+                        Err(e) => Err(e),
+                    };
+                    verified.map_err(|e| {
+                        VerificationError::PhalaQuoteVerify(format!(
+                            "quote verification failed: {e}"
+                        ))
+                    })
+                },
+            )
+            .await?;
 
         Ok(())
     }
 }
 
-#[derive(Default)]
-struct LockedDownloader(Mutex<()>);
+/// Whether a builder measurement index contains `measurement_hex`, either as one of the values
+/// of an object or as an element of an array.
+pub fn builder_index_contains(index: &serde_json::Value, measurement_hex: &str) -> bool {
+    match index {
+        serde_json::Value::Object(map) => map.values().any(|v| v.as_str() == Some(measurement_hex)),
+        serde_json::Value::Array(arr) => arr.iter().any(|v| v.as_str() == Some(measurement_hex)),
+        _ => false,
+    }
+}
+
+/// The SHA-256 hash of `app_compose` and the compose hash attested in `event_log`, hex encoded.
+fn compose_hashes(
+    app_compose: &str,
+    event_log: &str,
+) -> Result<(String, String), VerificationError> {
+    let mut hasher = Sha256::new();
+    hasher.update(app_compose.as_bytes());
+    let calculated_hash = hex::encode(hasher.finalize());
+
+    let events: Vec<serde_json::Value> = serde_json::from_str(event_log)
+        .map_err(|e| VerificationError::PhalaEventLogParse(e.to_string()))?;
+
+    let compose_event = events
+        .iter()
+        .find(|e| e.get("event").and_then(|v| v.as_str()) == Some("compose-hash"))
+        .ok_or_else(|| {
+            VerificationError::PhalaEventLogParse("compose-hash event not found".to_string())
+        })?;
+
+    let attested_hash = compose_event
+        .get("event_payload")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| {
+            VerificationError::PhalaEventLogParse("event_payload not found".to_string())
+        })?;
+    Ok((calculated_hash, attested_hash.to_string()))
+}
+
+/// Check that the compose hash attested in `event_log` is the hash of `app_compose`.
+pub fn compose_hash_matches(app_compose: &str, event_log: &str) -> Result<(), VerificationError> {
+    let (calculated_hash, attested_hash) = compose_hashes(app_compose, event_log)?;
+    if calculated_hash == attested_hash {
+        Ok(())
+    } else {
+        Err(VerificationError::PhalaComposeHashMismatch)
+    }
+}
+
+#[derive(Clone)]
+struct LockedDownloader {
+    lock: Arc<Mutex<()>>,
+    artifacts: Arc<ArtifactCache>,
+}
+
+impl LockedDownloader {
+    fn new(artifacts: Arc<ArtifactCache>) -> Self {
+        Self {
+            lock: Default::default(),
+            artifacts,
+        }
+    }
+}
 
 #[async_trait]
 impl ReportArtifactsDownloader for LockedDownloader {
@@ -319,11 +990,45 @@ impl ReportArtifactsDownloader for LockedDownloader {
         artifacts_url: String,
         download_path: &Path,
     ) -> Result<Artifacts, DownloadError> {
-        let _guard = self.0.lock().await;
+        let _guard = self.lock.lock().await;
+        // Whatever was verified before is about to be overwritten
+        self.artifacts.invalidate(&nilcc_version);
+        let mut partial = PartialDownload {
+            artifacts: &self.artifacts,
+            version: Some(&nilcc_version),
+        };
+        let started = Instant::now();
+        let _ = DOWNLOAD_PROGRESS.try_with(|progress| {
+            progress.send_replace(DownloadProgress::Started(started));
+        });
         let downloader = DefaultReportArtifactsDownloader;
-        downloader
-            .download(nilcc_version, vm_type, artifacts_url, download_path)
-            .await
+        let result = downloader
+            .download(nilcc_version.clone(), vm_type, artifacts_url, download_path)
+            .await;
+        let _ = DOWNLOAD_PROGRESS.try_with(|progress| {
+            progress.send_replace(DownloadProgress::Finished(started.elapsed()));
+        });
+        if result.is_ok() {
+            partial.version = None;
+        }
+        result
+    }
+}
+
+/// Removes the version being downloaded from the cache unless the download completes, so a
+/// download that fails or is dropped by a timeout doesn't leave partial artifacts behind.
+struct PartialDownload<'a> {
+    artifacts: &'a ArtifactCache,
+    version: Option<&'a str>,
+}
+
+impl Drop for PartialDownload<'_> {
+    fn drop(&mut self) {
+        if let Some(version) = self.version
+            && let Err(e) = self.artifacts.remove(version)
+        {
+            warn!(version, error = %e, "Failed to remove partial download");
+        }
     }
 }
 
@@ -356,6 +1061,9 @@ mod tests {
             VerificationError::PhalaEventLogParse("missing field".to_string()),
             VerificationError::FetchCerts("AMD server unreachable".to_string()),
             VerificationError::DetectProcessor("unknown CPU".to_string()),
+            VerificationError::ArtifactIntegrity("kernel mismatched".to_string()),
+            VerificationError::StageTimeout(Stage::ArtifactDownload),
+            VerificationError::DeadlineExceeded(Stage::CertFetch),
         ];
 
         for err in inconclusive_errors {
@@ -392,4 +1100,131 @@ mod tests {
             assert!(!err.is_inconclusive());
         }
     }
+
+    #[tokio::test]
+    async fn test_verify_shared() {
+        let dir = std::env::temp_dir().join(format!(
+            "blacklight-verify-shared-test-{}",
+            std::process::id()
+        ));
+        let timeouts = VerificationTimeouts {
+            report_fetch: Duration::from_secs(1),
+            artifact_download: Duration::from_secs(1),
+            measurement_generation: Duration::from_secs(1),
+            cert_fetch: Duration::from_secs(1),
+            builder_index: Duration::from_secs(1),
+            quote_collateral: Duration::from_secs(1),
+            submission_margin: Duration::from_secs(1),
+        };
+        let verifier = HtxVerifier::new(
+            Arc::new(ArtifactCache::new(
+                dir.join("artifacts"),
+                Default::default(),
+            )),
+            String::new(),
+            dir.join("certs"),
+            timeouts,
+            HttpClient::new(reqwest::Client::new(), 1),
+        )
+        .unwrap();
+
+        // Concurrent identities wait for the one verification in progress, even an
+        // inconclusive one
+        let runs = std::sync::atomic::AtomicU32::new(0);
+        let verify = || async {
+            runs.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            Err(VerificationError::FetchReport("timeout".to_string()))
+        };
+        let (first, second) = tokio::join!(
+            verifier.verify_shared(b"htx", verify()),
+            verifier.verify_shared(b"htx", verify()),
+        );
+        assert_eq!(runs.load(std::sync::atomic::Ordering::SeqCst), 1);
+        assert!(first.0.is_err() && second.0.is_err());
+        assert_ne!(first.1, second.1);
+
+        // Inconclusive results aren't cached, a later assignment verifies again
+        let (_, ran) = verifier.verify_shared(b"htx", verify()).await;
+        assert!(ran);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_failed_download_is_removed() {
+        let root = std::env::temp_dir().join(format!(
+            "blacklight-partial-download-test-{}",
+            std::process::id()
+        ));
+        let artifacts = Arc::new(ArtifactCache::new(root.clone(), Default::default()));
+        let path = artifacts.version_path("0.0.1");
+        std::fs::create_dir_all(&path).unwrap();
+        std::fs::write(path.join("vmlinuz"), b"partial").unwrap();
+
+        let downloader = LockedDownloader::new(artifacts);
+        let result = downloader
+            .download(
+                "0.0.1".to_string(),
+                VmType::Cpu,
+                "http://127.0.0.1:1".to_string(),
+                &path,
+            )
+            .await;
+        assert!(result.is_err());
+        assert!(!path.exists());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn test_stage_timeout() {
+        let mut timer = StageTimer::new(Duration::from_secs(60));
+        let result: Result<(), _> = timer
+            .run(Stage::BuilderIndex, Duration::from_millis(10), async {
+                tokio::time::sleep(Duration::from_secs(10)).await;
+                Ok(())
+            })
+            .await;
+        assert!(matches!(
+            result,
+            Err(VerificationError::StageTimeout(Stage::BuilderIndex))
+        ));
+        assert_eq!(timer.timings().len(), 1);
+        assert_eq!(timer.timings()[0].0, Stage::BuilderIndex);
+    }
+
+    #[tokio::test]
+    async fn test_overall_budget_exceeded() {
+        let mut timer = StageTimer::new(Duration::from_millis(10));
+        let result: Result<(), _> = timer
+            .run(Stage::CertFetch, Duration::from_secs(60), async {
+                tokio::time::sleep(Duration::from_secs(10)).await;
+                Ok(())
+            })
+            .await;
+        assert!(matches!(
+            result,
+            Err(VerificationError::DeadlineExceeded(Stage::CertFetch))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_stage_timings() {
+        let mut timer = StageTimer::new(Duration::from_secs(60));
+        timer
+            .run(Stage::ReportFetch, Duration::from_secs(1), async { Ok(()) })
+            .await
+            .unwrap();
+        timer.split(
+            Stage::ReportFetch,
+            Stage::ArtifactDownload,
+            Duration::from_secs(5),
+        );
+        let stages: Vec<_> = timer.timings().iter().map(|(stage, _)| *stage).collect();
+        assert_eq!(stages, vec![Stage::ReportFetch, Stage::ArtifactDownload]);
+        assert_eq!(timer.timings()[0].1, Duration::ZERO);
+        assert_eq!(
+            timer.to_string(),
+            "report_fetch=0ms artifact_download=5000ms"
+        );
+    }
 }
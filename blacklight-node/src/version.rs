@@ -1,7 +1,10 @@
 use anyhow::{Result, anyhow};
 use semver::Version;
 
+use crate::ShutdownRequested;
 use blacklight_contract_clients::BlacklightClient;
+use std::sync::Arc;
+use tokio::sync::Notify;
 use tracing::{debug, error, info, warn};
 
 /// BLACKLIGHT_VERSION is injected at build time by CI, falls back to Cargo.toml version for local builds
@@ -68,6 +71,48 @@ pub fn check_version_compatibility(
 /// Validate node version against the protocol's required version
 pub async fn validate_node_version(client: &BlacklightClient) -> Result<()> {
     let required_version = client.protocol_config.node_version().await?;
+    check_required_version(&required_version)
+}
+
+/// Follow `NodeVersionUpdated` events and initiate shutdown as soon as the protocol requires a
+/// version this node is incompatible with.
+///
+/// The current requirement is checked first, so an update missed while disconnected is still
+/// noticed.
+pub async fn watch_node_version(
+    client: Arc<BlacklightClient>,
+    shutdown_notify: Arc<Notify>,
+) -> Result<()> {
+    let required_version = client.protocol_config.node_version().await?;
+    if let Err(e) = check_required_version(&required_version) {
+        error!(error = %e, "Node version validation failed. Initiating shutdown...");
+        shutdown_notify.notify_waiters();
+        return Err(e.context(ShutdownRequested));
+    }
+
+    let protocol_config = Arc::new(client.protocol_config.clone());
+    protocol_config
+        .listen_node_version_updated_events(move |event| {
+            let shutdown_notify = shutdown_notify.clone();
+            async move {
+                info!(
+                    old_version = %event.oldVersion,
+                    new_version = %event.newVersion,
+                    "Protocol node version updated"
+                );
+                if let Err(e) = check_required_version(&event.newVersion) {
+                    error!(error = %e, "Node version validation failed. Initiating shutdown...");
+                    shutdown_notify.notify_waiters();
+                }
+                Ok(())
+            }
+        })
+        .await
+}
+
+/// Check this node's version against a required version, logging the outcome. Fails only if the
+/// versions are incompatible.
+pub fn check_required_version(required_version: &str) -> Result<()> {
     let required_version = required_version.trim();
 
     // Empty required version means no version enforcement
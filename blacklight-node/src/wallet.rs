@@ -1,6 +1,10 @@
 use alloy::primitives::{Address, U256};
-use alloy::signers::local::PrivateKeySigner;
-use anyhow::Result;
+use alloy::signers::local::coins_bip39::{English, Mnemonic};
+use alloy::signers::local::{MnemonicBuilder, PrivateKeySigner};
+use anyhow::{Context, Result, bail};
+use std::fs::{self, File};
+use std::io::ErrorKind;
+use std::path::Path;
 use term_table::row::Row;
 use term_table::table_cell::{Alignment as CellAlignment, TableCell};
 use term_table::{Table, TableStyle};
@@ -31,6 +35,70 @@ pub fn generate_wallet() -> Result<PrivateKeySigner> {
     Ok(wallet)
 }
 
+/// BIP-32 path of Ethereum accounts, the account index is appended to it
+pub const DEFAULT_DERIVATION_PATH: &str = "m/44'/60'/0'/0";
+
+/// Generate a new random 12 word BIP-39 mnemonic
+pub fn generate_mnemonic() -> Result<String> {
+    let mnemonic = Mnemonic::<English>::new_with_count(&mut rand::thread_rng(), 12)?;
+    Ok(mnemonic.to_phrase())
+}
+
+/// Derive the wallet of account `index` of a BIP-39 mnemonic along the BIP-32 `path`
+pub fn derive_wallet(mnemonic: &str, path: &str, index: u32) -> Result<PrivateKeySigner> {
+    let phrase = mnemonic.split_whitespace().collect::<Vec<_>>().join(" ");
+    let wallet = MnemonicBuilder::<English>::default()
+        .phrase(phrase)
+        .derivation_path(format!("{}/{index}", path.trim_end_matches('/')))?
+        .build()
+        .context("Failed to derive wallet from mnemonic")?;
+    Ok(wallet)
+}
+
+/// Allocate the mnemonic index of replica `id` among all replicas sharing the `root` directory
+///
+/// The first replica gets `base_index`, the next one `base_index + 1` and so on. A replica keeps
+/// its index across restarts, since it is recorded under `root/allocations/<id>`.
+pub fn allocate_index(root: &Path, id: &str, base_index: u32) -> Result<u32> {
+    if id.is_empty() || id.contains(['/', '\\']) || id.starts_with('.') {
+        bail!("Invalid replica id '{id}'");
+    }
+    let allocations = root.join("allocations");
+    fs::create_dir_all(&allocations)
+        .with_context(|| format!("Failed to create {}", allocations.display()))?;
+
+    // Replicas starting at the same time must not hand out the same index; the lock is released
+    // when the file is dropped
+    let lock = File::create(root.join("lock"))?;
+    lock.lock()?;
+
+    let id_file = allocations.join(id);
+    match fs::read_to_string(&id_file) {
+        Ok(index) => {
+            return index
+                .trim()
+                .parse()
+                .with_context(|| format!("Invalid index in {}", id_file.display()));
+        }
+        Err(e) if e.kind() == ErrorKind::NotFound => {}
+        Err(e) => return Err(e.into()),
+    }
+
+    let next_file = root.join("next");
+    let next: u32 = match fs::read_to_string(&next_file) {
+        Ok(next) => next
+            .trim()
+            .parse()
+            .with_context(|| format!("Invalid index in {}", next_file.display()))?,
+        Err(e) if e.kind() == ErrorKind::NotFound => 0,
+        Err(e) => return Err(e.into()),
+    };
+    let index = base_index + next;
+    fs::write(&next_file, format!("{}\n", next + 1))?;
+    fs::write(&id_file, format!("{index}\n"))?;
+    Ok(index)
+}
+
 /// Display wallet status banner with ETH balance and NIL stake information
 /// This consolidated function handles all wallet states with a single implementation
 pub fn display_wallet_status(
@@ -149,7 +217,6 @@ pub fn display_wallet_status(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use anyhow::Context;
 
     fn parse_wallet(private_key: &str) -> Result<PrivateKeySigner> {
         let wallet = private_key
@@ -186,4 +253,31 @@ mod tests {
             .unwrap();
         assert_eq!(wallet.address(), expected_address);
     }
+
+    #[test]
+    fn test_derive_wallet() {
+        // The development mnemonic of anvil and hardhat
+        let mnemonic = "test test test test test test test test test test test junk";
+        let wallet = derive_wallet(mnemonic, DEFAULT_DERIVATION_PATH, 1).unwrap();
+        let expected_address = "0x70997970C51812dc3A010C7d01b50e0d17dc79C8"
+            .parse::<Address>()
+            .unwrap();
+        assert_eq!(wallet.address(), expected_address);
+
+        let generated = generate_mnemonic().unwrap();
+        assert_eq!(generated.split(' ').count(), 12);
+        assert!(derive_wallet(&generated, DEFAULT_DERIVATION_PATH, 0).is_ok());
+        assert!(derive_wallet("not a mnemonic", DEFAULT_DERIVATION_PATH, 0).is_err());
+    }
+
+    #[test]
+    fn test_allocate_index() {
+        let root = std::env::temp_dir().join(format!("mnemonic-alloc-{}", std::process::id()));
+        assert_eq!(allocate_index(&root, "node-a", 3).unwrap(), 3);
+        assert_eq!(allocate_index(&root, "node-b", 3).unwrap(), 4);
+        // Restarted replicas keep their index
+        assert_eq!(allocate_index(&root, "node-a", 3).unwrap(), 3);
+        assert!(allocate_index(&root, "../node-c", 3).is_err());
+        fs::remove_dir_all(root).unwrap();
+    }
 }
@@ -1,22 +1,25 @@
 use crate::{
-    ContractConfig, HeartbeatManagerClient, NilTokenClient, ProtocolConfigClient,
-    StakingOperatorsClient,
+    ContractConfig, HeartbeatManagerClient, JailingPolicyClient, NilTokenClient, NonceManager,
+    ProtocolConfigClient, SignerConfig, StakingOperatorsClient,
 };
 use alloy::{
     network::{Ethereum, EthereumWallet, NetworkWallet},
     primitives::{Address, B256, TxKind, U256},
     providers::{DynProvider, Provider, ProviderBuilder, WsConnect},
     rpc::types::TransactionRequest,
-    signers::local::PrivateKeySigner,
 };
+use anyhow::bail;
 use std::sync::Arc;
-use tokio::sync::Mutex;
 
 /// High-level wrapper bundling all contract clients with a shared Alloy provider.
 #[derive(Clone)]
 pub struct BlacklightClient {
+    /// Connection without a wallet, shared by every client built with [`Self::with_signer`]
+    root: DynProvider,
+    config: ContractConfig,
     provider: DynProvider,
     wallet: EthereumWallet,
+    nonces: Arc<NonceManager>,
     pub manager: HeartbeatManagerClient<DynProvider>,
     pub token: NilTokenClient<DynProvider>,
     pub staking: StakingOperatorsClient<DynProvider>,
@@ -24,7 +27,11 @@ pub struct BlacklightClient {
 }
 
 impl BlacklightClient {
-    pub async fn new(config: ContractConfig, private_key: String) -> anyhow::Result<Self> {
+    /// Connect with transactions signed by `signer`, a [`SignerConfig`] or a private key.
+    pub async fn new(
+        config: ContractConfig,
+        signer: impl Into<SignerConfig>,
+    ) -> anyhow::Result<Self> {
         let rpc_url = config.rpc_url.clone();
         let ws_url = rpc_url
             .replace("http://", "ws://")
@@ -32,33 +39,78 @@ impl BlacklightClient {
 
         // Build WS transport with configurable retries
         let ws = WsConnect::new(ws_url).with_max_retries(config.max_ws_retries);
-        let signer: PrivateKeySigner = private_key.parse::<PrivateKeySigner>()?;
-        let wallet = EthereumWallet::from(signer);
+        let root: DynProvider = ProviderBuilder::new()
+            .disable_recommended_fillers()
+            .connect_ws(ws)
+            .await?
+            .erased();
+
+        let signer = signer.into();
+        let nonces = Arc::new(NonceManager::new(signer.address()?));
+        let staking = StakingOperatorsClient::new(root.clone(), config.clone(), nonces.clone());
+        let protocol_config_address = staking.protocol_config().await?;
+        Self::connect(root, config, protocol_config_address, signer, nonces)
+    }
+
+    /// Build a client signing with another identity over this client's websocket connection,
+    /// allocating its nonces with `nonces`.
+    ///
+    /// Identities with their own nonce managers never wait on each other's transactions. A
+    /// client of the same wallet as another must be given that client's [`Self::nonces`].
+    pub fn with_signer(
+        &self,
+        signer: impl Into<SignerConfig>,
+        nonces: Arc<NonceManager>,
+    ) -> anyhow::Result<Self> {
+        Self::connect(
+            self.root.clone(),
+            self.config.clone(),
+            self.protocol_config.address(),
+            signer.into(),
+            nonces,
+        )
+    }
+
+    fn connect(
+        root: DynProvider,
+        config: ContractConfig,
+        protocol_config_address: Address,
+        signer: SignerConfig,
+        nonces: Arc<NonceManager>,
+    ) -> anyhow::Result<Self> {
+        let wallet = signer.wallet()?;
+        let address = <EthereumWallet as NetworkWallet<Ethereum>>::default_signer_address(&wallet);
+        if nonces.address() != address {
+            bail!(
+                "nonce manager of {} can't sign for {address}",
+                nonces.address()
+            );
+        }
 
         // Build a provider that can sign transactions, then erase the concrete type
         let provider: DynProvider = ProviderBuilder::new()
             .wallet(wallet.clone())
-            .with_simple_nonce_management()
             .with_gas_estimation()
-            .connect_ws(ws)
-            .await?
+            .connect_provider(root.clone())
             .erased();
 
-        let tx_lock = Arc::new(Mutex::new(()));
-
         // Instantiate contract clients using the shared provider
-        let manager =
-            HeartbeatManagerClient::new(provider.clone(), config.clone(), tx_lock.clone());
-        let token = NilTokenClient::new(provider.clone(), config.clone(), tx_lock.clone());
-        let staking = StakingOperatorsClient::new(provider.clone(), config, tx_lock.clone());
-
-        let protocol_config_address = staking.protocol_config().await?;
-        let protocol_config =
-            ProtocolConfigClient::new(provider.clone(), protocol_config_address, tx_lock.clone());
+        let manager = HeartbeatManagerClient::new(provider.clone(), config.clone(), nonces.clone());
+        let token = NilTokenClient::new(provider.clone(), config.clone(), nonces.clone());
+        let staking = StakingOperatorsClient::new(provider.clone(), config.clone(), nonces.clone());
+        let protocol_config = ProtocolConfigClient::new(
+            provider.clone(),
+            protocol_config_address,
+            &config,
+            nonces.clone(),
+        );
 
         Ok(Self {
+            root,
+            config,
             provider,
             wallet,
+            nonces,
             manager,
             token,
             staking,
@@ -71,6 +123,17 @@ impl BlacklightClient {
         <EthereumWallet as NetworkWallet<Ethereum>>::default_signer_address(&self.wallet)
     }
 
+    /// The nonce manager of the signer's wallet
+    pub fn nonces(&self) -> Arc<NonceManager> {
+        self.nonces.clone()
+    }
+
+    /// A client for the jailing policy the protocol config points to
+    pub async fn jailing_policy(&self) -> anyhow::Result<JailingPolicyClient<DynProvider>> {
+        let address = self.protocol_config.jailing_policy_address().await?;
+        Ok(JailingPolicyClient::new(self.provider.clone(), address))
+    }
+
     /// Get the balance of the wallet
     pub async fn get_balance(&self) -> anyhow::Result<U256> {
         let address = self.signer_address();
@@ -82,17 +145,29 @@ impl BlacklightClient {
         Ok(self.provider.get_balance(address).await?)
     }
 
+    /// Get what a mined transaction cost in wei
+    pub async fn get_transaction_cost(&self, tx_hash: B256) -> anyhow::Result<U256> {
+        let receipt = self
+            .provider
+            .get_transaction_receipt(tx_hash)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("no receipt for transaction {tx_hash:?}"))?;
+        Ok(U256::from(receipt.effective_gas_price) * U256::from(receipt.gas_used))
+    }
+
     /// Send ETH to an address
     pub async fn send_eth(&self, to: Address, amount: U256) -> anyhow::Result<B256> {
+        let nonce = self.nonces.next(&self.provider).await?;
         let tx = TransactionRequest {
             to: Some(TxKind::Call(to)),
             value: Some(amount),
             max_priority_fee_per_gas: Some(0),
+            nonce: Some(nonce.value()),
             ..Default::default()
         };
 
         let tx_hash = self.provider.send_transaction(tx).await?.watch().await?;
-
+        nonce.mined();
         Ok(tx_hash)
     }
 }
@@ -0,0 +1,263 @@
+//! # Fee Strategies
+//!
+//! How the fees of contract transactions are chosen, since L2 deployments price priority fees
+//! differently:
+//!
+//! - [`FeeStrategy::Fixed`]: a fixed priority fee, the default being the 1 wei our L2 requires
+//! - [`FeeStrategy::Percentile`]: a percentile of the priority fees paid in recent blocks
+//! - [`FeeStrategy::Multiplier`]: the node's fee estimate scaled up, with a capped priority fee
+//!
+//! Whatever the strategy, transactions are never sent with a max fee above the configured
+//! ceiling. Gas limits are estimated with a buffer, configurable per contract method.
+
+use alloy::{
+    contract::{CallBuilder, CallDecoder},
+    eips::BlockNumberOrTag,
+    providers::Provider,
+    transports::TransportResult,
+};
+use anyhow::{Result, bail};
+use std::collections::HashMap;
+
+/// Number of recent blocks the percentile strategy looks at
+const FEE_HISTORY_BLOCKS: u64 = 10;
+
+/// Default gas limit buffer over the estimate, in percent
+pub const DEFAULT_GAS_BUFFER_PERCENT: u64 = 50;
+
+/// The fees a transaction is sent with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fees {
+    pub max_fee_per_gas: u128,
+    pub max_priority_fee_per_gas: u128,
+}
+
+impl Fees {
+    /// The fees of a replacement transaction, which nodes only accept if both fees are raised
+    /// by at least 10%. They are raised by 12.5%, and to at least `estimate`.
+    pub(crate) fn bumped(self, estimate: Fees) -> Fees {
+        let bump = |fee: u128| fee.saturating_add(fee.div_ceil(8).max(1));
+        let max_priority_fee_per_gas =
+            bump(self.max_priority_fee_per_gas).max(estimate.max_priority_fee_per_gas);
+        let max_fee_per_gas = bump(self.max_fee_per_gas)
+            .max(estimate.max_fee_per_gas)
+            .max(max_priority_fee_per_gas);
+        Fees {
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+        }
+    }
+}
+
+/// How the fees of a transaction are chosen.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FeeStrategy {
+    /// A fixed priority fee, with the estimated max fee
+    Fixed { priority_fee: u128 },
+    /// The given percentile of the priority fees paid in recent blocks, with a max fee of twice
+    /// the next base fee on top
+    Percentile { percentile: f64 },
+    /// The estimated fees multiplied by `multiplier`, with the priority fee capped
+    Multiplier {
+        multiplier: f64,
+        max_priority_fee: u128,
+    },
+}
+
+impl Default for FeeStrategy {
+    fn default() -> Self {
+        // Our L2 requires a minimum priority fee of 1 wei
+        FeeStrategy::Fixed { priority_fee: 1 }
+    }
+}
+
+/// Fee strategy, max fee ceiling and gas buffers of contract transactions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeeConfig {
+    pub strategy: FeeStrategy,
+    /// Transactions whose max fee per gas would exceed this are not sent
+    pub max_fee_per_gas: Option<u128>,
+    /// Gas limit buffer over the estimate, in percent
+    pub gas_buffer_percent: u64,
+    /// Gas limit buffers of specific contract methods, in percent
+    pub method_gas_buffers: HashMap<String, u64>,
+}
+
+impl Default for FeeConfig {
+    fn default() -> Self {
+        Self {
+            strategy: FeeStrategy::default(),
+            max_fee_per_gas: None,
+            gas_buffer_percent: DEFAULT_GAS_BUFFER_PERCENT,
+            method_gas_buffers: HashMap::new(),
+        }
+    }
+}
+
+impl FeeConfig {
+    /// The fees to send a transaction with now, failing if they are above the ceiling.
+    pub async fn fees<P: Provider>(&self, provider: &P) -> Result<Fees> {
+        let fees = self.estimate(provider).await?;
+        self.check(fees)?;
+        Ok(fees)
+    }
+
+    /// The fees the strategy picks now, regardless of the ceiling.
+    pub(crate) async fn estimate<P: Provider>(&self, provider: &P) -> TransportResult<Fees> {
+        let fees = match &self.strategy {
+            FeeStrategy::Fixed { priority_fee } => {
+                let estimate = provider.estimate_eip1559_fees().await?;
+                Fees {
+                    max_fee_per_gas: estimate.max_fee_per_gas.max(*priority_fee),
+                    max_priority_fee_per_gas: *priority_fee,
+                }
+            }
+            FeeStrategy::Percentile { percentile } => {
+                let history = provider
+                    .get_fee_history(FEE_HISTORY_BLOCKS, BlockNumberOrTag::Latest, &[*percentile])
+                    .await?;
+                let base_fee = history
+                    .next_block_base_fee()
+                    .or_else(|| history.latest_block_base_fee())
+                    .unwrap_or_default();
+                let rewards: Vec<u128> = history
+                    .reward
+                    .unwrap_or_default()
+                    .iter()
+                    .filter_map(|rewards| rewards.first().copied())
+                    .collect();
+                percentile_fees(base_fee, &rewards)
+            }
+            FeeStrategy::Multiplier {
+                multiplier,
+                max_priority_fee,
+            } => {
+                let estimate = provider.estimate_eip1559_fees().await?;
+                let max_priority_fee_per_gas =
+                    scale(estimate.max_priority_fee_per_gas, *multiplier).min(*max_priority_fee);
+                Fees {
+                    max_fee_per_gas: scale(estimate.max_fee_per_gas, *multiplier)
+                        .max(max_priority_fee_per_gas),
+                    max_priority_fee_per_gas,
+                }
+            }
+        };
+        Ok(fees)
+    }
+
+    /// Fail if `fees` are above the max fee ceiling.
+    pub fn check(&self, fees: Fees) -> Result<()> {
+        if let Some(ceiling) = self.max_fee_per_gas
+            && fees.max_fee_per_gas > ceiling
+        {
+            bail!(
+                "max fee per gas of {} wei is above the ceiling of {ceiling} wei, not sending",
+                fees.max_fee_per_gas
+            );
+        }
+        Ok(())
+    }
+
+    /// The gas limit buffer of a contract method, in percent.
+    pub fn gas_buffer(&self, method: &str) -> u64 {
+        self.method_gas_buffers
+            .get(method)
+            .copied()
+            .unwrap_or(self.gas_buffer_percent)
+    }
+
+    /// Add the gas limit buffer of a contract method to a gas estimate.
+    pub fn buffered_gas(&self, method: &str, estimate: u64) -> u64 {
+        let buffer = u128::from(estimate) * u128::from(self.gas_buffer(method)) / 100;
+        estimate.saturating_add(buffer.try_into().unwrap_or(u64::MAX))
+    }
+
+    /// Set the fees of a call, for transactions not sent through the contract clients.
+    pub async fn apply<P: Provider, D: CallDecoder>(
+        &self,
+        call: CallBuilder<P, D>,
+    ) -> Result<CallBuilder<P, D>> {
+        let fees = self.fees(&call.provider).await?;
+        Ok(call
+            .max_fee_per_gas(fees.max_fee_per_gas)
+            .max_priority_fee_per_gas(fees.max_priority_fee_per_gas))
+    }
+}
+
+/// Fees paying the median of the recent priority fees at the configured percentile.
+fn percentile_fees(base_fee: u128, rewards: &[u128]) -> Fees {
+    let mut rewards = rewards.to_vec();
+    rewards.sort_unstable();
+    let max_priority_fee_per_gas = rewards.get(rewards.len() / 2).copied().unwrap_or(1).max(1);
+    Fees {
+        max_fee_per_gas: base_fee
+            .saturating_mul(2)
+            .saturating_add(max_priority_fee_per_gas),
+        max_priority_fee_per_gas,
+    }
+}
+
+fn scale(fee: u128, multiplier: f64) -> u128 {
+    (fee as f64 * multiplier).ceil() as u128
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bumped_fees() {
+        let fees = Fees {
+            max_fee_per_gas: 1_000,
+            max_priority_fee_per_gas: 1,
+        };
+        let bumped = fees.bumped(fees);
+        assert_eq!(
+            bumped,
+            Fees {
+                max_fee_per_gas: 1_125,
+                max_priority_fee_per_gas: 2,
+            }
+        );
+
+        // A base fee that rose past the bump is followed
+        let estimate = Fees {
+            max_fee_per_gas: 5_000,
+            max_priority_fee_per_gas: 1,
+        };
+        assert_eq!(fees.bumped(estimate).max_fee_per_gas, 5_000);
+    }
+
+    #[test]
+    fn test_percentile_fees() {
+        let fees = percentile_fees(100, &[7, 3, 0, 5, 9]);
+        assert_eq!(
+            fees,
+            Fees {
+                max_fee_per_gas: 205,
+                max_priority_fee_per_gas: 5,
+            }
+        );
+        // Empty blocks still pay the minimum priority fee
+        assert_eq!(percentile_fees(100, &[]).max_priority_fee_per_gas, 1);
+        assert_eq!(scale(1_000, 1.25), 1_250);
+    }
+
+    #[test]
+    fn test_ceiling_and_gas_buffers() {
+        let config = FeeConfig {
+            max_fee_per_gas: Some(1_000),
+            method_gas_buffers: HashMap::from([("submitVerdict".to_string(), 20)]),
+            ..Default::default()
+        };
+        let fees = |max_fee_per_gas| Fees {
+            max_fee_per_gas,
+            max_priority_fee_per_gas: 1,
+        };
+        assert!(config.check(fees(1_000)).is_ok());
+        assert!(config.check(fees(1_001)).is_err());
+
+        assert_eq!(config.buffered_gas("submitVerdict", 100_000), 120_000);
+        assert_eq!(config.buffered_gas("stakeTo", 100_000), 150_000);
+    }
+}
@@ -1,4 +1,5 @@
 use crate::common::errors::decode_any_error;
+use crate::common::fees::FeeConfig;
 use alloy::{
     contract::{CallBuilder, CallDecoder},
     providers::Provider,
@@ -7,16 +8,20 @@ use anyhow::anyhow;
 
 pub mod errors;
 pub mod event_helper;
+pub mod fees;
+pub mod nonce_manager;
+pub mod tx_error;
 pub mod tx_submitter;
 
+/// Estimate the gas of a call of `method`, with the method's buffer of `fees` added
 pub async fn overestimate_gas<P: Provider, D: CallDecoder>(
     call: &CallBuilder<&P, D>,
+    fees: &FeeConfig,
+    method: &str,
 ) -> anyhow::Result<u64> {
-    // Estimate gas and add a 50% buffer
     let estimated_gas = call.estimate_gas().await.map_err(|e| {
         let decoded = decode_any_error(&e);
         anyhow!("failed to estimate gas: {decoded}")
     })?;
-    let gas_with_buffer = estimated_gas.saturating_add(estimated_gas / 2);
-    Ok(gas_with_buffer)
+    Ok(fees.buffered_gas(method, estimated_gas))
 }
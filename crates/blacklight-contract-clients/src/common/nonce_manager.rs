@@ -0,0 +1,163 @@
+//! # Nonce Management
+//!
+//! Transactions of a wallet are numbered by nonces. Handing them out locally lets several
+//! transactions be in flight at once: the lock is only held while allocating a nonce, not while
+//! waiting for the transaction to be mined.
+//!
+//! Every allocation resyncs with the pending nonce of the wallet, so transactions sent by other
+//! processes are skipped over. A nonce whose transaction failed before being mined is handed out
+//! again, before any new one, unless the wallet's pending nonce has moved past it. Nonces of
+//! other transactions still in flight are never handed out twice.
+//!
+//! The manager of a wallet is owned by the client signing with it, and handed to the contract
+//! clients it builds. Clients of the same wallet must share one manager.
+
+use alloy::{primitives::Address, providers::Provider};
+use anyhow::Result;
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+
+/// Hands out the nonces of one wallet's transactions.
+#[derive(Debug)]
+pub struct NonceManager {
+    address: Address,
+    state: Mutex<NonceState>,
+}
+
+impl NonceManager {
+    /// A nonce manager for the wallet `address`, starting from its pending nonce.
+    pub fn new(address: Address) -> Self {
+        Self {
+            address,
+            state: Mutex::default(),
+        }
+    }
+
+    /// The wallet the nonces belong to.
+    pub fn address(&self) -> Address {
+        self.address
+    }
+
+    /// Allocate the nonce of the next transaction. It is released for reuse when dropped, unless
+    /// the transaction was mined.
+    pub async fn next<P: Provider>(&self, provider: &P) -> Result<Nonce<'_>> {
+        let pending = provider
+            .get_transaction_count(self.address)
+            .pending()
+            .await?;
+        let value = self.state.lock().unwrap().allocate(pending);
+        Ok(Nonce {
+            value,
+            manager: self,
+            used: false,
+        })
+    }
+}
+
+/// A nonce allocated to a transaction.
+#[derive(Debug)]
+pub struct Nonce<'a> {
+    value: u64,
+    manager: &'a NonceManager,
+    used: bool,
+}
+
+impl Nonce<'_> {
+    pub fn value(&self) -> u64 {
+        self.value
+    }
+
+    /// Mark the nonce as used by a mined transaction, so it is never handed out again.
+    pub fn mined(mut self) {
+        self.used = true;
+    }
+}
+
+impl Drop for Nonce<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.manager.state.lock() {
+            state.finish(self.value, self.used);
+        }
+    }
+}
+
+/// The allocated nonces of a wallet.
+#[derive(Debug, Default)]
+struct NonceState {
+    /// The nonce after the highest one handed out
+    next: Option<u64>,
+    /// Nonces of transactions not mined yet
+    in_flight: BTreeSet<u64>,
+    /// Nonces below `next` released by failed transactions, handed out first
+    released: BTreeSet<u64>,
+}
+
+impl NonceState {
+    /// Allocate a nonce given the wallet's `pending` nonce, never handing out one in flight.
+    fn allocate(&mut self, pending: u64) -> u64 {
+        // Released nonces the wallet has since used, e.g. by a transaction that was mined after
+        // all, are gone
+        self.released = self.released.split_off(&pending);
+        let nonce = match self.released.pop_first() {
+            Some(nonce) => nonce,
+            None => {
+                let nonce = self.next.map_or(pending, |next| next.max(pending));
+                self.next = Some(nonce + 1);
+                nonce
+            }
+        };
+        self.in_flight.insert(nonce);
+        nonce
+    }
+
+    /// Finish the transaction of `nonce`, releasing the nonce unless it was `used`.
+    fn finish(&mut self, nonce: u64, used: bool) {
+        self.in_flight.remove(&nonce);
+        if used {
+            return;
+        }
+        if self.in_flight.is_empty() {
+            // Nothing else is in flight, so the next allocation resyncs with the pending nonce
+            *self = Self::default();
+        } else if self.next == Some(nonce + 1) {
+            self.next = Some(nonce);
+        } else {
+            self.released.insert(nonce);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allocate() {
+        let mut state = NonceState::default();
+        assert_eq!(state.allocate(5), 5);
+        // Transactions in flight are not pending yet
+        assert_eq!(state.allocate(5), 6);
+        assert_eq!(state.allocate(6), 7);
+        // Another process sent transactions
+        assert_eq!(state.allocate(10), 10);
+
+        // A failed nonce is handed out again, while the others stay in flight
+        state.finish(6, false);
+        assert_eq!(state.allocate(6), 6);
+        assert_eq!(state.allocate(6), 11);
+        // The last nonce handed out is rolled back
+        state.finish(11, false);
+        assert_eq!(state.allocate(6), 11);
+        // A released nonce the wallet has used since is skipped
+        state.finish(7, false);
+        assert_eq!(state.allocate(8), 12);
+
+        // Once nothing is in flight, the gap left by a failed transaction is filled from the
+        // pending nonce
+        for nonce in [5, 6, 10, 11] {
+            state.finish(nonce, true);
+        }
+        state.finish(12, false);
+        assert_eq!(state.allocate(8), 8);
+    }
+}
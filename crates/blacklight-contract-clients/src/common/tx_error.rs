@@ -0,0 +1,316 @@
+//! # Transaction Errors
+//!
+//! Failed contract transactions are classified, so callers can tell apart the failures worth
+//! retrying from the ones that fail again on retry:
+//!
+//! - Retryable: transport failures and nonces taken by other transactions
+//! - Not retryable: reverts caught by simulation, transactions the RPC node rejects or already
+//!   knows, running out of gas, transactions not mined in time, fees above the ceiling and calls
+//!   that can't be built
+//!
+//! A transaction that was not mined in time or that the RPC node already knows may still be
+//! mined, so sending the call again could execute it twice. On-chain reverts are not retryable as
+//! such either: the state the transaction raced for has usually moved on. The submitter sends
+//! them again only when simulating the call no longer reverts.
+//!
+//! Even retryable errors are only retried for calls that are idempotent by construction, such as
+//! `submitVerdict`, whose duplicate reverts in simulation.
+//!
+//! Client methods sending transactions return `Result<B256, TxError>`.
+
+use alloy::{contract::Error as ContractError, primitives::B256, transports::TransportError};
+use std::fmt;
+use std::time::Duration;
+
+/// RPC error messages of transactions whose nonce was taken by another transaction. An
+/// "already known" transaction is not one of them, it is pending with its nonce.
+const NONCE_CONFLICT_MESSAGES: &[&str] = &[
+    "nonce too low",
+    "nonce too high",
+    "replacement transaction underpriced",
+];
+
+/// Why a contract transaction failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TxError {
+    /// The RPC node could not be reached or gave no usable answer
+    Transport { method: String, message: String },
+    /// The call reverted when simulated, so it was not sent
+    SimulationReverted { method: String, reason: String },
+    /// The RPC node refused the transaction, e.g. for lack of funds
+    Rejected { method: String, message: String },
+    /// The transaction was mined but reverted
+    Reverted { method: String, tx_hash: B256 },
+    /// The transaction was mined but ran out of gas
+    OutOfGas {
+        method: String,
+        tx_hash: B256,
+        gas_used: u64,
+        gas_limit: u64,
+    },
+    /// The transaction was not mined in time, even after replacing it with higher fees
+    Timeout {
+        method: String,
+        tx_hash: B256,
+        replacements: u32,
+    },
+    /// The transaction's nonce was taken by another transaction
+    NonceConflict { method: String, message: String },
+    /// The fees the transaction needs are above the configured ceiling
+    FeeCeiling { method: String, message: String },
+    /// The call could not be built from its arguments
+    InvalidCall { method: String, message: String },
+}
+
+impl TxError {
+    /// Whether sending the transaction again may succeed.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            TxError::Transport { .. } | TxError::NonceConflict { .. } => true,
+            TxError::SimulationReverted { .. }
+            | TxError::Rejected { .. }
+            | TxError::Reverted { .. }
+            | TxError::OutOfGas { .. }
+            | TxError::Timeout { .. }
+            | TxError::FeeCeiling { .. }
+            | TxError::InvalidCall { .. } => false,
+        }
+    }
+
+    /// The contract method of the failed transaction.
+    pub fn method(&self) -> &str {
+        match self {
+            TxError::Transport { method, .. }
+            | TxError::SimulationReverted { method, .. }
+            | TxError::Rejected { method, .. }
+            | TxError::Reverted { method, .. }
+            | TxError::OutOfGas { method, .. }
+            | TxError::Timeout { method, .. }
+            | TxError::NonceConflict { method, .. }
+            | TxError::FeeCeiling { method, .. }
+            | TxError::InvalidCall { method, .. } => method,
+        }
+    }
+
+    /// Classify an error of simulating a call or estimating its gas. The RPC node answering
+    /// with an error means the call reverted.
+    pub(crate) fn simulation(method: &str, error: &ContractError, reason: String) -> Self {
+        if let ContractError::TransportError(error) = error
+            && error.as_error_resp().is_none()
+        {
+            return Self::transport(method, reason);
+        }
+        TxError::SimulationReverted {
+            method: method.to_string(),
+            reason,
+        }
+    }
+
+    /// Classify an error of sending a transaction.
+    pub(crate) fn send(method: &str, error: &ContractError, reason: String) -> Self {
+        let ContractError::TransportError(error) = error else {
+            return TxError::Rejected {
+                method: method.to_string(),
+                message: reason,
+            };
+        };
+        let Some(payload) = error.as_error_resp() else {
+            return Self::transport(method, reason);
+        };
+        let message = payload.message.to_lowercase();
+        if NONCE_CONFLICT_MESSAGES.iter().any(|m| message.contains(m)) {
+            TxError::NonceConflict {
+                method: method.to_string(),
+                message: reason,
+            }
+        } else if payload.as_revert_data().is_some() {
+            TxError::SimulationReverted {
+                method: method.to_string(),
+                reason,
+            }
+        } else {
+            TxError::Rejected {
+                method: method.to_string(),
+                message: reason,
+            }
+        }
+    }
+
+    /// An RPC request that failed.
+    pub(crate) fn rpc(method: &str, error: TransportError) -> Self {
+        Self::transport(method, error.to_string())
+    }
+
+    /// Arguments the call could not be built from.
+    pub(crate) fn invalid_call(method: &str, error: impl fmt::Display) -> Self {
+        TxError::InvalidCall {
+            method: method.to_string(),
+            message: error.to_string(),
+        }
+    }
+
+    fn transport(method: &str, message: String) -> Self {
+        TxError::Transport {
+            method: method.to_string(),
+            message,
+        }
+    }
+}
+
+impl fmt::Display for TxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TxError::Transport { method, message } => {
+                write!(f, "{method} failed to reach the RPC node: {message}")
+            }
+            TxError::SimulationReverted { method, reason } => {
+                write!(f, "{method} reverted: {reason}")
+            }
+            TxError::Rejected { method, message } => {
+                write!(f, "{method} failed to send: {message}")
+            }
+            TxError::Reverted { method, tx_hash } => {
+                write!(f, "{method} reverted on-chain. Tx hash: {tx_hash:?}")
+            }
+            TxError::OutOfGas {
+                method,
+                tx_hash,
+                gas_used,
+                gas_limit,
+            } => write!(
+                f,
+                "{method} ran out of gas (used {gas_used} of {gas_limit} limit). Tx: {tx_hash:?}"
+            ),
+            TxError::Timeout {
+                method,
+                tx_hash,
+                replacements,
+            } => write!(
+                f,
+                "{method} was not mined after {replacements} replacements, last tx: {tx_hash:?}"
+            ),
+            TxError::NonceConflict { method, message } => {
+                write!(
+                    f,
+                    "{method} lost its nonce to another transaction: {message}"
+                )
+            }
+            TxError::FeeCeiling { method, message } => {
+                write!(f, "{method} was not sent: {message}")
+            }
+            TxError::InvalidCall { method, message } => {
+                write!(f, "{method} call could not be built: {message}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TxError {}
+
+/// How failed transactions are retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled before each following one
+    pub backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            backoff: Duration::from_secs(1),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// The delay before `retry` (counting from 1) of a failed transaction, or `None` when the
+    /// retries are used up.
+    pub fn delay(&self, retry: u32) -> Option<Duration> {
+        if retry > self.max_retries {
+            return None;
+        }
+        Some(self.backoff.saturating_mul(1 << (retry - 1).min(16)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::transports::RpcError;
+
+    fn rpc_error(message: &str, data: Option<&str>) -> ContractError {
+        let payload = serde_json::json!({
+            "code": 3,
+            "message": message,
+            "data": data,
+        });
+        ContractError::TransportError(RpcError::ErrorResp(
+            serde_json::from_value(payload).unwrap(),
+        ))
+    }
+
+    #[test]
+    fn test_classify_send_errors() {
+        let send = |error: ContractError| TxError::send("submitVerdict", &error, String::new());
+
+        let error = send(rpc_error("nonce too low: next nonce 5, tx nonce 4", None));
+        assert!(matches!(error, TxError::NonceConflict { .. }));
+        assert!(error.is_retryable());
+
+        let error = send(rpc_error("execution reverted", Some("0x08c379a0")));
+        assert!(matches!(error, TxError::SimulationReverted { .. }));
+        assert!(!error.is_retryable());
+
+        let error = send(rpc_error(
+            "insufficient funds for gas * price + value",
+            None,
+        ));
+        assert!(matches!(error, TxError::Rejected { .. }));
+        assert!(!error.is_retryable());
+
+        // The transaction is pending, sending it again could execute the call twice
+        let error = send(rpc_error("already known", None));
+        assert!(matches!(error, TxError::Rejected { .. }));
+        assert!(!error.is_retryable());
+
+        let error = send(ContractError::TransportError(RpcError::NullResp));
+        assert!(matches!(error, TxError::Transport { .. }));
+        assert!(error.is_retryable());
+        assert_eq!(error.method(), "submitVerdict");
+    }
+
+    #[test]
+    fn test_retry_policy() {
+        let policy = RetryPolicy {
+            max_retries: 2,
+            backoff: Duration::from_millis(100),
+        };
+        assert_eq!(policy.delay(1), Some(Duration::from_millis(100)));
+        assert_eq!(policy.delay(2), Some(Duration::from_millis(200)));
+        assert_eq!(policy.delay(3), None);
+
+        // Reverts and running out of gas fail the same way when sent again, and transactions not
+        // mined in time may still be mined
+        let reverted = TxError::Reverted {
+            method: "submitHeartbeat".to_string(),
+            tx_hash: B256::ZERO,
+        };
+        assert!(!reverted.is_retryable());
+        let out_of_gas = TxError::OutOfGas {
+            method: "submitHeartbeat".to_string(),
+            tx_hash: B256::ZERO,
+            gas_used: 100,
+            gas_limit: 100,
+        };
+        assert!(!out_of_gas.is_retryable());
+        let timeout = TxError::Timeout {
+            method: "submitHeartbeat".to_string(),
+            tx_hash: B256::ZERO,
+            replacements: 3,
+        };
+        assert!(!timeout.is_retryable());
+    }
+}
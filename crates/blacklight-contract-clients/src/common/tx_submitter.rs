@@ -1,66 +1,193 @@
+use super::fees::{FeeConfig, Fees};
+use super::nonce_manager::NonceManager;
+use super::tx_error::{RetryPolicy, TxError};
+use crate::ContractConfig;
 use alloy::{
     consensus::Transaction, contract::CallBuilder, primitives::B256, providers::Provider,
     rpc::types::TransactionReceipt, sol_types::SolInterface,
 };
-use anyhow::{Result, anyhow};
 use std::fmt::Debug;
 use std::marker::PhantomData;
 use std::sync::Arc;
-use tokio::sync::Mutex;
-use tracing::{info, warn};
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::{debug, info, warn};
+
+/// How often to check whether a sent transaction was mined
+const RECEIPT_POLL_INTERVAL: Duration = Duration::from_secs(1);
 
 #[derive(Clone)]
 pub(crate) struct TransactionSubmitter<S> {
-    tx_lock: Arc<Mutex<()>>,
-    gas_limit: Option<u64>,
+    nonces: Arc<NonceManager>,
+    fees: FeeConfig,
+    receipt_timeout: Duration,
+    max_replacements: u32,
+    retry_policy: Option<RetryPolicy>,
     _decoder: PhantomData<S>,
 }
 
 impl<S: SolInterface + Debug + Clone> TransactionSubmitter<S> {
-    pub(crate) fn new(tx_lock: Arc<Mutex<()>>) -> Self {
+    pub(crate) fn new(nonces: Arc<NonceManager>, config: &ContractConfig) -> Self {
         Self {
-            tx_lock,
-            gas_limit: None,
+            nonces,
+            fees: config.fees.clone(),
+            receipt_timeout: config.receipt_timeout,
+            max_replacements: config.max_replacements,
+            retry_policy: config.retry_policy,
             _decoder: PhantomData,
         }
     }
 
-    pub(crate) async fn invoke<P, D>(&self, method: &str, call: CallBuilder<P, D>) -> Result<B256>
+    /// Send a call and wait for it to be mined, without retrying it.
+    pub(crate) async fn invoke<P, D>(
+        &self,
+        method: &str,
+        call: CallBuilder<P, D>,
+    ) -> Result<B256, TxError>
+    where
+        P: Provider + Clone,
+        D: alloy::contract::CallDecoder + Clone,
+    {
+        self.invoke_once(method, call).await
+    }
+
+    /// Send a call and wait for it to be mined, retrying it as the retry policy allows. Calls that
+    /// reverted on-chain are retried only if they no longer revert when simulated.
+    ///
+    /// Only for calls that are idempotent by construction, whose duplicate reverts in simulation
+    /// should an earlier attempt have gone through after all.
+    pub(crate) async fn invoke_idempotent<P, D>(
+        &self,
+        method: &str,
+        call: CallBuilder<P, D>,
+    ) -> Result<B256, TxError>
+    where
+        P: Provider + Clone,
+        D: alloy::contract::CallDecoder + Clone,
+    {
+        let mut retry = 0;
+        loop {
+            let error = match self.invoke_once(method, call.clone()).await {
+                Ok(tx_hash) => return Ok(tx_hash),
+                Err(e) => e,
+            };
+            retry += 1;
+            let Some(delay) = self.retry_policy.and_then(|policy| policy.delay(retry)) else {
+                return Err(error);
+            };
+            if !error.is_retryable() {
+                let TxError::Reverted { .. } = error else {
+                    return Err(error);
+                };
+                if let Err(e) = call.call().await {
+                    let reason = self.decode_error(&e);
+                    debug!(method = %method, reason = %reason, "Reverted transaction still reverts when simulated");
+                    return Err(error);
+                }
+            }
+            warn!(method = %method, retry, delay = ?delay, error = %error, "🔁 transaction failed, retrying");
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    async fn invoke_once<P, D>(
+        &self,
+        method: &str,
+        call: CallBuilder<P, D>,
+    ) -> Result<B256, TxError>
     where
         P: Provider + Clone,
         D: alloy::contract::CallDecoder + Clone,
     {
         // Pre-simulate to catch reverts with proper error messages
         if let Err(e) = call.call().await {
-            let e = self.decode_error(e);
-            return Err(anyhow!("{method} reverted: {e}"));
+            let reason = self.decode_error(&e);
+            return Err(TxError::simulation(method, &e, reason));
         }
 
-        let call = match self.gas_limit {
-            Some(gas) => call.gas(gas),
-            None => call,
-        };
-
         let provider = call.provider.clone();
-        let estimate = provider.estimate_eip1559_fees().await?;
-
-        // Our L2 requires a minimum priority fee of 1 wei
-        let priority_fee = 1u128;
-        let call = call
-            .max_priority_fee_per_gas(priority_fee)
-            .max_fee_per_gas(estimate.max_fee_per_gas);
-
-        let estimated_gas = call.clone().estimate_gas().await?;
+        let rpc = |e| TxError::rpc(method, e);
+        let mut fees = self.fees.estimate(&provider).await.map_err(rpc)?;
+        self.check_fees(method, fees)?;
+        let estimated_priority_fee = fees.max_priority_fee_per_gas;
+        let estimated_gas = match call
+            .clone()
+            .max_priority_fee_per_gas(fees.max_priority_fee_per_gas)
+            .max_fee_per_gas(fees.max_fee_per_gas)
+            .estimate_gas()
+            .await
+        {
+            Ok(gas) => gas,
+            Err(e) => {
+                let reason = self.decode_error(&e);
+                return Err(TxError::simulation(method, &e, reason));
+            }
+        };
+        let gas_limit = self.fees.buffered_gas(method, estimated_gas);
+        let call = call.gas(gas_limit);
 
-        // Acquire lock and send
-        let _guard = self.tx_lock.lock().await;
-        let pending = call.send().await.map_err(|e| {
-            let e = self.decode_error(e);
-            anyhow!("{method} failed to send: {e}")
-        })?;
+        // Only the nonce allocation is serialized, so several transactions can be in flight. The
+        // nonce is released for reuse on every early return, unless the transaction was mined.
+        let allocated = self
+            .nonces
+            .next(&provider)
+            .await
+            .map_err(|e| TxError::Transport {
+                method: method.to_string(),
+                message: e.to_string(),
+            })?;
+        let nonce = allocated.value();
+        let send = |fees: Fees| {
+            call.clone()
+                .nonce(nonce)
+                .max_priority_fee_per_gas(fees.max_priority_fee_per_gas)
+                .max_fee_per_gas(fees.max_fee_per_gas)
+        };
+        let pending = match send(fees).send().await {
+            Ok(pending) => pending,
+            Err(e) => {
+                let reason = self.decode_error(&e);
+                return Err(TxError::send(method, &e, reason));
+            }
+        };
 
-        // Wait for receipt
-        let receipt = pending.get_receipt().await?;
+        // Wait for the receipt, replacing the transaction with higher fees when it is stuck
+        let mut sent = vec![*pending.tx_hash()];
+        let mut replacements = 0;
+        let receipt = loop {
+            if let Some(receipt) = self.wait_for_receipt(&provider, &sent).await {
+                break receipt;
+            }
+            let stuck = *sent.last().expect("a transaction was sent");
+            if replacements == self.max_replacements {
+                return Err(TxError::Timeout {
+                    method: method.to_string(),
+                    tx_hash: stuck,
+                    replacements,
+                });
+            }
+            replacements += 1;
+            let current = self.fees.estimate(&provider).await.unwrap_or(fees);
+            fees = fees.bumped(current);
+            self.check_fees(method, fees)?;
+            warn!(
+                method = %method,
+                tx_hash = ?stuck,
+                nonce,
+                max_fee_per_gas = fees.max_fee_per_gas,
+                max_priority_fee_per_gas = fees.max_priority_fee_per_gas,
+                "⏳ transaction not mined in time, replacing it with higher fees"
+            );
+            match send(fees).send().await {
+                Ok(pending) => sent.push(*pending.tx_hash()),
+                // A previous transaction may have been mined in the meantime
+                Err(e) => {
+                    let e = self.decode_error(&e);
+                    warn!(method = %method, nonce, error = %e, "Failed to send replacement transaction");
+                }
+            }
+        };
+        allocated.mined();
         let tx_hash = receipt.transaction_hash;
 
         Self::log_fee_details(
@@ -69,37 +196,65 @@ impl<S: SolInterface + Debug + Clone> TransactionSubmitter<S> {
             tx_hash,
             &receipt,
             estimated_gas,
-            estimate.max_priority_fee_per_gas,
+            estimated_priority_fee,
         )
         .await;
 
         // Validate success
         if !receipt.status() {
-            if let Some(limit) = self.gas_limit {
-                let used = receipt.gas_used;
-                if used >= limit {
-                    return Err(anyhow!(
-                        "{method} ran out of gas (used {used} of {limit} limit). Tx: {tx_hash:?}"
-                    ));
-                }
+            let gas_used = receipt.gas_used;
+            if gas_used >= gas_limit {
+                return Err(TxError::OutOfGas {
+                    method: method.to_string(),
+                    tx_hash,
+                    gas_used,
+                    gas_limit,
+                });
             }
 
-            return Err(anyhow!("{method} reverted on-chain. Tx hash: {tx_hash:?}"));
+            return Err(TxError::Reverted {
+                method: method.to_string(),
+                tx_hash,
+            });
         }
 
         Ok(tx_hash)
     }
 
-    pub(crate) fn with_gas_limit(&self, limit: u64) -> Self {
-        let mut this = self.clone();
-        this.gas_limit = Some(limit);
-        this
+    /// Fail if `fees` are above the max fee ceiling
+    fn check_fees(&self, method: &str, fees: Fees) -> Result<(), TxError> {
+        self.fees.check(fees).map_err(|e| TxError::FeeCeiling {
+            method: method.to_string(),
+            message: e.to_string(),
+        })
+    }
+
+    /// Wait for any of the `sent` transactions, which share a nonce, to be mined
+    async fn wait_for_receipt<P: Provider>(
+        &self,
+        provider: &P,
+        sent: &[B256],
+    ) -> Option<TransactionReceipt> {
+        let deadline = Instant::now() + self.receipt_timeout;
+        loop {
+            for tx_hash in sent.iter().rev() {
+                match provider.get_transaction_receipt(*tx_hash).await {
+                    Ok(Some(receipt)) => return Some(receipt),
+                    Ok(None) => {}
+                    Err(e) => debug!(tx_hash = ?tx_hash, error = %e, "Failed to get receipt"),
+                }
+            }
+            if Instant::now() >= deadline {
+                return None;
+            }
+            tokio::time::sleep(RECEIPT_POLL_INTERVAL).await;
+        }
     }
 
-    fn decode_error(&self, error: alloy::contract::Error) -> String {
-        match error.try_decode_into_interface_error::<S>() {
-            Ok(error) => format!("{error:?}"),
-            Err(error) => super::errors::decode_any_error(&error).to_string(),
+    fn decode_error(&self, error: &alloy::contract::Error) -> String {
+        match error.as_decoded_interface_error::<S>() {
+            Some(error) => format!("{error:?}"),
+            None => super::errors::decode_any_error(error).to_string(),
         }
     }
 
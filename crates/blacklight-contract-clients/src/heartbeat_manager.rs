@@ -1,7 +1,7 @@
 use crate::common::event_helper::{BlockRange, listen_events, listen_events_filtered};
 use crate::htx::Htx;
 use crate::{
-    common::{overestimate_gas, tx_submitter::TransactionSubmitter},
+    NonceManager, TxError, common::tx_submitter::TransactionSubmitter,
     heartbeat_manager::HearbeatManager::HearbeatManagerInstance,
 };
 use alloy::{
@@ -12,7 +12,6 @@ use alloy::{
 };
 use anyhow::{Context, Result, anyhow, bail};
 use std::sync::Arc;
-use tokio::sync::Mutex;
 
 const DEFAULT_LOOKBACK_BLOCKS: u64 = 50;
 
@@ -110,10 +109,10 @@ pub struct HeartbeatManagerClient<P: Provider + Clone> {
 
 impl<P: Provider + Clone> HeartbeatManagerClient<P> {
     /// Create a new WebSocket client from ContractConfig
-    pub fn new(provider: P, config: super::ContractConfig, tx_lock: Arc<Mutex<()>>) -> Self {
+    pub fn new(provider: P, config: super::ContractConfig, nonces: Arc<NonceManager>) -> Self {
         let contract =
             HearbeatManagerInstance::new(config.manager_contract_address, provider.clone());
-        let submitter = TransactionSubmitter::new(tx_lock);
+        let submitter = TransactionSubmitter::new(nonces, &config);
         Self {
             provider,
             contract,
@@ -161,16 +160,19 @@ impl<P: Provider + Clone> HeartbeatManagerClient<P> {
     // ------------------------------------------------------------------------
 
     /// Submit an HTX for verification
-    pub async fn submit_htx(&self, htx: &Htx) -> Result<B256> {
-        let snapshot_id = self.contract.provider().get_block_number().await?;
+    pub async fn submit_htx(&self, htx: &Htx) -> Result<B256, TxError> {
+        let method = "submitHeartbeat";
+        let snapshot_id = self
+            .contract
+            .provider()
+            .get_block_number()
+            .await
+            .map_err(|e| TxError::rpc(method, e))?;
         let snapshot_id = snapshot_id.saturating_sub(1);
-        let raw_htx = alloy::primitives::Bytes::try_from(htx)?;
+        let raw_htx = alloy::primitives::Bytes::try_from(htx)
+            .map_err(|e| TxError::invalid_call(method, e))?;
         let call = self.contract.submitHeartbeat(raw_htx, snapshot_id);
-        let gas_with_buffer = overestimate_gas(&call).await?;
-        self.submitter
-            .with_gas_limit(gas_with_buffer)
-            .invoke("submitHeartbeat", call)
-            .await
+        self.submitter.invoke(method, call).await
     }
 
     /// Respond to an HTX assignment (called by assigned node)
@@ -179,42 +181,31 @@ impl<P: Provider + Clone> HeartbeatManagerClient<P> {
         event: RoundStartedEvent,
         verdict: Verdict,
         submitter_address: Address,
-    ) -> Result<B256> {
+    ) -> Result<B256, TxError> {
+        let method = "submitVerdict";
         let proofs =
-            Self::compute_merkle_proof(*self.contract.address(), &event, submitter_address)?;
-        let verdict = match verdict {
-            Verdict::Success => 1,
-            Verdict::Failure => 2,
-            Verdict::Inconclusive => 3,
-        };
+            Self::compute_merkle_proof(*self.contract.address(), &event, submitter_address)
+                .map_err(|e| TxError::invalid_call(method, e))?;
         let call = self
             .contract
-            .submitVerdict(event.heartbeatKey, verdict, proofs);
-        let gas_with_buffer = overestimate_gas(&call).await?;
-        self.submitter
-            .with_gas_limit(gas_with_buffer)
-            .invoke("submitVerdict", call)
-            .await
+            .submitVerdict(event.heartbeatKey, verdict.code(), proofs);
+        // A second verdict of the same node in a round reverts, so retrying never votes twice
+        self.submitter.invoke_idempotent(method, call).await
     }
 
-    /// Check if a specific node has responded to an HTX
+    /// Get the vote a node cast in a round of an HTX
     pub async fn get_node_vote(
         &self,
         workload_key: B256,
+        round: u8,
         node: Address,
-    ) -> Result<Option<Verdict>> {
-        let vote = self
+    ) -> Result<NodeVote> {
+        let packed = self
             .contract
-            .getVotePacked(workload_key, 0, node)
+            .getVotePacked(workload_key, round, node)
             .call()
             .await?;
-        match u8::try_from(vote).context("invalid vote")? {
-            0 => Ok(None),
-            1 => Ok(Some(Verdict::Success)),
-            2 => Ok(Some(Verdict::Failure)),
-            3 => Ok(Some(Verdict::Inconclusive)),
-            other => bail!("invalid vote: {other}"),
-        }
+        NodeVote::decode(packed)
     }
 
     // ------------------------------------------------------------------------
@@ -259,6 +250,20 @@ impl<P: Provider + Clone> HeartbeatManagerClient<P> {
         .await
     }
 
+    /// Start listening for round finalized events
+    pub async fn listen_round_finalized_events<F, Fut>(self: Arc<Self>, callback: F) -> Result<()>
+    where
+        F: FnMut(RoundFinalizedEvent) -> Fut + Send,
+        Fut: std::future::Future<Output = Result<()>> + Send,
+    {
+        let subscription = self
+            .contract
+            .event_filter::<RoundFinalizedEvent>()
+            .subscribe()
+            .await?;
+        listen_events(subscription.into_stream(), "RoundFinalized", callback).await
+    }
+
     /// Start listening for HTX submitted events
     pub async fn listen_htx_submitted_events<F, Fut>(self: Arc<Self>, callback: F) -> Result<()>
     where
@@ -489,6 +494,67 @@ pub enum Verdict {
     Inconclusive,
 }
 
+impl Verdict {
+    /// The on-chain encoding of this verdict, as used in votes and round outcomes.
+    pub fn code(self) -> u8 {
+        match self {
+            Verdict::Success => 1,
+            Verdict::Failure => 2,
+            Verdict::Inconclusive => 3,
+        }
+    }
+
+    /// Decode an on-chain verdict, `None` for codes that don't represent a verdict (e.g. 0 for
+    /// no vote).
+    pub fn from_code(code: u8) -> Option<Self> {
+        match code {
+            1 => Some(Verdict::Success),
+            2 => Some(Verdict::Failure),
+            3 => Some(Verdict::Inconclusive),
+            _ => None,
+        }
+    }
+}
+
+/// A member's vote in a round, as packed by `getVotePacked`: the verdict in the low two bits,
+/// the responded bit above it and the member's weight in the remaining bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeVote {
+    /// The verdict, `None` if the member hasn't voted.
+    pub verdict: Option<Verdict>,
+    /// The stake weight the vote counts with.
+    pub weight: U256,
+    pub responded: bool,
+}
+
+impl NodeVote {
+    const VERDICT_MASK: u64 = 0x3;
+    const RESPONDED_BIT: u64 = 1 << 2;
+    const WEIGHT_SHIFT: usize = 3;
+
+    /// Decode a packed vote.
+    pub fn decode(packed: U256) -> Result<Self> {
+        let code = u8::try_from(packed & U256::from(Self::VERDICT_MASK)).context("invalid vote")?;
+        let verdict = match code {
+            0 => None,
+            code => match Verdict::from_code(code) {
+                Some(verdict) => Some(verdict),
+                None => bail!("invalid vote: {code}"),
+            },
+        };
+        Ok(Self {
+            verdict,
+            weight: packed >> Self::WEIGHT_SHIFT,
+            responded: packed & U256::from(Self::RESPONDED_BIT) != U256::ZERO,
+        })
+    }
+
+    /// Whether the member has voted in the round.
+    pub fn has_voted(&self) -> bool {
+        self.responded
+    }
+}
+
 fn hash_pair(a: B256, b: B256) -> B256 {
     let (first, second) = if a < b { (a, b) } else { (b, a) };
 
@@ -501,6 +567,28 @@ mod tests {
     use super::*;
     use alloy::providers::DynProvider;
 
+    #[test]
+    fn verdict_codes() {
+        for verdict in [Verdict::Success, Verdict::Failure, Verdict::Inconclusive] {
+            assert_eq!(Verdict::from_code(verdict.code()), Some(verdict));
+        }
+        assert_eq!(Verdict::from_code(0), None);
+        assert_eq!(Verdict::from_code(4), None);
+    }
+
+    #[test]
+    fn node_vote_decode() {
+        let packed = (U256::from(1000) << 3) | U256::from(1 << 2) | U256::from(2);
+        let vote = NodeVote::decode(packed).unwrap();
+        assert_eq!(vote.verdict, Some(Verdict::Failure));
+        assert_eq!(vote.weight, U256::from(1000));
+        assert!(vote.has_voted());
+
+        let vote = NodeVote::decode(U256::ZERO).unwrap();
+        assert_eq!(vote.verdict, None);
+        assert!(!vote.has_voted());
+    }
+
     #[test]
     fn leaf() {
         let contract_address = "0x3dbe95e20b370c5295e7436e2d887cfda8bcb02c"
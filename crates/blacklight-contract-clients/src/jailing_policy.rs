@@ -0,0 +1,133 @@
+use crate::common::event_helper::BlockRange;
+use alloy::{
+    primitives::{Address, B256},
+    providers::Provider,
+    sol,
+};
+use anyhow::Result;
+use std::fmt;
+
+/// Blocks searched back for the jailing of an operator by default
+const DEFAULT_LOOKBACK_BLOCKS: u64 = 10_000;
+
+sol!(
+    #[sol(rpc)]
+    #[derive(Debug)]
+    contract JailingPolicy {
+        event OperatorJailed(
+            address indexed operator,
+            bytes32 indexed heartbeatKey,
+            uint8 round,
+            uint64 jailedUntil,
+            uint8 reason
+        );
+    }
+);
+
+use JailingPolicy::JailingPolicyInstance;
+
+pub type OperatorJailedEvent = JailingPolicy::OperatorJailed;
+
+/// Why the jailing policy jailed an operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JailReason {
+    /// The operator did not vote in the round
+    MissedVote,
+    /// The operator voted against the round's outcome
+    MinorityVote,
+    /// A reason code this client doesn't know
+    Other(u8),
+}
+
+impl From<u8> for JailReason {
+    fn from(code: u8) -> Self {
+        match code {
+            0 => JailReason::MissedVote,
+            1 => JailReason::MinorityVote,
+            code => JailReason::Other(code),
+        }
+    }
+}
+
+impl fmt::Display for JailReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JailReason::MissedVote => write!(f, "missed vote"),
+            JailReason::MinorityVote => write!(f, "voted against the outcome"),
+            JailReason::Other(code) => write!(f, "reason code {code}"),
+        }
+    }
+}
+
+/// The record of an operator's jailing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Jailing {
+    pub heartbeat_key: B256,
+    pub round: u8,
+    pub jailed_until: u64,
+    pub reason: JailReason,
+}
+
+impl From<OperatorJailedEvent> for Jailing {
+    fn from(event: OperatorJailedEvent) -> Self {
+        Self {
+            heartbeat_key: event.heartbeatKey,
+            round: event.round,
+            jailed_until: event.jailedUntil,
+            reason: event.reason.into(),
+        }
+    }
+}
+
+/// Read-only client for the JailingPolicy contract
+#[derive(Clone)]
+pub struct JailingPolicyClient<P: Provider + Clone> {
+    contract: JailingPolicyInstance<P>,
+    block_lookback: u64,
+}
+
+impl<P: Provider + Clone> JailingPolicyClient<P> {
+    pub fn new(provider: P, contract_address: Address) -> Self {
+        Self {
+            contract: JailingPolicyInstance::new(contract_address, provider),
+            block_lookback: DEFAULT_LOOKBACK_BLOCKS,
+        }
+    }
+
+    /// Set the number of blocks searched back for jailings.
+    pub fn with_block_lookback(mut self, blocks: u64) -> Self {
+        self.block_lookback = blocks;
+        self
+    }
+
+    /// Get the contract address
+    pub fn address(&self) -> Address {
+        *self.contract.address()
+    }
+
+    /// The most recent jailing of `operator` within the lookback, if any.
+    pub async fn last_jailing(&self, operator: Address) -> Result<Option<Jailing>> {
+        let current_block = self.contract.provider().get_block_number().await?;
+        let range = BlockRange::from_lookback(current_block, self.block_lookback);
+        let events = self
+            .contract
+            .event_filter::<OperatorJailedEvent>()
+            .topic1(operator.into_word())
+            .from_block(range.from_block)
+            .query()
+            .await?;
+        Ok(events.into_iter().last().map(|(event, _log)| event.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jail_reason() {
+        assert_eq!(JailReason::from(0), JailReason::MissedVote);
+        assert_eq!(JailReason::from(1), JailReason::MinorityVote);
+        assert_eq!(JailReason::from(7).to_string(), "reason code 7");
+    }
+}
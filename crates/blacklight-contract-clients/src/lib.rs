@@ -1,11 +1,15 @@
 use alloy::primitives::Address;
+use std::time::Duration;
 
 pub mod blacklight_client;
 pub mod common;
 pub mod heartbeat_manager;
 pub mod htx;
+pub mod jailing_policy;
 pub mod nil_token;
+pub mod operator_metadata;
 pub mod protocol_config;
+pub mod signer;
 pub mod staking_operators;
 
 // ============================================================================
@@ -13,9 +17,15 @@ pub mod staking_operators;
 // ============================================================================
 
 pub use blacklight_client::BlacklightClient;
+pub use common::fees::{FeeConfig, FeeStrategy};
+pub use common::nonce_manager::NonceManager;
+pub use common::tx_error::{RetryPolicy, TxError};
 pub use heartbeat_manager::HeartbeatManagerClient;
+pub use jailing_policy::JailingPolicyClient;
 pub use nil_token::NilTokenClient;
+pub use operator_metadata::OperatorMetadata;
 pub use protocol_config::ProtocolConfigClient;
+pub use signer::{RemoteSigner, SignerConfig};
 pub use staking_operators::StakingOperatorsClient;
 
 // ============================================================================
@@ -34,6 +44,9 @@ pub use staking_operators::StakingOperators;
 // NilToken events
 pub use nil_token::NilToken;
 
+// JailingPolicy events
+pub use jailing_policy::JailingPolicy;
+
 // ============================================================================
 // Type Aliases
 // ============================================================================
@@ -61,8 +74,22 @@ pub struct ContractConfig {
     pub rpc_url: String,
     /// Maximum number of WebSocket reconnection attempts (default: u32::MAX for infinite)
     pub max_ws_retries: u32,
+    /// How long to wait for a transaction to be mined before replacing it with higher fees
+    pub receipt_timeout: Duration,
+    /// Maximum number of fee-bumped replacements of a transaction that is not mined
+    pub max_replacements: u32,
+    /// How transaction fees and gas limits are chosen
+    pub fees: FeeConfig,
+    /// How failed idempotent transactions, i.e. verdicts, are retried, they are not by default
+    pub retry_policy: Option<RetryPolicy>,
 }
 
+/// Default time a transaction may take to be mined
+pub const DEFAULT_RECEIPT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Default number of fee-bumped replacements of a transaction
+pub const DEFAULT_MAX_REPLACEMENTS: u32 = 3;
+
 impl Default for ContractConfig {
     fn default() -> Self {
         Self {
@@ -71,6 +98,10 @@ impl Default for ContractConfig {
             token_contract_address: Address::ZERO,
             rpc_url: String::new(),
             max_ws_retries: u32::MAX,
+            receipt_timeout: DEFAULT_RECEIPT_TIMEOUT,
+            max_replacements: DEFAULT_MAX_REPLACEMENTS,
+            fees: FeeConfig::default(),
+            retry_policy: None,
         }
     }
 }
@@ -95,6 +126,10 @@ impl ContractConfig {
             token_contract_address,
             rpc_url,
             max_ws_retries: u32::MAX,
+            receipt_timeout: DEFAULT_RECEIPT_TIMEOUT,
+            max_replacements: DEFAULT_MAX_REPLACEMENTS,
+            fees: FeeConfig::default(),
+            retry_policy: None,
         }
     }
 
@@ -104,6 +139,27 @@ impl ContractConfig {
         self
     }
 
+    /// Set how long transactions may take to be mined and how often they are replaced
+    pub fn with_receipt_timeout(mut self, timeout: Duration, max_replacements: u32) -> Self {
+        self.receipt_timeout = timeout;
+        self.max_replacements = max_replacements;
+        self
+    }
+
+    /// Set how transaction fees and gas limits are chosen
+    pub fn with_fees(mut self, fees: FeeConfig) -> Self {
+        self.fees = fees;
+        self
+    }
+
+    /// Retry failed verdict submissions whose errors are retryable, and on-chain reverts that no
+    /// longer revert when simulated. Other transactions are never retried, since sending them
+    /// again could execute them twice.
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(policy);
+        self
+    }
+
     /// Create a configuration with Anvil local testnet defaults
     ///
     /// Uses deterministic Anvil deployment addresses based on standard nonce order:
@@ -125,6 +181,10 @@ impl ContractConfig {
                 .expect("Invalid manager address"),
             rpc_url: "http://127.0.0.1:8545".to_string(),
             max_ws_retries: u32::MAX,
+            receipt_timeout: DEFAULT_RECEIPT_TIMEOUT,
+            max_replacements: DEFAULT_MAX_REPLACEMENTS,
+            fees: FeeConfig::default(),
+            retry_policy: None,
         }
     }
 }
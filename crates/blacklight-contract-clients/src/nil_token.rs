@@ -1,5 +1,5 @@
 use crate::{
-    ContractConfig,
+    ContractConfig, NonceManager, TxError,
     common::{event_helper::listen_events, tx_submitter::TransactionSubmitter},
 };
 use alloy::{
@@ -9,7 +9,6 @@ use alloy::{
 };
 use anyhow::Result;
 use std::{convert::Infallible, sync::Arc};
-use tokio::sync::Mutex;
 
 // Generate type-safe contract bindings from ABI
 sol!(
@@ -42,10 +41,10 @@ pub struct NilTokenClient<P: Provider + Clone> {
 
 impl<P: Provider + Clone> NilTokenClient<P> {
     /// Create a new WebSocket client from configuration
-    pub fn new(provider: P, config: ContractConfig, tx_lock: Arc<Mutex<()>>) -> Self {
+    pub fn new(provider: P, config: ContractConfig, nonces: Arc<NonceManager>) -> Self {
         let contract_address = config.token_contract_address;
         let contract = NilTokenInstance::new(contract_address, provider.clone());
-        let submitter = TransactionSubmitter::new(tx_lock);
+        let submitter = TransactionSubmitter::new(nonces, &config);
         Self {
             contract,
             submitter,
@@ -96,19 +95,19 @@ impl<P: Provider + Clone> NilTokenClient<P> {
     // ------------------------------------------------------------------------
 
     /// Transfers tokens to a recipient
-    pub async fn transfer(&self, to: Address, amount: U256) -> Result<B256> {
+    pub async fn transfer(&self, to: Address, amount: U256) -> Result<B256, TxError> {
         let call = self.contract.transfer(to, amount);
         self.submitter.invoke("transfer", call).await
     }
 
     /// Approves a spender to spend tokens on behalf of the caller
-    pub async fn approve(&self, spender: Address, amount: U256) -> Result<B256> {
+    pub async fn approve(&self, spender: Address, amount: U256) -> Result<B256, TxError> {
         let call = self.contract.approve(spender, amount);
         self.submitter.invoke("approve", call).await
     }
 
     /// Mints new tokens (requires owner privileges)
-    pub async fn mint(&self, to: Address, amount: U256) -> Result<B256> {
+    pub async fn mint(&self, to: Address, amount: U256) -> Result<B256, TxError> {
         let call = self.contract.mint(to, amount);
         self.submitter.invoke("mint", call).await
     }
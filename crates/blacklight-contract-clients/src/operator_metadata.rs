@@ -0,0 +1,212 @@
+//! # Operator Metadata
+//!
+//! Operators register a metadata URI with the StakingOperators contract. It points to a JSON
+//! document describing the operator, so it is more than an anonymous address:
+//!
+//! ```json
+//! {"name":"acme-1","contact":"ops@acme.example","region":"eu-west","version":"0.9.0","endpoint":"https://acme.example"}
+//! ```
+//!
+//! The document is either hosted at an `http(s)://` URI or embedded in a `data:` URI, which needs
+//! no hosting at the cost of a larger registration transaction.
+
+use alloy::transports::http::reqwest::{Client, Url};
+use anyhow::{Context, Result, bail};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Prefix of metadata embedded in a `data:` URI.
+const DATA_URI_PREFIX: &str = "data:application/json;base64,";
+
+/// Maximum length of a metadata URI, which is stored on-chain.
+pub const MAX_METADATA_URI_LEN: usize = 1024;
+
+/// Maximum size of a hosted metadata document.
+const MAX_DOCUMENT_SIZE: usize = 16 * 1024;
+
+/// Timeout for fetching a hosted metadata document.
+const FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+
+const MAX_NAME_LEN: usize = 64;
+const MAX_FIELD_LEN: usize = 128;
+
+/// Description of an operator, referenced by its registered metadata URI.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct OperatorMetadata {
+    /// Human readable name of the operator
+    pub name: String,
+    /// How to reach the operator, e.g. an email address
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contact: Option<String>,
+    /// Where the node runs, e.g. `eu-west`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    /// Version of the node software
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Public `http(s)` endpoint of the operator
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+}
+
+impl OperatorMetadata {
+    /// Check that all fields are within bounds and the endpoint is an `http(s)` URL.
+    pub fn validate(&self) -> Result<()> {
+        if self.name.trim().is_empty() {
+            bail!("operator name must not be empty");
+        }
+        if self.name.len() > MAX_NAME_LEN {
+            bail!("operator name is longer than {MAX_NAME_LEN} bytes");
+        }
+        let fields = [
+            ("contact", &self.contact),
+            ("region", &self.region),
+            ("version", &self.version),
+            ("endpoint", &self.endpoint),
+        ];
+        for (field, value) in fields {
+            if value
+                .as_ref()
+                .is_some_and(|value| value.len() > MAX_FIELD_LEN)
+            {
+                bail!("operator {field} is longer than {MAX_FIELD_LEN} bytes");
+            }
+        }
+        if let Some(endpoint) = &self.endpoint {
+            let url = Url::parse(endpoint)
+                .with_context(|| format!("invalid operator endpoint '{endpoint}'"))?;
+            if !matches!(url.scheme(), "http" | "https") {
+                bail!("operator endpoint '{endpoint}' is not an http(s) URL");
+            }
+        }
+        Ok(())
+    }
+
+    /// Serialize the metadata as a JSON document.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("metadata serializes")
+    }
+
+    /// Embed the metadata in a `data:` URI.
+    pub fn to_data_uri(&self) -> Result<String> {
+        let uri = format!("{DATA_URI_PREFIX}{}", BASE64.encode(self.to_json()));
+        if uri.len() > MAX_METADATA_URI_LEN {
+            bail!(
+                "operator metadata is too large for a data: URI ({} > {MAX_METADATA_URI_LEN} bytes), host it instead",
+                uri.len()
+            );
+        }
+        Ok(uri)
+    }
+
+    /// Parse and validate a metadata document.
+    pub fn from_json(document: &[u8]) -> Result<Self> {
+        let metadata: Self =
+            serde_json::from_slice(document).context("invalid operator metadata document")?;
+        metadata.validate()?;
+        Ok(metadata)
+    }
+
+    /// Resolve a registered metadata URI, `None` if the operator registered none.
+    pub async fn resolve(uri: &str) -> Result<Option<Self>> {
+        if uri.is_empty() {
+            return Ok(None);
+        }
+        if uri.len() > MAX_METADATA_URI_LEN {
+            bail!("metadata URI is longer than {MAX_METADATA_URI_LEN} bytes");
+        }
+        if let Some(encoded) = uri.strip_prefix(DATA_URI_PREFIX) {
+            let document = BASE64
+                .decode(encoded)
+                .context("invalid base64 in metadata data: URI")?;
+            return Self::from_json(&document).map(Some);
+        }
+
+        let url = Url::parse(uri).with_context(|| format!("invalid metadata URI '{uri}'"))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            bail!("unsupported metadata URI '{uri}', expected http(s) or {DATA_URI_PREFIX}");
+        }
+        let mut response = Client::builder()
+            .timeout(FETCH_TIMEOUT)
+            .build()?
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?;
+        if response
+            .content_length()
+            .is_some_and(|length| length as usize > MAX_DOCUMENT_SIZE)
+        {
+            bail!("metadata document at {uri} is larger than {MAX_DOCUMENT_SIZE} bytes");
+        }
+        // The length may be missing or wrong, so the body is read in chunks up to the limit
+        let mut document = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            if document.len() + chunk.len() > MAX_DOCUMENT_SIZE {
+                bail!("metadata document at {uri} is larger than {MAX_DOCUMENT_SIZE} bytes");
+            }
+            document.extend_from_slice(&chunk);
+        }
+        Self::from_json(&document).map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata() -> OperatorMetadata {
+        OperatorMetadata {
+            name: "acme-1".to_string(),
+            contact: Some("ops@acme.example".to_string()),
+            region: Some("eu-west".to_string()),
+            version: Some("0.9.0".to_string()),
+            endpoint: Some("https://acme.example".to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_data_uri_roundtrip() {
+        let metadata = metadata();
+        let uri = metadata.to_data_uri().unwrap();
+        assert!(uri.starts_with(DATA_URI_PREFIX));
+        assert_eq!(
+            OperatorMetadata::resolve(&uri).await.unwrap(),
+            Some(metadata)
+        );
+        assert_eq!(OperatorMetadata::resolve("").await.unwrap(), None);
+        assert!(OperatorMetadata::resolve("ipfs://cid").await.is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(metadata().validate().is_ok());
+        let invalid = [
+            OperatorMetadata {
+                name: " ".to_string(),
+                ..metadata()
+            },
+            OperatorMetadata {
+                name: "x".repeat(MAX_NAME_LEN + 1),
+                ..metadata()
+            },
+            OperatorMetadata {
+                endpoint: Some("ftp://acme.example".to_string()),
+                ..metadata()
+            },
+            OperatorMetadata {
+                contact: Some("x".repeat(MAX_FIELD_LEN + 1)),
+                ..metadata()
+            },
+        ];
+        for metadata in invalid {
+            assert!(metadata.validate().is_err(), "{metadata:?}");
+        }
+
+        // Optional fields may be left out of the document
+        let minimal = OperatorMetadata::from_json(br#"{"name":"acme-2"}"#).unwrap();
+        assert_eq!(minimal.region, None);
+    }
+}
@@ -1,4 +1,6 @@
+use crate::common::event_helper::listen_events;
 use crate::common::tx_submitter::TransactionSubmitter;
+use crate::{ContractConfig, NonceManager, TxError};
 use alloy::{
     primitives::{Address, B256},
     providers::Provider,
@@ -6,7 +8,6 @@ use alloy::{
 };
 use anyhow::Result;
 use std::sync::Arc;
-use tokio::sync::Mutex;
 
 sol!(
     #[sol(rpc)]
@@ -28,11 +29,14 @@ sol!(
         function nodeVersion() external view returns (string memory);
         function setNodeVersion(string calldata newVersion) external;
         function rewardPolicy() external view override returns (address);
+        function jailingPolicy() external view override returns (address);
     }
 );
 
 use ProtocolConfig::ProtocolConfigInstance;
 
+pub type NodeVersionUpdatedEvent = ProtocolConfig::NodeVersionUpdated;
+
 /// Client for interacting with the ProtocolConfig contract
 #[derive(Clone)]
 pub struct ProtocolConfigClient<P: Provider + Clone> {
@@ -42,9 +46,14 @@ pub struct ProtocolConfigClient<P: Provider + Clone> {
 
 impl<P: Provider + Clone> ProtocolConfigClient<P> {
     /// Create a new ProtocolConfigClient
-    pub fn new(provider: P, contract_address: Address, tx_lock: Arc<Mutex<()>>) -> Self {
+    pub fn new(
+        provider: P,
+        contract_address: Address,
+        config: &ContractConfig,
+        nonces: Arc<NonceManager>,
+    ) -> Self {
         let contract = ProtocolConfigInstance::new(contract_address, provider);
-        let submitter = TransactionSubmitter::new(tx_lock);
+        let submitter = TransactionSubmitter::new(nonces, config);
         Self {
             contract,
             submitter,
@@ -70,12 +79,38 @@ impl<P: Provider + Clone> ProtocolConfigClient<P> {
         Ok(self.contract.rewardPolicy().call().await?)
     }
 
+    /// Returns the jailing policy address.
+    pub async fn jailing_policy_address(&self) -> Result<Address> {
+        Ok(self.contract.jailingPolicy().call().await?)
+    }
+
+    // ------------------------------------------------------------------------
+    // Real-time Event Streaming
+    // ------------------------------------------------------------------------
+
+    /// Start listening for node version updates
+    pub async fn listen_node_version_updated_events<F, Fut>(
+        self: Arc<Self>,
+        callback: F,
+    ) -> Result<()>
+    where
+        F: FnMut(NodeVersionUpdatedEvent) -> Fut + Send,
+        Fut: std::future::Future<Output = Result<()>> + Send,
+    {
+        let subscription = self
+            .contract
+            .event_filter::<NodeVersionUpdatedEvent>()
+            .subscribe()
+            .await?;
+        listen_events(subscription.into_stream(), "NodeVersionUpdated", callback).await
+    }
+
     // ------------------------------------------------------------------------
     // Admin Functions (owner only)
     // ------------------------------------------------------------------------
 
     /// Sets the node version (owner only)
-    pub async fn set_node_version(&self, new_version: String) -> Result<B256> {
+    pub async fn set_node_version(&self, new_version: String) -> Result<B256, TxError> {
         let call = self.contract.setNodeVersion(new_version);
         self.submitter.invoke("setNodeVersion", call).await
     }
@@ -0,0 +1,241 @@
+//! # Signers
+//!
+//! Transactions are signed either in-process with a private key, or by a remote signing service
+//! such as web3signer, so the key never enters this process.
+//!
+//! The remote signer speaks the `eth_signTransaction` JSON-RPC method: it receives the
+//! transaction fields and answers with the signed, EIP-2718 encoded transaction. The signature is
+//! only used if the service signed exactly the transaction that was asked for.
+
+use alloy::{
+    consensus::{SignableTransaction, TxEnvelope},
+    eips::eip2718::Decodable2718,
+    network::{EthereumWallet, TxSigner},
+    primitives::{Address, Bytes, Signature},
+    rpc::{client::RpcClient, types::TransactionRequest},
+    signers::{self, local::PrivateKeySigner},
+    transports::http::reqwest::Url,
+};
+use anyhow::Context;
+use async_trait::async_trait;
+use std::fmt;
+
+/// Where transactions are signed.
+#[derive(Clone)]
+pub enum SignerConfig {
+    /// A private key held in-process.
+    Local(String),
+    /// A remote signing service holding the key of `address`.
+    Remote { url: String, address: Address },
+}
+
+impl SignerConfig {
+    /// The address transactions are sent from.
+    pub fn address(&self) -> anyhow::Result<Address> {
+        match self {
+            SignerConfig::Local(private_key) => {
+                let signer: PrivateKeySigner = private_key.parse()?;
+                Ok(signer.address())
+            }
+            SignerConfig::Remote { address, .. } => Ok(*address),
+        }
+    }
+
+    /// A wallet signing with this signer.
+    pub fn wallet(&self) -> anyhow::Result<EthereumWallet> {
+        match self {
+            SignerConfig::Local(private_key) => {
+                let signer: PrivateKeySigner = private_key.parse::<PrivateKeySigner>()?;
+                Ok(EthereumWallet::from(signer))
+            }
+            SignerConfig::Remote { url, address } => {
+                Ok(EthereumWallet::new(RemoteSigner::new(url, *address)?))
+            }
+        }
+    }
+}
+
+impl From<String> for SignerConfig {
+    fn from(private_key: String) -> Self {
+        SignerConfig::Local(private_key)
+    }
+}
+
+// Never print the private key
+impl fmt::Debug for SignerConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignerConfig::Local(_) => f.write_str("Local(..)"),
+            SignerConfig::Remote { url, address } => f
+                .debug_struct("Remote")
+                .field("url", url)
+                .field("address", address)
+                .finish(),
+        }
+    }
+}
+
+/// Signs transactions through a remote `eth_signTransaction` endpoint.
+#[derive(Clone, Debug)]
+pub struct RemoteSigner {
+    client: RpcClient,
+    address: Address,
+}
+
+impl RemoteSigner {
+    pub fn new(url: &str, address: Address) -> anyhow::Result<Self> {
+        let url = Url::parse(url).with_context(|| format!("invalid remote signer url {url}"))?;
+        Ok(Self {
+            client: RpcClient::new_http(url),
+            address,
+        })
+    }
+
+    /// The `eth_signTransaction` parameters of a transaction.
+    fn request(&self, tx: &dyn SignableTransaction<Signature>) -> TransactionRequest {
+        let mut request = TransactionRequest::default()
+            .from(self.address)
+            .nonce(tx.nonce())
+            .gas_limit(tx.gas_limit())
+            .value(tx.value())
+            .input(tx.input().clone().into())
+            .transaction_type(tx.ty());
+        request.to = Some(tx.kind());
+        request.chain_id = tx.chain_id();
+        match tx.gas_price() {
+            Some(gas_price) => request.gas_price = Some(gas_price),
+            None => {
+                request.max_fee_per_gas = Some(tx.max_fee_per_gas());
+                request.max_priority_fee_per_gas = tx.max_priority_fee_per_gas();
+            }
+        }
+        if let Some(access_list) = tx.access_list() {
+            request.access_list = Some(access_list.clone());
+        }
+        request
+    }
+}
+
+#[async_trait]
+impl TxSigner<Signature> for RemoteSigner {
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    async fn sign_transaction(
+        &self,
+        tx: &mut dyn SignableTransaction<Signature>,
+    ) -> signers::Result<Signature> {
+        let request = self.request(tx);
+        let raw: Bytes = self
+            .client
+            .request("eth_signTransaction", (request,))
+            .await
+            .map_err(signers::Error::other)?;
+        let signed = TxEnvelope::decode_2718(&mut raw.as_ref()).map_err(signers::Error::other)?;
+
+        // The service must not have changed any field of the transaction
+        if signed.signature_hash() != tx.signature_hash() {
+            return Err(signers::Error::message(
+                "remote signer signed a different transaction",
+            ));
+        }
+        Ok(*signed.signature())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::{
+        consensus::{TxEip1559, TypedTransaction},
+        eips::eip2718::Encodable2718,
+        network::TxSignerSync,
+        primitives::{U256, address},
+    };
+    use serde_json::{Value, json};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// A local stand-in for a remote signing service, answering `eth_signTransaction` with
+    /// `signer`'s key.
+    async fn signing_service(signer: PrivateKeySigner) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut buf = Vec::new();
+                let body = loop {
+                    let mut chunk = [0u8; 4096];
+                    let n = stream.read(&mut chunk).await.unwrap();
+                    buf.extend_from_slice(&chunk[..n]);
+                    let text = String::from_utf8_lossy(&buf).to_string();
+                    if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                        let length = head
+                            .lines()
+                            .find_map(|line| {
+                                let (name, value) = line.split_once(':')?;
+                                name.eq_ignore_ascii_case("content-length")
+                                    .then(|| value.trim().parse::<usize>().ok())?
+                            })
+                            .unwrap_or(0);
+                        if body.len() >= length {
+                            break body.to_string();
+                        }
+                    }
+                };
+
+                let call: Value = serde_json::from_str(&body).unwrap();
+                let request: TransactionRequest =
+                    serde_json::from_value(call["params"][0].clone()).unwrap();
+                let mut tx = request.build_typed_tx().unwrap();
+                let signature = signer.sign_transaction_sync(&mut tx).unwrap();
+                let signed = TxEnvelope::from((tx, signature));
+                let response = json!({
+                    "jsonrpc": "2.0",
+                    "id": call["id"],
+                    "result": Bytes::from(signed.encoded_2718()),
+                })
+                .to_string();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{response}",
+                    response.len()
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        format!("http://{addr}")
+    }
+
+    #[tokio::test]
+    async fn test_remote_signer() {
+        let key = PrivateKeySigner::random();
+        let url = signing_service(key.clone()).await;
+        let remote = RemoteSigner::new(&url, key.address()).unwrap();
+
+        let mut tx = TypedTransaction::Eip1559(TxEip1559 {
+            chain_id: 31337,
+            nonce: 7,
+            gas_limit: 100_000,
+            max_fee_per_gas: 2_000_000_000,
+            max_priority_fee_per_gas: 1,
+            to: address!("0x5FbDB2315678afecb367f032d93F642f64180aa3").into(),
+            value: U256::from(1),
+            ..Default::default()
+        });
+        let signature = remote.sign_transaction(&mut tx).await.unwrap();
+        let recovered = signature
+            .recover_address_from_prehash(&tx.signature_hash())
+            .unwrap();
+        assert_eq!(recovered, key.address());
+    }
+
+    #[test]
+    fn test_signer_config() {
+        let key = PrivateKeySigner::random();
+        let local = SignerConfig::from(format!("0x{}", alloy::hex::encode(key.to_bytes())));
+        assert_eq!(local.address().unwrap(), key.address());
+        assert_eq!(format!("{local:?}"), "Local(..)");
+    }
+}
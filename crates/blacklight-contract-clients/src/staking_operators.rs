@@ -1,4 +1,4 @@
-use crate::{ContractConfig, common::tx_submitter::TransactionSubmitter};
+use crate::{ContractConfig, NonceManager, TxError, common::tx_submitter::TransactionSubmitter};
 use alloy::{
     primitives::{Address, B256, U256},
     providers::Provider,
@@ -7,9 +7,9 @@ use alloy::{
 use anyhow::Result;
 use futures_util::future::join_all;
 use std::sync::Arc;
-use tokio::sync::Mutex;
 
 sol!(
+    #[derive(Debug)]
     interface IStakingOperators {
         struct Tranche { uint256 amount; uint64 releaseTime; }
     }
@@ -35,10 +35,13 @@ sol!(
         function stakeOf(address operator) external view override returns (uint256);
         function totalStaked() external view override returns (uint256);
         function unbondingStaker(address operator) external view returns (address);
+        function getUnbondingTranches(address operator) external view returns (IStakingOperators.Tranche[] memory);
         function isActiveOperator(address operator) public view override returns (bool);
+        function jailedUntil(address operator) external view returns (uint64);
         function getActiveOperators() external view override returns (address[] memory);
         function stakeTo(address operator, uint256 amount) external override nonReentrant whenNotPaused;
         function registerOperator(string calldata metadataURI) external override whenNotPaused;
+        function operatorMetadataURI(address operator) external view returns (string memory);
         function deactivateOperator() external override whenNotPaused;
         function reactivateOperator() external override whenNotPaused;
         function requestUnstake(address operator, uint256 amount) external override nonReentrant whenNotPaused;
@@ -48,6 +51,9 @@ sol!(
 
 use StakingOperators::StakingOperatorsInstance;
 
+/// NIL being unbonded from an operator, withdrawable once its release time (unix seconds) passes
+pub type Tranche = IStakingOperators::Tranche;
+
 /// Client for interacting with the StakingOperators contract
 #[derive(Clone)]
 pub struct StakingOperatorsClient<P: Provider + Clone> {
@@ -56,10 +62,10 @@ pub struct StakingOperatorsClient<P: Provider + Clone> {
 }
 
 impl<P: Provider + Clone> StakingOperatorsClient<P> {
-    pub fn new(provider: P, config: ContractConfig, tx_lock: Arc<Mutex<()>>) -> Self {
+    pub fn new(provider: P, config: ContractConfig, nonces: Arc<NonceManager>) -> Self {
         let contract =
             StakingOperatorsInstance::new(config.staking_contract_address, provider.clone());
-        let submitter = TransactionSubmitter::new(tx_lock);
+        let submitter = TransactionSubmitter::new(nonces, &config);
 
         Self {
             contract,
@@ -92,12 +98,38 @@ impl<P: Provider + Clone> StakingOperatorsClient<P> {
         Ok(self.contract.stakeOf(operator).call().await?)
     }
 
+    /// Returns the total stake across all operators
+    pub async fn total_staked(&self) -> Result<U256> {
+        Ok(self.contract.totalStaked().call().await?)
+    }
+
+    /// Returns the staker whose unstake request on an operator is unbonding, zero if there is none
+    pub async fn unbonding_staker(&self, operator: Address) -> Result<Address> {
+        Ok(self.contract.unbondingStaker(operator).call().await?)
+    }
+
+    /// Returns the tranches being unbonded from an operator
+    pub async fn unbonding_tranches(&self, operator: Address) -> Result<Vec<Tranche>> {
+        Ok(self.contract.getUnbondingTranches(operator).call().await?)
+    }
+
     /// Checks if an operator is active
     pub async fn is_active_operator(&self, operator: Address) -> Result<bool> {
         // Solidity: function isActiveOperator(address) external view returns (bool)
         Ok(self.contract.isActiveOperator(operator).call().await?)
     }
 
+    /// Returns the time (unix seconds) an operator's jail ends, 0 if it was never jailed
+    pub async fn jailed_until(&self, operator: Address) -> Result<u64> {
+        // Solidity: function jailedUntil(address) external view returns (uint64)
+        Ok(self.contract.jailedUntil(operator).call().await?)
+    }
+
+    /// Returns the metadata URI an operator registered with, empty if none
+    pub async fn metadata_uri(&self, operator: Address) -> Result<String> {
+        Ok(self.contract.operatorMetadataURI(operator).call().await?)
+    }
+
     /// Returns a list of all currently active operators
     pub async fn get_active_operators(&self) -> Result<Vec<Address>> {
         // Solidity: function getActiveOperators() external view returns (address[])
@@ -142,19 +174,19 @@ impl<P: Provider + Clone> StakingOperatorsClient<P> {
     // ------------------------------------------------------------------------
 
     /// Stakes tokens to a specific operator
-    pub async fn stake_to(&self, operator: Address, amount: U256) -> Result<B256> {
+    pub async fn stake_to(&self, operator: Address, amount: U256) -> Result<B256, TxError> {
         let call = self.contract.stakeTo(operator, amount);
         self.submitter.invoke("stakeTo", call).await
     }
 
     /// Requests to unstake tokens from an operator
-    pub async fn request_unstake(&self, operator: Address, amount: U256) -> Result<B256> {
+    pub async fn request_unstake(&self, operator: Address, amount: U256) -> Result<B256, TxError> {
         let call = self.contract.requestUnstake(operator, amount);
         self.submitter.invoke("requestUnstake", call).await
     }
 
     /// Withdraws unstaked tokens after the unbonding period has passed
-    pub async fn withdraw_unstaked(&self, operator: Address) -> Result<B256> {
+    pub async fn withdraw_unstaked(&self, operator: Address) -> Result<B256, TxError> {
         let call = self.contract.withdrawUnstaked(operator);
         self.submitter.invoke("withdrawUnstaked", call).await
     }
@@ -164,14 +196,20 @@ impl<P: Provider + Clone> StakingOperatorsClient<P> {
     // ------------------------------------------------------------------------
 
     /// Registers the caller as an operator or updates their metadata
-    pub async fn register_operator(&self, metadata_uri: String) -> Result<B256> {
+    pub async fn register_operator(&self, metadata_uri: String) -> Result<B256, TxError> {
         let call = self.contract.registerOperator(metadata_uri);
         self.submitter.invoke("registerOperator", call).await
     }
 
     /// Deactivates the caller as an operator
-    pub async fn deactivate_operator(&self) -> Result<B256> {
+    pub async fn deactivate_operator(&self) -> Result<B256, TxError> {
         let call = self.contract.deactivateOperator();
         self.submitter.invoke("deactivateOperator", call).await
     }
+
+    /// Reactivates the caller as an operator after a deactivation
+    pub async fn reactivate_operator(&self) -> Result<B256, TxError> {
+        let call = self.contract.reactivateOperator();
+        self.submitter.invoke("reactivateOperator", call).await
+    }
 }
@@ -1,7 +1,11 @@
 use alloy::primitives::Address;
 use anyhow::anyhow;
-use clap::Args;
+use blacklight_contract_clients::{
+    DEFAULT_MAX_REPLACEMENTS, DEFAULT_RECEIPT_TIMEOUT, FeeConfig, FeeStrategy,
+};
+use clap::{Args, ValueEnum};
 use state_file::StateFile;
+use std::time::Duration;
 
 #[derive(Args, Debug)]
 pub struct ChainArgs {
@@ -63,3 +67,166 @@ impl ChainConfig {
         })
     }
 }
+
+/// How transaction fees are chosen
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FeeStrategyKind {
+    /// A fixed priority fee
+    Fixed,
+    /// A percentile of the priority fees paid in recent blocks
+    Percentile,
+    /// The estimated fees multiplied, with a capped priority fee
+    Multiplier,
+}
+
+/// Fee and gas settings of contract transactions
+#[derive(Args, Debug, Clone)]
+pub struct FeeArgs {
+    /// How transaction fees are chosen
+    #[arg(long, env = "FEE_STRATEGY", value_enum, default_value_t = FeeStrategyKind::Fixed)]
+    pub fee_strategy: FeeStrategyKind,
+
+    /// Priority fee in wei, for the fixed strategy
+    #[arg(long, env = "PRIORITY_FEE_WEI", default_value_t = 1)]
+    pub priority_fee_wei: u128,
+
+    /// Percentile of the priority fees paid in recent blocks, for the percentile strategy
+    #[arg(long, env = "FEE_PERCENTILE", default_value_t = 50.0)]
+    pub fee_percentile: f64,
+
+    /// Multiplier of the estimated fees, for the multiplier strategy
+    #[arg(long, env = "FEE_MULTIPLIER", default_value_t = 1.5)]
+    pub fee_multiplier: f64,
+
+    /// Cap of the priority fee in wei, for the multiplier strategy
+    #[arg(long, env = "MAX_PRIORITY_FEE_WEI", default_value_t = 1_000_000_000)]
+    pub max_priority_fee_wei: u128,
+
+    /// Transactions are not sent with a max fee per gas above this many wei
+    #[arg(long, env = "MAX_FEE_PER_GAS_WEI")]
+    pub max_fee_per_gas_wei: Option<u128>,
+
+    /// Gas limit buffer over the estimate, in percent
+    #[arg(long, env = "GAS_BUFFER_PERCENT", default_value_t = 50)]
+    pub gas_buffer_percent: u64,
+
+    /// Gas limit buffers of specific contract methods, e.g. `submitVerdict=30,distributeRewards=100`
+    #[arg(long, env = "METHOD_GAS_BUFFERS", value_delimiter = ',', value_parser = parse_method_gas_buffer)]
+    pub method_gas_buffers: Vec<(String, u64)>,
+}
+
+impl FeeArgs {
+    /// The fee configuration of the contract clients
+    pub fn fee_config(&self) -> anyhow::Result<FeeConfig> {
+        let strategy = match self.fee_strategy {
+            FeeStrategyKind::Fixed => FeeStrategy::Fixed {
+                priority_fee: self.priority_fee_wei,
+            },
+            FeeStrategyKind::Percentile => {
+                if !(0.0..=100.0).contains(&self.fee_percentile) {
+                    return Err(anyhow!("fee percentile must be between 0 and 100"));
+                }
+                FeeStrategy::Percentile {
+                    percentile: self.fee_percentile,
+                }
+            }
+            FeeStrategyKind::Multiplier => {
+                if !(self.fee_multiplier.is_finite() && self.fee_multiplier > 0.0) {
+                    return Err(anyhow!("fee multiplier must be positive"));
+                }
+                FeeStrategy::Multiplier {
+                    multiplier: self.fee_multiplier,
+                    max_priority_fee: self.max_priority_fee_wei,
+                }
+            }
+        };
+        Ok(FeeConfig {
+            strategy,
+            max_fee_per_gas: self.max_fee_per_gas_wei,
+            gas_buffer_percent: self.gas_buffer_percent,
+            method_gas_buffers: self.method_gas_buffers.iter().cloned().collect(),
+        })
+    }
+}
+
+/// How long transactions are waited for and replaced
+#[derive(Args, Debug, Clone)]
+pub struct ReceiptArgs {
+    /// Seconds to wait for a transaction to be mined before replacing it with higher fees
+    #[arg(long, env = "RECEIPT_TIMEOUT_SECS", default_value_t = DEFAULT_RECEIPT_TIMEOUT.as_secs())]
+    pub receipt_timeout_secs: u64,
+
+    /// Number of times a transaction not mined in time is replaced before giving up on it
+    #[arg(long, env = "MAX_TX_REPLACEMENTS", default_value_t = DEFAULT_MAX_REPLACEMENTS)]
+    pub max_tx_replacements: u32,
+}
+
+impl ReceiptArgs {
+    /// How long to wait for a transaction to be mined
+    pub fn receipt_timeout(&self) -> anyhow::Result<Duration> {
+        if self.receipt_timeout_secs == 0 {
+            return Err(anyhow!("receipt timeout must be positive"));
+        }
+        Ok(Duration::from_secs(self.receipt_timeout_secs))
+    }
+}
+
+/// Parse a `method=percent` gas buffer
+fn parse_method_gas_buffer(value: &str) -> Result<(String, u64), String> {
+    let (method, percent) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected method=percent, got '{value}'"))?;
+    let percent = percent
+        .trim()
+        .parse()
+        .map_err(|e| format!("invalid gas buffer of {method}: {e}"))?;
+    Ok((method.trim().to_string(), percent))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[clap(flatten)]
+        fees: FeeArgs,
+    }
+
+    fn fee_config(args: &[&str]) -> anyhow::Result<FeeConfig> {
+        let cli = Cli::try_parse_from(std::iter::once("test").chain(args.iter().copied()))?;
+        cli.fees.fee_config()
+    }
+
+    #[test]
+    fn test_fee_config() {
+        assert_eq!(fee_config(&[]).unwrap(), FeeConfig::default());
+
+        let config = fee_config(&[
+            "--fee-strategy",
+            "multiplier",
+            "--fee-multiplier",
+            "2",
+            "--max-fee-per-gas-wei",
+            "5000000000",
+            "--method-gas-buffers",
+            "submitVerdict=30,distributeRewards=100",
+        ])
+        .unwrap();
+        assert_eq!(
+            config.strategy,
+            FeeStrategy::Multiplier {
+                multiplier: 2.0,
+                max_priority_fee: 1_000_000_000
+            }
+        );
+        assert_eq!(config.max_fee_per_gas, Some(5_000_000_000));
+        assert_eq!(config.gas_buffer("submitVerdict"), 30);
+        assert_eq!(config.gas_buffer("distributeRewards"), 100);
+        assert_eq!(config.gas_buffer("stakeTo"), 50);
+
+        assert!(fee_config(&["--fee-strategy", "percentile", "--fee-percentile", "150"]).is_err());
+        assert!(fee_config(&["--method-gas-buffers", "submitVerdict"]).is_err());
+    }
+}
@@ -0,0 +1,253 @@
+//! Loading of the private key transactions are signed with, either given in plaintext or read
+//! from an encrypted JSON keystore (Web3 Secret Storage, scrypt).
+
+use alloy::hex;
+use alloy::primitives::Address;
+use alloy::signers::local::PrivateKeySigner;
+use anyhow::{Context, anyhow, bail};
+use blacklight_contract_clients::SignerConfig;
+use clap::Args;
+use state_file::StateFile;
+use std::fs;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Environment variable the keystore passphrase can be given in.
+const KEYSTORE_PASSWORD_ENV: &str = "KEYSTORE_PASSWORD";
+
+#[derive(Args, Debug, Clone)]
+pub struct KeyArgs {
+    /// Private key for contract interactions
+    #[arg(long, env = "PRIVATE_KEY")]
+    pub private_key: Option<String>,
+
+    /// Encrypted JSON keystore holding the private key
+    #[arg(long, env = "KEYSTORE_PATH")]
+    pub keystore_path: Option<PathBuf>,
+
+    /// File containing the keystore passphrase; otherwise it is read from `KEYSTORE_PASSWORD` or
+    /// prompted for
+    #[arg(long, env = "KEYSTORE_PASSWORD_FILE")]
+    pub keystore_password_file: Option<PathBuf>,
+
+    /// URL of a remote signing service (web3signer-compatible `eth_signTransaction`) holding the
+    /// key, instead of a local private key
+    #[arg(long, env = "REMOTE_SIGNER_URL", requires = "remote_signer_address")]
+    pub remote_signer_url: Option<String>,
+
+    /// Address of the key held by the remote signing service
+    #[arg(long, env = "REMOTE_SIGNER_ADDRESS")]
+    pub remote_signer_address: Option<Address>,
+}
+
+impl KeyArgs {
+    /// The remote signing service, if one is configured.
+    pub fn remote_signer(&self) -> Option<SignerConfig> {
+        let url = self.remote_signer_url.clone()?;
+        let address = self.remote_signer_address?;
+        info!(url = %url, address = %address, "Signing transactions with a remote signer");
+        Some(SignerConfig::Remote { url, address })
+    }
+
+    /// The remote signer or the private key given in plaintext or as a keystore, if any.
+    pub fn signer(&self) -> anyhow::Result<Option<SignerConfig>> {
+        if let Some(signer) = self.remote_signer() {
+            return Ok(Some(signer));
+        }
+        Ok(self.private_key()?.map(SignerConfig::Local))
+    }
+
+    /// The private key given in plaintext or as a keystore, if any.
+    pub fn private_key(&self) -> anyhow::Result<Option<String>> {
+        if let Some(private_key) = &self.private_key {
+            return Ok(Some(private_key.clone()));
+        }
+        match &self.keystore_path {
+            Some(path) => self.decrypt(path).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Like [`KeyArgs::private_key`], falling back to the keystore or plaintext key recorded in
+    /// the state file.
+    pub fn load(&self, state_file: &StateFile) -> anyhow::Result<Option<String>> {
+        if let Some(private_key) = self.private_key()? {
+            return Ok(Some(private_key));
+        }
+        if let Some(path) = state_file.load_value("KEYSTORE_PATH") {
+            return self.decrypt(Path::new(&path)).map(Some);
+        }
+        let private_key = state_file.load_value("PRIVATE_KEY");
+        if private_key.is_some() {
+            warn!(
+                "⚠️ The private key is stored in plaintext in {}, consider migrating it to an encrypted keystore",
+                state_file.path().display()
+            );
+        }
+        Ok(private_key)
+    }
+
+    /// Generate a new private key into an encrypted keystore at the configured path, or at
+    /// `default_path`. Returns `None` if no passphrase is available to encrypt it with.
+    pub fn generate_keystore(
+        &self,
+        default_path: &Path,
+    ) -> anyhow::Result<Option<(PrivateKeySigner, PathBuf)>> {
+        let Some(password) = self.new_passphrase()? else {
+            return Ok(None);
+        };
+        let path = self.keystore_path.as_deref().unwrap_or(default_path);
+        let signer = PrivateKeySigner::random();
+        encrypt_keystore(path, &signer, &password)?;
+        Ok(Some((signer, path.to_path_buf())))
+    }
+
+    /// Move the plaintext private key out of `state_file` into an encrypted keystore at the
+    /// configured path, or at `default_path`, and record the keystore's path in its place.
+    pub fn migrate(&self, state_file: &StateFile, default_path: &Path) -> anyhow::Result<Address> {
+        let private_key = state_file
+            .load_value("PRIVATE_KEY")
+            .ok_or_else(|| anyhow!("no private key in {}", state_file.path().display()))?;
+        let signer: PrivateKeySigner = private_key.parse().context("invalid private key")?;
+        let path = self.keystore_path.as_deref().unwrap_or(default_path);
+        if path.exists() {
+            bail!("keystore {} already exists", path.display());
+        }
+        let password = self
+            .new_passphrase()?
+            .ok_or_else(|| anyhow!("no keystore passphrase given"))?;
+        encrypt_keystore(path, &signer, &password)?;
+
+        // Make sure the keystore opens before the plaintext key is dropped
+        let decrypted = PrivateKeySigner::decrypt_keystore(path, &password)?;
+        if decrypted.address() != signer.address() {
+            bail!("keystore {} does not hold the migrated key", path.display());
+        }
+        state_file.save_value("KEYSTORE_PATH", &path.display().to_string())?;
+        state_file.remove_value("PRIVATE_KEY")?;
+        info!(
+            address = %signer.address(),
+            keystore = %path.display(),
+            "🔐 Private key migrated to an encrypted keystore"
+        );
+        Ok(signer.address())
+    }
+
+    fn decrypt(&self, path: &Path) -> anyhow::Result<String> {
+        let password = match self.passphrase()? {
+            Some(password) => password,
+            None if std::io::stdin().is_terminal() => {
+                rpassword::prompt_password(format!("Passphrase for {}: ", path.display()))?
+            }
+            None => bail!(
+                "no passphrase for keystore {}, set KEYSTORE_PASSWORD_FILE or {KEYSTORE_PASSWORD_ENV}",
+                path.display()
+            ),
+        };
+        let signer = PrivateKeySigner::decrypt_keystore(path, password)
+            .with_context(|| format!("failed to decrypt keystore {}", path.display()))?;
+        Ok(format!("0x{}", hex::encode(signer.to_bytes())))
+    }
+
+    /// The passphrase from the password file or the environment.
+    fn passphrase(&self) -> anyhow::Result<Option<String>> {
+        if let Some(path) = &self.keystore_password_file {
+            let password = fs::read_to_string(path).with_context(|| {
+                format!("failed to read keystore password file {}", path.display())
+            })?;
+            return Ok(Some(password.trim_end_matches(['\r', '\n']).to_string()));
+        }
+        Ok(std::env::var(KEYSTORE_PASSWORD_ENV).ok())
+    }
+
+    /// The passphrase to encrypt a new keystore with, prompting for it twice on a terminal.
+    fn new_passphrase(&self) -> anyhow::Result<Option<String>> {
+        if let Some(password) = self.passphrase()? {
+            return Ok(Some(password));
+        }
+        if !std::io::stdin().is_terminal() {
+            return Ok(None);
+        }
+        let password = rpassword::prompt_password("New keystore passphrase: ")?;
+        let confirmation = rpassword::prompt_password("Repeat the passphrase: ")?;
+        if password != confirmation {
+            bail!("passphrases do not match");
+        }
+        Ok(Some(password))
+    }
+}
+
+/// Write `signer`'s key to an encrypted keystore at `path`, readable only by its owner.
+fn encrypt_keystore(path: &Path, signer: &PrivateKeySigner, password: &str) -> anyhow::Result<()> {
+    let name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| anyhow!("invalid keystore path {}", path.display()))?;
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    fs::create_dir_all(dir)?;
+    PrivateKeySigner::encrypt_keystore(
+        dir,
+        &mut rand::thread_rng(),
+        signer.to_bytes(),
+        password,
+        Some(name),
+    )
+    .with_context(|| format!("failed to write keystore {}", path.display()))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_state_file() {
+        let dir = std::env::temp_dir().join(format!("key-args-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let password_file = dir.join("password");
+        fs::write(&password_file, "correct horse\n").unwrap();
+        let state = StateFile::new(dir.join("node.env"));
+        let signer = PrivateKeySigner::random();
+        state
+            .save_value(
+                "PRIVATE_KEY",
+                &format!("0x{}", hex::encode(signer.to_bytes())),
+            )
+            .unwrap();
+
+        let args = KeyArgs {
+            private_key: None,
+            keystore_path: None,
+            keystore_password_file: Some(password_file),
+            remote_signer_url: None,
+            remote_signer_address: None,
+        };
+        let keystore = dir.join("node.keystore.json");
+        assert_eq!(args.migrate(&state, &keystore).unwrap(), signer.address());
+        assert_eq!(state.load_value("PRIVATE_KEY"), None);
+        assert!(
+            fs::read_to_string(state.path())
+                .unwrap()
+                .contains("KEYSTORE_PATH")
+        );
+
+        let private_key = args.load(&state).unwrap().unwrap();
+        let loaded: PrivateKeySigner = private_key.parse().unwrap();
+        assert_eq!(loaded.address(), signer.address());
+
+        // The keystore is never overwritten
+        assert!(args.migrate(&state, &keystore).is_err());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -18,6 +18,11 @@ impl StateFile {
         }
     }
 
+    /// The path of the state file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
     /// Load a single value by key from the state file.
     pub fn load_value(&self, key: &str) -> Option<String> {
         if !self.path.exists() {
@@ -40,6 +45,15 @@ impl StateFile {
         self.save_all(&state)
     }
 
+    /// Remove a single key from the state file, preserving other values.
+    pub fn remove_value(&self, key: &str) -> Result<()> {
+        let mut state = self.load_all();
+        if state.remove(key).is_some() {
+            self.save_all(&state)?;
+        }
+        Ok(())
+    }
+
     /// Load all key-value pairs from the state file.
     pub fn load_all(&self) -> HashMap<String, String> {
         let mut state = HashMap::new();
@@ -135,6 +149,22 @@ mod tests {
         fs::remove_file(temp_file).unwrap();
     }
 
+    #[test]
+    fn test_remove_value() {
+        let temp_file = "test_state_5.env";
+        let state = StateFile::new(temp_file);
+
+        state.save_value("KEY1", "value1").unwrap();
+        state.save_value("KEY2", "value2").unwrap();
+        state.remove_value("KEY1").unwrap();
+
+        assert_eq!(state.load_value("KEY1"), None);
+        assert_eq!(state.load_value("KEY2"), Some("value2".to_string()));
+
+        // Clean up
+        fs::remove_file(temp_file).unwrap();
+    }
+
     #[test]
     fn test_delete() {
         let temp_file = "test_state_4.env";
@@ -1,7 +1,9 @@
 use alloy::primitives::{Address, U256};
-use alloy::signers::local::PrivateKeySigner;
-use anyhow::Result;
+use anyhow::{Result, anyhow};
+use blacklight_contract_clients::{FeeConfig, SignerConfig};
+use chain_args::{FeeArgs, ReceiptArgs};
 use clap::Parser;
+use key_args::KeyArgs;
 use std::env;
 use std::time::Duration;
 use tracing::info;
@@ -42,9 +44,17 @@ pub struct CliArgs {
     #[arg(long, env = "L2_STAKING_OPERATORS_ADDRESS")]
     pub l2_staking_operators_address: Address,
 
-    /// Private key for contract interactions
-    #[arg(long, env = "PRIVATE_KEY")]
-    pub private_key: String,
+    #[clap(flatten)]
+    pub keys: KeyArgs,
+
+    /// Fee settings of L2 transactions, L1 transactions use the node's estimate
+    #[clap(flatten)]
+    pub fees: FeeArgs,
+
+    /// Receipt timeout of transactions. The keeper doesn't replace transactions, those not mined
+    /// in time fail
+    #[clap(flatten)]
+    pub receipts: ReceiptArgs,
 
     /// ETH value (wei) to forward for L1 -> L2 bridge messages
     #[arg(long, env = "L1_BRIDGE_VALUE_WEI", default_value_t = Default::default())]
@@ -84,7 +94,9 @@ pub struct KeeperConfig {
     pub l2_jailing_policy_address: Option<Address>,
     pub l1_emissions_controller_address: Address,
     pub l2_staking_operators_address: Address,
-    pub private_key: String,
+    pub signer: SignerConfig,
+    pub fees: FeeConfig,
+    pub receipt_timeout: Duration,
     pub l1_bridge_value: U256,
     pub lookback_blocks: u64,
     pub tick_interval: Duration,
@@ -104,19 +116,22 @@ impl KeeperConfig {
         let l2_staking_operators_address = args.l2_staking_operators_address;
         let l2_jailing_policy_address = args.l2_jailing_policy_address;
         let disable_jailing = args.disable_jailing;
-        let private_key = args.private_key;
+        let signer = args.keys.signer()?.ok_or_else(|| {
+            anyhow!("no private key given, set PRIVATE_KEY, KEYSTORE_PATH or REMOTE_SIGNER_URL")
+        })?;
         let l2_jailing_policy_address = if disable_jailing {
             None
         } else {
             l2_jailing_policy_address
         };
+        let fees = args.fees.fee_config()?;
+        let receipt_timeout = args.receipts.receipt_timeout()?;
         let l1_bridge_value = args.l1_bridge_value_wei;
         let lookback_blocks = args.lookback_blocks;
         let tick_interval = Duration::from_secs(args.tick_interval_secs);
         let emissions_interval = Duration::from_secs(args.emissions_interval_secs);
 
-        let wallet: PrivateKeySigner = private_key.parse()?;
-        let address = wallet.address();
+        let address = signer.address()?;
         let otel = match (is_otel_disabled(), args.otel_endpoint) {
             (true, _) => {
                 info!("OTEL export is disabled via environment variable");
@@ -141,7 +156,9 @@ impl KeeperConfig {
             l2_jailing_policy_address,
             l1_emissions_controller_address,
             l2_staking_operators_address,
-            private_key,
+            signer,
+            fees,
+            receipt_timeout,
             l1_bridge_value,
             lookback_blocks,
             tick_interval,
@@ -3,9 +3,9 @@ use alloy::{
     network::{Ethereum, EthereumWallet, NetworkWallet},
     primitives::{Address, U256},
     providers::{DynProvider, Provider, ProviderBuilder, WsConnect},
-    signers::local::PrivateKeySigner,
 };
-use blacklight_contract_clients::{HearbeatManager, StakingOperators};
+use blacklight_contract_clients::{FeeConfig, HearbeatManager, SignerConfig, StakingOperators};
+use std::time::Duration;
 
 pub type HeartbeatManagerInstance = HearbeatManager::HearbeatManagerInstance<DynProvider>;
 pub type StakingOperatorsInstance = StakingOperators::StakingOperatorsInstance<DynProvider>;
@@ -17,14 +17,13 @@ pub type ERC20Instance = Erc20::Erc20Instance<DynProvider>;
 
 async fn connect_ws(
     rpc_url: &str,
-    private_key: &str,
+    signer: &SignerConfig,
 ) -> anyhow::Result<(DynProvider, EthereumWallet)> {
     let ws_url = rpc_url
         .replace("http://", "ws://")
         .replace("https://", "wss://");
     let ws = WsConnect::new(ws_url).with_max_retries(u32::MAX);
-    let signer: PrivateKeySigner = private_key.parse::<PrivateKeySigner>()?;
-    let wallet = EthereumWallet::from(signer);
+    let wallet = signer.wallet()?;
 
     let provider: DynProvider = ProviderBuilder::new()
         .wallet(wallet.clone())
@@ -44,6 +43,8 @@ pub struct L2KeeperClient {
     jailing_policy: Option<JailingPolicyInstance>,
     provider: DynProvider,
     wallet: EthereumWallet,
+    fees: FeeConfig,
+    receipt_timeout: Duration,
 }
 
 impl L2KeeperClient {
@@ -52,9 +53,11 @@ impl L2KeeperClient {
         heartbeat_manager_address: Address,
         staking_operators_address: Address,
         jailing_policy_address: Option<Address>,
-        private_key: String,
+        signer: SignerConfig,
+        fees: FeeConfig,
+        receipt_timeout: Duration,
     ) -> anyhow::Result<Self> {
-        let (provider, wallet) = connect_ws(&rpc_url, &private_key).await?;
+        let (provider, wallet) = connect_ws(&rpc_url, &signer).await?;
         let heartbeat_manager =
             HeartbeatManagerInstance::new(heartbeat_manager_address, provider.clone());
         let staking_operators =
@@ -68,6 +71,8 @@ impl L2KeeperClient {
             jailing_policy,
             provider,
             wallet,
+            fees,
+            receipt_timeout,
         })
     }
 
@@ -95,6 +100,16 @@ impl L2KeeperClient {
         self.provider.clone()
     }
 
+    /// Fee strategy and gas buffers of L2 transactions
+    pub fn fees(&self) -> &FeeConfig {
+        &self.fees
+    }
+
+    /// How long to wait for L2 transactions to be mined
+    pub fn receipt_timeout(&self) -> Duration {
+        self.receipt_timeout
+    }
+
     pub fn signer_address(&self) -> Address {
         <EthereumWallet as NetworkWallet<Ethereum>>::default_signer_address(&self.wallet)
     }
@@ -115,9 +130,9 @@ impl L1EmissionsClient {
     pub async fn new(
         rpc_url: String,
         emissions_address: Address,
-        private_key: String,
+        signer: SignerConfig,
     ) -> anyhow::Result<Self> {
-        let (provider, wallet) = connect_ws(&rpc_url, &private_key).await?;
+        let (provider, wallet) = connect_ws(&rpc_url, &signer).await?;
         let emissions = EmissionsControllerInstance::new(emissions_address, provider.clone());
         Ok(Self {
             emissions,
@@ -21,7 +21,7 @@ impl EmissionsSupervisor {
         let l1_client = L1EmissionsClient::new(
             config.l1_rpc_url.clone(),
             config.l1_emissions_controller_address,
-            config.private_key.clone(),
+            config.signer.clone(),
         )
         .await
         .context("Failed to create L1 client")?;
@@ -75,7 +75,10 @@ impl EmissionsSupervisor {
             .value(self.config.l1_bridge_value);
         match call.send().await {
             Ok(pending) => {
-                let receipt = pending.get_receipt().await?;
+                let receipt = pending
+                    .with_timeout(Some(self.config.receipt_timeout))
+                    .get_receipt()
+                    .await?;
                 let tx_hash = receipt.transaction_hash;
                 self.publish_balance_metric().await;
 
@@ -77,10 +77,14 @@ impl RoundEscalator {
                 .client
                 .heartbeat_manager()
                 .escalateOrExpire(heartbeat_key, raw_htx.clone());
+            let call = self.client.fees().apply(call).await?;
 
             match call.send().await {
                 Ok(pending) => {
-                    let receipt = pending.get_receipt().await?;
+                    let receipt = pending
+                        .with_timeout(Some(self.client.receipt_timeout()))
+                        .get_receipt()
+                        .await?;
                     info!(
                         heartbeat_key = ?heartbeat_key,
                         tx_hash = ?receipt.transaction_hash,
@@ -114,10 +118,14 @@ impl RoundEscalator {
                 .client
                 .heartbeat_manager()
                 .escalateOrExpire(heartbeat_key, raw_htx.clone());
+            let call = self.client.fees().apply(call).await?;
 
             match call.send().await {
                 Ok(pending) => {
-                    let receipt = pending.get_receipt().await?;
+                    let receipt = pending
+                        .with_timeout(Some(self.client.receipt_timeout()))
+                        .get_receipt()
+                        .await?;
                     info!(
                         heartbeat_key = ?heartbeat_key,
                         tx_hash = ?receipt.transaction_hash,
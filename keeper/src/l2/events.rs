@@ -6,6 +6,7 @@ use crate::{
 use alloy::{primitives::B256, rpc::types::Log, sol_types::SolEvent};
 use anyhow::Context;
 use blacklight_contract_clients::{
+    FeeConfig,
     HearbeatManager::SlashingCallbackFailed,
     heartbeat_manager::{
         HeartbeatEnqueuedEvent, RewardDistributionAbandonedEvent, RewardsDistributedEvent,
@@ -13,17 +14,27 @@ use blacklight_contract_clients::{
     },
 };
 use futures_util::{Stream, StreamExt};
-use std::{pin::pin, sync::Arc};
+use std::{pin::pin, sync::Arc, time::Duration};
 use tokio::sync::Mutex;
 use tracing::{debug, error, info};
 
 pub(crate) struct EventListener {
     manager: HeartbeatManagerInstance,
+    fees: FeeConfig,
+    receipt_timeout: Duration,
 }
 
 impl EventListener {
-    pub(crate) fn new(manager: HeartbeatManagerInstance) -> Self {
-        Self { manager }
+    pub(crate) fn new(
+        manager: HeartbeatManagerInstance,
+        fees: FeeConfig,
+        receipt_timeout: Duration,
+    ) -> Self {
+        Self {
+            manager,
+            fees,
+            receipt_timeout,
+        }
     }
 
     pub(crate) async fn process_historical_events(
@@ -132,6 +143,8 @@ impl EventListener {
         tokio::spawn(Self::process_slashing_callback_failed(
             slashing_callback_failed,
             self.manager.clone(),
+            self.fees.clone(),
+            self.receipt_timeout,
         ));
         Ok(())
     }
@@ -291,6 +304,8 @@ impl EventListener {
     async fn process_slashing_callback_failed(
         events: impl Stream<Item = SlashingCallbackFailedEvent>,
         manager: HeartbeatManagerInstance,
+        fees: FeeConfig,
+        receipt_timeout: Duration,
     ) {
         let mut events = pin!(events);
         while let Some(event) = events.next().await {
@@ -305,7 +320,9 @@ impl EventListener {
                 round,
                 ..
             } = event;
-            if let Err(e) = Self::retry_slashing(&manager, heartbeat_key, round).await {
+            if let Err(e) =
+                Self::retry_slashing(&manager, &fees, receipt_timeout, heartbeat_key, round).await
+            {
                 error!("Failed to retry slashing for key {heartbeat_key} and round {round}: {e}");
             }
         }
@@ -313,15 +330,19 @@ impl EventListener {
 
     async fn retry_slashing(
         manager: &HeartbeatManagerInstance,
+        fees: &FeeConfig,
+        receipt_timeout: Duration,
         heartbeat_key: B256,
         round: u8,
     ) -> anyhow::Result<()> {
-        let pending = manager
-            .retrySlashing(heartbeat_key, round)
+        let pending = fees
+            .apply(manager.retrySlashing(heartbeat_key, round))
+            .await?
             .send()
             .await
             .context("Failed to retry slashing")?;
         let receipt = pending
+            .with_timeout(Some(receipt_timeout))
             .get_receipt()
             .await
             .context("Failed to get tx receipt")?;
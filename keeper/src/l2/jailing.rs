@@ -31,8 +31,10 @@ impl Jailer {
             "Enforcing jailing"
         );
 
-        if let Err(e) = policy
-            .recordRound(key.heartbeat_key, key.round)
+        let fees = self.client.fees();
+        if let Err(e) = fees
+            .apply(policy.recordRound(key.heartbeat_key, key.round))
+            .await?
             .send()
             .await
         {
@@ -44,13 +46,17 @@ impl Jailer {
             );
         }
 
-        match policy
-            .enforceJailFromMembers(key.heartbeat_key, key.round, members)
+        match fees
+            .apply(policy.enforceJailFromMembers(key.heartbeat_key, key.round, members))
+            .await?
             .send()
             .await
         {
             Ok(pending) => {
-                let receipt = pending.get_receipt().await?;
+                let receipt = pending
+                    .with_timeout(Some(self.client.receipt_timeout()))
+                    .get_receipt()
+                    .await?;
                 info!(
                     heartbeat_key = ?key.heartbeat_key,
                     round = key.round,
@@ -8,15 +8,13 @@ use anyhow::{Context, bail};
 use blacklight_contract_clients::{
     ProtocolConfig::ProtocolConfigInstance,
     common::{errors::decode_any_error, overestimate_gas},
+    heartbeat_manager::{NodeVote, Verdict},
 };
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use tracing::{debug, info, warn};
 
 const MIN_NIL_SYNC_THRESHOLD: u64 = 100;
-const RESPONDED_BIT: u64 = 1 << 2;
-const VERDICT_MASK: u64 = 0x3;
-const WEIGHT_SHIFT: u32 = 3;
 
 #[derive(Clone, Copy)]
 struct TokenContext {
@@ -66,11 +64,15 @@ impl RewardsDistributor {
             let balance = format_units(balance, token.decimals)?;
             let sync_limit = format_units(sync_limit, token.decimals)?;
             info!("Need to sync balance because balance ({balance}) > sync limit ({sync_limit})");
-            let receipt = reward_policy
-                .sync()
+            let receipt = self
+                .client
+                .fees()
+                .apply(reward_policy.sync())
+                .await?
                 .send()
                 .await
                 .context("Failed to sync")?
+                .with_timeout(Some(self.client.receipt_timeout()))
                 .get_receipt()
                 .await?;
             info!(tx_hash = ?receipt.transaction_hash, "Reward policy synced");
@@ -149,10 +151,14 @@ impl RewardsDistributor {
             self.client
                 .heartbeat_manager()
                 .distributeRewards(key.heartbeat_key, key.round, voters);
-        let gas_with_buffer = overestimate_gas(&call).await?;
-        match call.gas(gas_with_buffer).send().await {
+        let fees = self.client.fees();
+        let gas_with_buffer = overestimate_gas(&call, fees, "distributeRewards").await?;
+        match fees.apply(call.gas(gas_with_buffer)).await?.send().await {
             Ok(pending) => {
-                let receipt = pending.get_receipt().await?;
+                let receipt = pending
+                    .with_timeout(Some(self.client.receipt_timeout()))
+                    .get_receipt()
+                    .await?;
                 info!(
                     heartbeat_key = ?key.heartbeat_key,
                     round = key.round,
@@ -286,9 +292,13 @@ impl RewardsDistributor {
             "Reward budget unlocking, syncing policy",
         );
 
-        match reward_policy.sync().send().await {
+        let call = self.client.fees().apply(reward_policy.sync()).await?;
+        match call.send().await {
             Ok(pending) => {
-                let receipt = pending.get_receipt().await?;
+                let receipt = pending
+                    .with_timeout(Some(self.client.receipt_timeout()))
+                    .get_receipt()
+                    .await?;
                 info!(
                     heartbeat_key = ?key.heartbeat_key,
                     round = key.round,
@@ -391,11 +401,9 @@ impl RewardsDistributor {
                 .getVotePacked(key.heartbeat_key, key.round, *member)
                 .call()
                 .await?;
-            let responded = (packed & U256::from(RESPONDED_BIT)) != U256::ZERO;
-            let verdict = u8::try_from(packed & U256::from(VERDICT_MASK))?;
-            if responded && verdict == expected_verdict {
-                let weight = packed >> WEIGHT_SHIFT;
-                total_weight += weight;
+            let vote = NodeVote::decode(packed)?;
+            if vote.responded && vote.verdict.map(Verdict::code) == Some(expected_verdict) {
+                total_weight += vote.weight;
                 voters.push(*member);
             }
         }
@@ -48,7 +48,11 @@ impl L2Supervisor {
         let from_block = latest_block.saturating_sub(config.lookback_blocks);
 
         // Process historic events from current block - lookback until now
-        let event_listener = EventListener::new(self.client.heartbeat_manager().clone());
+        let event_listener = EventListener::new(
+            self.client.heartbeat_manager().clone(),
+            self.client.fees().clone(),
+            self.client.receipt_timeout(),
+        );
         event_listener
             .process_historical_events(from_block, latest_block, &mut *self.state.lock().await)
             .await
@@ -123,7 +123,9 @@ async fn main() -> Result<()> {
             config.l2_heartbeat_manager_address,
             config.l2_staking_operators_address,
             config.l2_jailing_policy_address,
-            config.private_key.clone(),
+            config.signer.clone(),
+            config.fees.clone(),
+            config.receipt_timeout,
         )
         .await?,
     );
@@ -131,7 +133,7 @@ async fn main() -> Result<()> {
         L1EmissionsClient::new(
             config.l1_rpc_url.clone(),
             config.l1_emissions_controller_address,
-            config.private_key.clone(),
+            config.signer.clone(),
         )
         .await?,
     );
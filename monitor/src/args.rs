@@ -3,6 +3,7 @@ use anyhow::Result;
 use clap::Parser;
 
 use chain_args::{ChainArgs, ChainConfig};
+use key_args::KeyArgs;
 use state_file::StateFile;
 use tracing::info;
 
@@ -16,9 +17,8 @@ pub struct CliArgs {
     #[clap(flatten)]
     pub chain_args: ChainArgs,
 
-    /// Private key for contract interactions
-    #[arg(long, env = "PRIVATE_KEY")]
-    pub private_key: Option<String>,
+    #[clap(flatten)]
+    pub keys: KeyArgs,
 
     /// Load all historical HTX events
     #[arg(long, env = "ALL_HTXS")]
@@ -48,12 +48,9 @@ impl MonitorConfig {
         } = ChainConfig::new(cli_args.chain_args, &state_file)?;
 
         // Load private key with priority (monitor uses first Hardhat account)
-        let private_key = cli_args
-            .private_key
-            .or_else(|| state_file.load_value("PRIVATE_KEY"))
-            .unwrap_or_else(|| {
-                "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80".to_string()
-            });
+        let private_key = cli_args.keys.load(&state_file)?.unwrap_or_else(|| {
+            "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80".to_string()
+        });
 
         // Load all_htxs flag with priority
         let all_htxs = cli_args
@@ -1,7 +1,7 @@
 use alloy::primitives::{Address, U256, utils::format_units};
 use anyhow::Result;
 use args::{CliArgs, MonitorConfig};
-use blacklight_contract_clients::{BlacklightClient, ContractConfig};
+use blacklight_contract_clients::{BlacklightClient, ContractConfig, OperatorMetadata};
 use clap::Parser;
 use crossterm::{
     event::{
@@ -23,10 +23,16 @@ use std::collections::{HashMap, HashSet};
 use std::io;
 use std::sync::Arc;
 use std::time::{Duration, SystemTime};
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, Semaphore};
 
 mod args;
 
+/// How long resolved operator metadata is shown before it is fetched again
+const METADATA_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Maximum number of operator metadata documents fetched at once
+const METADATA_FETCH_CONCURRENCY: usize = 8;
+
 /// Convert a byte array to a 0x-prefixed hex string
 fn bytes_to_hex(bytes: &[u8]) -> String {
     format!(
@@ -65,6 +71,13 @@ struct NodeInfo {
     eth_balance: U256,
 }
 
+/// Operator metadata as last resolved
+#[derive(Debug, Clone)]
+struct CachedMetadata {
+    metadata: Option<OperatorMetadata>,
+    fetched: std::time::Instant,
+}
+
 #[derive(Debug, Clone)]
 struct TokenHolder {
     address: Address,
@@ -197,6 +210,9 @@ struct MonitorState {
     validation_is_loading: bool,
     // Queue validation responses received before their request/assignment
     pending_htx_responses: HashMap<String, HashSet<String>>,
+    // Operator metadata, resolved in the background
+    operator_metadata: HashMap<Address, CachedMetadata>,
+    metadata_in_flight: HashSet<Address>,
 }
 
 #[tokio::main]
@@ -402,6 +418,8 @@ async fn main() -> Result<()> {
         validation_last_refresh: std::time::Instant::now(),
         validation_is_loading: false,
         pending_htx_responses: HashMap::new(),
+        operator_metadata: HashMap::new(),
+        metadata_in_flight: HashSet::new(),
     };
 
     run_monitor(client, initial_state).await
@@ -423,6 +441,7 @@ async fn run_monitor(client: BlacklightClient, initial_state: MonitorState) -> R
     // Wrap state in Arc<Mutex> for thread-safe sharing
     let state = Arc::new(Mutex::new(initial_state));
     let client_arc = Arc::new(client);
+    refresh_operator_metadata(client_arc.clone(), state.clone()).await;
 
     // Spawn WebSocket event listeners with reconnection logic
     let state_clone = state.clone();
@@ -650,6 +669,8 @@ async fn run_monitor_loop(
                                     state_guard.eth_balance = eth_balance;
                                     state_guard.token_holders = token_holders;
                                     state_guard.status_message = "Refreshed!".to_string();
+                                    drop(state_guard);
+                                    refresh_operator_metadata(client.clone(), state.clone()).await;
                                 }
                                 (Err(e), _) | (_, Err(e)) => {
                                     let mut state_guard = state.lock().await;
@@ -1042,7 +1063,11 @@ async fn run_monitor_loop(
                                             token_client.approve(staking_addr, amount_wei).await?;
 
                                             // Then stake
-                                            staking_client.stake_to(target_addr, amount_wei).await
+                                            anyhow::Ok(
+                                                staking_client
+                                                    .stake_to(target_addr, amount_wei)
+                                                    .await?,
+                                            )
                                         }
                                         .await;
 
@@ -1173,7 +1198,9 @@ async fn run_monitor_loop(
                                             };
                                             let amount_wei = U256::from((amount_eth * 1e6) as u128);
 
-                                            token_client.mint(target_addr, amount_wei).await
+                                            anyhow::Ok(
+                                                token_client.mint(target_addr, amount_wei).await?,
+                                            )
                                         }
                                         .await;
 
@@ -1775,6 +1802,59 @@ fn render_overview(f: &mut Frame, area: Rect, state: &MonitorState) {
     f.render_widget(activity, chunks[2]);
 }
 
+/// Fetch the metadata an operator registered, if any and it can be resolved
+async fn fetch_operator_metadata(
+    client: &BlacklightClient,
+    operator: Address,
+) -> Option<OperatorMetadata> {
+    let uri = client.staking.metadata_uri(operator).await.ok()?;
+    OperatorMetadata::resolve(&uri).await.ok().flatten()
+}
+
+/// Resolve in the background the metadata of listed operators that isn't cached or is stale,
+/// a few operators at a time, so slow metadata hosts never hold up the UI
+async fn refresh_operator_metadata(client: Arc<BlacklightClient>, state: Arc<Mutex<MonitorState>>) {
+    let operators: Vec<Address> = {
+        let mut state_guard = state.lock().await;
+        let state_guard = &mut *state_guard;
+        let stale: Vec<Address> = state_guard
+            .nodes
+            .iter()
+            .map(|node| node.address)
+            .filter(|address| {
+                !state_guard.metadata_in_flight.contains(address)
+                    && state_guard
+                        .operator_metadata
+                        .get(address)
+                        .is_none_or(|cached| cached.fetched.elapsed() > METADATA_TTL)
+            })
+            .collect();
+        state_guard.metadata_in_flight.extend(stale.iter().copied());
+        stale
+    };
+    let permits = Arc::new(Semaphore::new(METADATA_FETCH_CONCURRENCY));
+    for operator in operators {
+        let client = client.clone();
+        let state = state.clone();
+        let permits = permits.clone();
+        tokio::spawn(async move {
+            let metadata = match permits.acquire().await {
+                Ok(_permit) => fetch_operator_metadata(&client, operator).await,
+                Err(_) => None,
+            };
+            let mut state_guard = state.lock().await;
+            state_guard.metadata_in_flight.remove(&operator);
+            state_guard.operator_metadata.insert(
+                operator,
+                CachedMetadata {
+                    metadata,
+                    fetched: std::time::Instant::now(),
+                },
+            );
+        });
+    }
+}
+
 fn render_nodes(f: &mut Frame, area: Rect, state: &MonitorState) {
     let registered_count = state.nodes.iter().filter(|n| n.is_registered).count();
     let total_count = state.nodes.len();
@@ -1799,7 +1879,7 @@ fn render_nodes(f: &mut Frame, area: Rect, state: &MonitorState) {
                 Color::Red
             };
 
-            let content = vec![
+            let mut content = vec![
                 Span::raw(format!("{}. ", idx + 1)),
                 Span::styled(
                     status,
@@ -1812,6 +1892,25 @@ fn render_nodes(f: &mut Frame, area: Rect, state: &MonitorState) {
                     node_info.address, stake_formatted, eth_balance_formatted
                 )),
             ];
+            if let Some(metadata) = state
+                .operator_metadata
+                .get(&node_info.address)
+                .and_then(|cached| cached.metadata.as_ref())
+            {
+                content.push(Span::raw(" | "));
+                content.push(Span::styled(
+                    metadata.name.clone(),
+                    Style::default().fg(Color::Cyan),
+                ));
+                let details: Vec<&str> = [&metadata.region, &metadata.version, &metadata.contact]
+                    .into_iter()
+                    .flatten()
+                    .map(String::as_str)
+                    .collect();
+                if !details.is_empty() {
+                    content.push(Span::raw(format!(" ({})", details.join(", "))));
+                }
+            }
 
             let style = if Some(idx) == state.selected_node_index {
                 Style::default()
@@ -1,8 +1,11 @@
 use alloy::primitives::Address;
 use anyhow::Result;
 use clap::Parser;
+use std::time::Duration;
 
-use chain_args::{ChainArgs, ChainConfig};
+use blacklight_contract_clients::FeeConfig;
+use chain_args::{ChainArgs, ChainConfig, FeeArgs, ReceiptArgs};
+use key_args::KeyArgs;
 use state_file::StateFile;
 use tracing::info;
 
@@ -26,9 +29,14 @@ pub struct CliArgs {
     #[clap(flatten)]
     pub chain_args: ChainArgs,
 
-    /// Private key for signing transactions
-    #[arg(long, env = "PRIVATE_KEY")]
-    pub private_key: Option<String>,
+    #[clap(flatten)]
+    pub keys: KeyArgs,
+
+    #[clap(flatten)]
+    pub fees: FeeArgs,
+
+    #[clap(flatten)]
+    pub receipts: ReceiptArgs,
 
     /// Path to HTXs JSON file
     #[arg(long, env = "HTXS_PATH")]
@@ -43,6 +51,9 @@ pub struct SimulatorConfig {
     pub staking_contract_address: Address,
     pub token_contract_address: Address,
     pub private_key: String,
+    pub fees: FeeConfig,
+    pub receipt_timeout: Duration,
+    pub max_replacements: u32,
     pub htxs_path: String,
     pub slot_ms: u64,
 }
@@ -59,12 +70,13 @@ impl SimulatorConfig {
         } = ChainConfig::new(cli_args.chain_args, &state_file)?;
 
         // Load private key with priority (different default than node)
-        let private_key = cli_args
-            .private_key
-            .or_else(|| state_file.load_value("PRIVATE_KEY"))
-            .unwrap_or_else(|| {
-                "0x5de4111afa1a4b94908f83103eb1f1706367c2e68ca870fc3fb9a804cdab365a".to_string()
-            });
+        let private_key = cli_args.keys.load(&state_file)?.unwrap_or_else(|| {
+            "0x5de4111afa1a4b94908f83103eb1f1706367c2e68ca870fc3fb9a804cdab365a".to_string()
+        });
+
+        let fees = cli_args.fees.fee_config()?;
+        let receipt_timeout = cli_args.receipts.receipt_timeout()?;
+        let max_replacements = cli_args.receipts.max_tx_replacements;
 
         // Load HTXs path with priority
         let htxs_path = cli_args
@@ -81,6 +93,9 @@ impl SimulatorConfig {
             staking_contract_address,
             token_contract_address,
             private_key,
+            fees,
+            receipt_timeout,
+            max_replacements,
             htxs_path,
             slot_ms: DEFAULT_SLOT_MS,
         })
@@ -2,7 +2,7 @@ use anyhow::Result;
 use args::{CliArgs, SimulatorConfig};
 use blacklight_contract_clients::{
     htx::{Htx, NillionHtx, PhalaHtx},
-    {BlacklightClient, ContractConfig},
+    {BlacklightClient, ContractConfig, TxError},
 };
 use clap::Parser;
 use rand::Rng;
@@ -45,7 +45,9 @@ async fn setup_client(config: &SimulatorConfig) -> Result<BlacklightClient> {
         config.manager_contract_address,
         config.staking_contract_address,
         config.token_contract_address,
-    );
+    )
+    .with_fees(config.fees.clone())
+    .with_receipt_timeout(config.receipt_timeout, config.max_replacements);
 
     let client = BlacklightClient::new(contract_config, config.private_key.clone()).await?;
 
@@ -145,16 +147,17 @@ async fn submit_next_htx(
                 return Ok(());
             }
             Err(e) => {
-                let error_str = e.to_string();
-                // Only retry on on-chain reverts (state race conditions)
-                if error_str.contains("reverted on-chain") {
-                    warn!(slot, attempt, error = %e, "Submission reverted, will retry");
-                    last_error = Some(e);
+                // Only retry transient failures, lost nonces and on-chain reverts from state
+                // races, which the next attempt simulates again before sending. Transactions not
+                // mined in time may still be mined, so they are not sent again.
+                if e.is_retryable() || matches!(e, TxError::Reverted { .. }) {
+                    warn!(slot, attempt, error = %e, "Submission failed, will retry");
+                    last_error = Some(e.into());
                     tokio::time::sleep(Duration::from_millis(RETRY_DELAY_MS)).await;
                     continue;
                 }
-                // For other errors (simulation failures, etc.), fail immediately
-                return Err(e);
+                // For other errors (simulation reverts, etc.), fail immediately
+                return Err(e.into());
             }
         }
     }